@@ -0,0 +1,189 @@
+/* hashes.h - C API of the hashes crate
+ * Generated by hashes::ffi::header(), do not edit.
+ * Regenerate: HASHES_FFI_BLESS=1 cargo test -p hashes-ffi --test header */
+#ifndef HASHES_H
+#define HASHES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Every function return HASHES_OK or one of the errors, new return NULL on failure.
+ * final does not change the context, free it with the free function. */
+#define HASHES_OK 0 /* success */
+#define HASHES_ERR_NULL -1 /* null pointer */
+#define HASHES_ERR_BUFFER -2 /* output buffer too small */
+#define HASHES_ERR_PANIC -3 /* internal error */
+const char *hashes_strerror(int code);
+
+/* md5 */
+#define HASHES_MD5_SIZE 16
+typedef struct hashes_md5_ctx hashes_md5_ctx;
+hashes_md5_ctx *hashes_md5_new(void);
+int hashes_md5_update(hashes_md5_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_md5_final(const hashes_md5_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_md5_free(hashes_md5_ctx *ctx);
+int hashes_md5(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* sha1 */
+#define HASHES_SHA1_SIZE 20
+typedef struct hashes_sha1_ctx hashes_sha1_ctx;
+hashes_sha1_ctx *hashes_sha1_new(void);
+int hashes_sha1_update(hashes_sha1_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_sha1_final(const hashes_sha1_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_sha1_free(hashes_sha1_ctx *ctx);
+int hashes_sha1(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* sha1dc */
+#define HASHES_SHA1DC_SIZE 20
+typedef struct hashes_sha1dc_ctx hashes_sha1dc_ctx;
+hashes_sha1dc_ctx *hashes_sha1dc_new(void);
+int hashes_sha1dc_update(hashes_sha1dc_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_sha1dc_final(const hashes_sha1dc_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_sha1dc_free(hashes_sha1dc_ctx *ctx);
+int hashes_sha1dc(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+int hashes_sha1dc_collision_detected(const hashes_sha1dc_ctx *ctx, int *detected);
+
+/* adler32 */
+#define HASHES_ADLER32_SIZE 4
+typedef struct hashes_adler32_ctx hashes_adler32_ctx;
+hashes_adler32_ctx *hashes_adler32_new(void);
+int hashes_adler32_update(hashes_adler32_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_adler32_final(const hashes_adler32_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_adler32_free(hashes_adler32_ctx *ctx);
+int hashes_adler32(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* fletcher16 */
+#define HASHES_FLETCHER16_SIZE 2
+typedef struct hashes_fletcher16_ctx hashes_fletcher16_ctx;
+hashes_fletcher16_ctx *hashes_fletcher16_new(void);
+int hashes_fletcher16_update(hashes_fletcher16_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_fletcher16_final(const hashes_fletcher16_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_fletcher16_free(hashes_fletcher16_ctx *ctx);
+int hashes_fletcher16(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* fletcher32 */
+#define HASHES_FLETCHER32_SIZE 4
+typedef struct hashes_fletcher32_ctx hashes_fletcher32_ctx;
+hashes_fletcher32_ctx *hashes_fletcher32_new(void);
+int hashes_fletcher32_update(hashes_fletcher32_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_fletcher32_final(const hashes_fletcher32_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_fletcher32_free(hashes_fletcher32_ctx *ctx);
+int hashes_fletcher32(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* fletcher64 */
+#define HASHES_FLETCHER64_SIZE 8
+typedef struct hashes_fletcher64_ctx hashes_fletcher64_ctx;
+hashes_fletcher64_ctx *hashes_fletcher64_new(void);
+int hashes_fletcher64_update(hashes_fletcher64_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_fletcher64_final(const hashes_fletcher64_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_fletcher64_free(hashes_fletcher64_ctx *ctx);
+int hashes_fletcher64(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* fnv1a32 */
+#define HASHES_FNV1A32_SIZE 4
+typedef struct hashes_fnv1a32_ctx hashes_fnv1a32_ctx;
+hashes_fnv1a32_ctx *hashes_fnv1a32_new(void);
+int hashes_fnv1a32_update(hashes_fnv1a32_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_fnv1a32_final(const hashes_fnv1a32_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_fnv1a32_free(hashes_fnv1a32_ctx *ctx);
+int hashes_fnv1a32(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* fnv1a64 */
+#define HASHES_FNV1A64_SIZE 8
+typedef struct hashes_fnv1a64_ctx hashes_fnv1a64_ctx;
+hashes_fnv1a64_ctx *hashes_fnv1a64_new(void);
+int hashes_fnv1a64_update(hashes_fnv1a64_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_fnv1a64_final(const hashes_fnv1a64_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_fnv1a64_free(hashes_fnv1a64_ctx *ctx);
+int hashes_fnv1a64(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* murmur3_32 */
+#define HASHES_MURMUR3_32_SIZE 4
+typedef struct hashes_murmur3_32_ctx hashes_murmur3_32_ctx;
+hashes_murmur3_32_ctx *hashes_murmur3_32_new(void);
+int hashes_murmur3_32_update(hashes_murmur3_32_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_murmur3_32_final(const hashes_murmur3_32_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_murmur3_32_free(hashes_murmur3_32_ctx *ctx);
+int hashes_murmur3_32(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* murmur3_128 */
+#define HASHES_MURMUR3_128_SIZE 16
+typedef struct hashes_murmur3_128_ctx hashes_murmur3_128_ctx;
+hashes_murmur3_128_ctx *hashes_murmur3_128_new(void);
+int hashes_murmur3_128_update(hashes_murmur3_128_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_murmur3_128_final(const hashes_murmur3_128_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_murmur3_128_free(hashes_murmur3_128_ctx *ctx);
+int hashes_murmur3_128(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* xxh64 */
+#define HASHES_XXH64_SIZE 8
+typedef struct hashes_xxh64_ctx hashes_xxh64_ctx;
+hashes_xxh64_ctx *hashes_xxh64_new(void);
+int hashes_xxh64_update(hashes_xxh64_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_xxh64_final(const hashes_xxh64_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_xxh64_free(hashes_xxh64_ctx *ctx);
+int hashes_xxh64(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* xxh3_64 */
+#define HASHES_XXH3_64_SIZE 8
+typedef struct hashes_xxh3_64_ctx hashes_xxh3_64_ctx;
+hashes_xxh3_64_ctx *hashes_xxh3_64_new(void);
+int hashes_xxh3_64_update(hashes_xxh3_64_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_xxh3_64_final(const hashes_xxh3_64_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_xxh3_64_free(hashes_xxh3_64_ctx *ctx);
+int hashes_xxh3_64(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* xxh3_128 */
+#define HASHES_XXH3_128_SIZE 16
+typedef struct hashes_xxh3_128_ctx hashes_xxh3_128_ctx;
+hashes_xxh3_128_ctx *hashes_xxh3_128_new(void);
+int hashes_xxh3_128_update(hashes_xxh3_128_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_xxh3_128_final(const hashes_xxh3_128_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_xxh3_128_free(hashes_xxh3_128_ctx *ctx);
+int hashes_xxh3_128(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* siphash24 */
+#define HASHES_SIPHASH24_SIZE 8
+typedef struct hashes_siphash24_ctx hashes_siphash24_ctx;
+hashes_siphash24_ctx *hashes_siphash24_new(const uint8_t key[16]);
+int hashes_siphash24_update(hashes_siphash24_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_siphash24_final(const hashes_siphash24_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_siphash24_free(hashes_siphash24_ctx *ctx);
+int hashes_siphash24(const uint8_t key[16], const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* siphash13 */
+#define HASHES_SIPHASH13_SIZE 8
+typedef struct hashes_siphash13_ctx hashes_siphash13_ctx;
+hashes_siphash13_ctx *hashes_siphash13_new(const uint8_t key[16]);
+int hashes_siphash13_update(hashes_siphash13_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_siphash13_final(const hashes_siphash13_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_siphash13_free(hashes_siphash13_ctx *ctx);
+int hashes_siphash13(const uint8_t key[16], const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* siphash128_24 */
+#define HASHES_SIPHASH128_24_SIZE 16
+typedef struct hashes_siphash128_24_ctx hashes_siphash128_24_ctx;
+hashes_siphash128_24_ctx *hashes_siphash128_24_new(const uint8_t key[16]);
+int hashes_siphash128_24_update(hashes_siphash128_24_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_siphash128_24_final(const hashes_siphash128_24_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_siphash128_24_free(hashes_siphash128_24_ctx *ctx);
+int hashes_siphash128_24(const uint8_t key[16], const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+/* siphash128_13 */
+#define HASHES_SIPHASH128_13_SIZE 16
+typedef struct hashes_siphash128_13_ctx hashes_siphash128_13_ctx;
+hashes_siphash128_13_ctx *hashes_siphash128_13_new(const uint8_t key[16]);
+int hashes_siphash128_13_update(hashes_siphash128_13_ctx *ctx, const uint8_t *data, size_t len);
+int hashes_siphash128_13_final(const hashes_siphash128_13_ctx *ctx, uint8_t *out, size_t out_len);
+void hashes_siphash128_13_free(hashes_siphash128_13_ctx *ctx);
+int hashes_siphash128_13(const uint8_t key[16], const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HASHES_H */
@@ -0,0 +1,3 @@
+// The C ABI is defined in hashes::ffi, this crate only link it as libhashes_ffi.so and libhashes_ffi.a
+// The #[no_mangle] functions of the dependency are exported by the cdylib, the re-export is for Rust users.
+pub use hashes::ffi::*;
@@ -0,0 +1,60 @@
+extern crate hashes_ffi;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// The C test is compiled against include/hashes.h and linked with the shared library,
+// which cargo built next to this test (target/<profile>/deps). CC choose the compiler, cc by default.
+// A machine without a C compiler skip the test with a message, like the tests without their data files.
+
+fn library_dir() -> PathBuf {
+    std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+#[test]
+fn c_test() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib = library_dir();
+    assert!(
+        lib.join("libhashes_ffi.so").exists() || lib.join("libhashes_ffi.dylib").exists(),
+        "no shared library in {}",
+        lib.display()
+    );
+    let exe = lib.join("hashes_ffi_c_test");
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    let compile = Command::new(&cc)
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-I"])
+        .arg(dir.join("include"))
+        .arg(dir.join("tests/c/test.c"))
+        .arg("-L")
+        .arg(&lib)
+        .arg(format!("-Wl,-rpath,{}", lib.display()))
+        .arg("-lhashes_ffi")
+        .arg("-o")
+        .arg(&exe)
+        .output();
+    let compile = match compile {
+        Ok(output) => output,
+        Err(e) => {
+            println!("skipped: can not run {}: {}", cc, e);
+            return;
+        }
+    };
+    assert!(
+        compile.status.success(),
+        "{} failed\n{}",
+        cc,
+        String::from_utf8_lossy(&compile.stderr)
+    );
+
+    let run = Command::new(&exe).output().unwrap();
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    println!("{}", stdout);
+    assert!(run.status.success(), "C test failed\n{}", stdout);
+    assert_eq!("0 failures", stdout.trim_end().lines().last().unwrap());
+}
@@ -0,0 +1,152 @@
+/* C test of the hashes C API, compiled and run by tests/c.rs
+ * Every algorithm hash "abc" with the one shot function and with a context fed in pieces,
+ * a longer input is hashed both ways too, and the errors are checked.
+ * It print one line for each failure and exit with 1 if there was any. */
+#include <stdio.h>
+#include <string.h>
+
+#include "hashes.h"
+
+static int failures = 0;
+
+static void hex(const uint8_t *bytes, size_t len, char *out) {
+    for (size_t i = 0; i < len; i++) {
+        sprintf(out + 2 * i, "%02x", bytes[i]);
+    }
+    out[2 * len] = '\0';
+}
+
+static void check_hex(const char *name, const char *what, const uint8_t *bytes, size_t len,
+                      const char *expected) {
+    char text[2 * 64 + 1];
+    hex(bytes, len, text);
+    if (strcmp(text, expected) != 0) {
+        printf("%s %s: got %s, expected %s\n", name, what, text, expected);
+        failures++;
+    }
+}
+
+static void check_code(const char *name, const char *what, int code, int expected) {
+    if (code != expected) {
+        printf("%s %s: got %d (%s), expected %d\n", name, what, code, hashes_strerror(code),
+               expected);
+        failures++;
+    }
+}
+
+/* CHECK run every function of one algorithm, long is 1000 bytes hashed in 7 byte pieces
+ * NEW and ONESHOT call new and the one shot, they are defined again with the key for siphash */
+#define NEW(name) hashes_##name##_new()
+#define ONESHOT(name, ...) hashes_##name(__VA_ARGS__)
+#define CHECK(name, SIZE, abc)                                                                  \
+    do {                                                                                        \
+        uint8_t out[SIZE], again[SIZE], whole[SIZE];                                            \
+        hashes_##name##_ctx *ctx = NEW(name);                                                   \
+        if (ctx == NULL) {                                                                      \
+            printf(#name " new: NULL\n");                                                       \
+            failures++;                                                                         \
+            break;                                                                              \
+        }                                                                                       \
+        check_code(#name, "one shot", ONESHOT(name, (const uint8_t *)"abc", 3, out, SIZE),      \
+                   HASHES_OK);                                                                  \
+        check_hex(#name, "one shot", out, SIZE, abc);                                           \
+        check_code(#name, "update", hashes_##name##_update(ctx, (const uint8_t *)"a", 1),       \
+                   HASHES_OK);                                                                  \
+        check_code(#name, "update", hashes_##name##_update(ctx, NULL, 0), HASHES_OK);           \
+        check_code(#name, "update", hashes_##name##_update(ctx, (const uint8_t *)"bc", 2),      \
+                   HASHES_OK);                                                                  \
+        check_code(#name, "final", hashes_##name##_final(ctx, out, SIZE), HASHES_OK);           \
+        check_hex(#name, "context", out, SIZE, abc);                                            \
+        /* final does not change the context */                                                 \
+        check_code(#name, "final", hashes_##name##_final(ctx, again, sizeof(again)), HASHES_OK); \
+        check_hex(#name, "final again", again, SIZE, abc);                                      \
+        check_code(#name, "small buffer", hashes_##name##_final(ctx, out, SIZE - 1),            \
+                   HASHES_ERR_BUFFER);                                                          \
+        check_code(#name, "NULL out", hashes_##name##_final(ctx, NULL, SIZE), HASHES_ERR_NULL); \
+        check_code(#name, "NULL context", hashes_##name##_update(NULL, long_input, 1),          \
+                   HASHES_ERR_NULL);                                                            \
+        hashes_##name##_free(ctx);                                                              \
+        hashes_##name##_free(NULL);                                                             \
+                                                                                                \
+        ctx = NEW(name);                                                                        \
+        for (size_t i = 0; i < sizeof(long_input); i += 7) {                                    \
+            size_t len = sizeof(long_input) - i < 7 ? sizeof(long_input) - i : 7;              \
+            hashes_##name##_update(ctx, long_input + i, len);                                   \
+        }                                                                                       \
+        hashes_##name##_final(ctx, out, SIZE);                                                  \
+        hashes_##name##_free(ctx);                                                              \
+        ONESHOT(name, long_input, sizeof(long_input), whole, SIZE);                             \
+        if (memcmp(out, whole, SIZE) != 0) {                                                    \
+            printf(#name " long: the context and the one shot differ\n");                       \
+            failures++;                                                                         \
+        }                                                                                       \
+    } while (0)
+
+int main(void) {
+    uint8_t long_input[1000];
+    for (size_t i = 0; i < sizeof(long_input); i++) {
+        long_input[i] = (uint8_t)(i * 31 + 7);
+    }
+
+    CHECK(md5, HASHES_MD5_SIZE, "900150983cd24fb0d6963f7d28e17f72");
+    CHECK(sha1, HASHES_SHA1_SIZE, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    CHECK(sha1dc, HASHES_SHA1DC_SIZE, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    CHECK(adler32, HASHES_ADLER32_SIZE, "024d0127");
+    CHECK(fletcher16, HASHES_FLETCHER16_SIZE, "4c27");
+    CHECK(fletcher32, HASHES_FLETCHER32_SIZE, "c52562c4");
+    CHECK(fletcher64, HASHES_FLETCHER64_SIZE, "0063626100636261");
+    CHECK(fnv1a32, HASHES_FNV1A32_SIZE, "1a47e90b");
+    CHECK(fnv1a64, HASHES_FNV1A64_SIZE, "e71fa2190541574b");
+    CHECK(murmur3_32, HASHES_MURMUR3_32_SIZE, "b3dd93fa");
+    CHECK(murmur3_128, HASHES_MURMUR3_128_SIZE, "3ba2744126ca2d52b4963f3f3fad7867");
+    CHECK(xxh64, HASHES_XXH64_SIZE, "44bc2cf5ad770999");
+    CHECK(xxh3_64, HASHES_XXH3_64_SIZE, "78af5f94892f3950");
+    CHECK(xxh3_128, HASHES_XXH3_128_SIZE, "06b05ab6733a618578af5f94892f3950");
+    /* the siphash functions take the key 00 01 .. 0f */
+    uint8_t key[16];
+    for (int i = 0; i < 16; i++) {
+        key[i] = (uint8_t)i;
+    }
+#undef NEW
+#undef ONESHOT
+#define NEW(name) hashes_##name##_new(key)
+#define ONESHOT(name, ...) hashes_##name(key, __VA_ARGS__)
+    CHECK(siphash24, HASHES_SIPHASH24_SIZE, "a50720aa53fabc5d");
+    CHECK(siphash13, HASHES_SIPHASH13_SIZE, "eb4681afe824ce6f");
+    CHECK(siphash128_24, HASHES_SIPHASH128_24_SIZE, "bb96c3a813c9903e3325ef616045abb8");
+    CHECK(siphash128_13, HASHES_SIPHASH128_13_SIZE, "43753f1546fa1ee8b2d4d501495fd601");
+
+    /* SipHash-2-4 of the empty message, the first vector of the paper */
+    uint8_t sip[HASHES_SIPHASH24_SIZE];
+    check_code("siphash24", "empty", hashes_siphash24(key, NULL, 0, sip, sizeof(sip)), HASHES_OK);
+    check_hex("siphash24", "empty", sip, sizeof(sip), "310e0edd47db6f72");
+    check_code("siphash24", "NULL key", hashes_siphash24(NULL, NULL, 0, sip, sizeof(sip)),
+               HASHES_ERR_NULL);
+    if (hashes_siphash24_new(NULL) != NULL) {
+        printf("siphash24 new: NULL key did not return NULL\n");
+        failures++;
+    }
+
+    hashes_sha1dc_ctx *dc = hashes_sha1dc_new();
+    hashes_sha1dc_update(dc, long_input, sizeof(long_input));
+    int detected = -1;
+    check_code("sha1dc", "collision detected", hashes_sha1dc_collision_detected(dc, &detected),
+               HASHES_OK);
+    check_code("sha1dc", "detected", detected, 0);
+    check_code("sha1dc", "NULL detected", hashes_sha1dc_collision_detected(dc, NULL),
+               HASHES_ERR_NULL);
+    check_code("sha1dc", "NULL context", hashes_sha1dc_collision_detected(NULL, &detected),
+               HASHES_ERR_NULL);
+    hashes_sha1dc_free(dc);
+
+    uint8_t out[HASHES_MD5_SIZE];
+    check_code("md5", "NULL data", hashes_md5(NULL, 1, out, sizeof(out)), HASHES_ERR_NULL);
+    if (strcmp(hashes_strerror(HASHES_ERR_BUFFER), "output buffer too small") != 0 ||
+        strcmp(hashes_strerror(12345), "unknown error") != 0) {
+        printf("strerror: wrong message\n");
+        failures++;
+    }
+
+    printf("%d failures\n", failures);
+    return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,20 @@
+extern crate hashes_ffi;
+
+use std::path::Path;
+
+// include/hashes.h must be the output of hashes::ffi::header()
+// Run with HASHES_FFI_BLESS=1 to write the header after the C API was changed.
+#[test]
+fn header_is_generated() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/hashes.h");
+    let header = hashes_ffi::header();
+    if std::env::var_os("HASHES_FFI_BLESS").is_some() {
+        std::fs::write(&path, &header).unwrap();
+    }
+    let file = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        file == header,
+        "{} is out of date, run HASHES_FFI_BLESS=1 cargo test -p hashes-ffi --test header",
+        path.display()
+    );
+}
@@ -0,0 +1,205 @@
+use core::fmt::Display;
+
+// 65521 is the largest prime number smaller than 65536
+const BASE: u32 = 65521;
+// NMAX is the largest n such that 255n(n+1)/2 + (n+1)(BASE-1) <= 2^32-1
+// We can add up to NMAX bytes before the modulo has to be taken, without overflowing u32.
+const NMAX: usize = 5552;
+
+#[derive(Clone, Copy)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+// Implement Display trait to Adler32 struct
+impl Display for Adler32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        // If not set to 08x, the leading 0 will disappear
+        write!(f, "{:08x}", self.checksum())
+    }
+}
+
+// Adler32Digest calculate adler-32 checksum
+pub trait Adler32Digest {
+    // type related to trait
+    type Output;
+
+    // const for 32bit output
+    const HASH_SIZE: usize = 4;
+
+    fn new() -> Self;
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self) -> Self::Output;
+}
+
+// Implement Adler32Digest trait to Adler32 struct
+impl Adler32Digest for Adler32 {
+    type Output = Self;
+
+    // new method return the initialized Adler32 struct
+    fn new() -> Self {
+        // A starts with 1 and B starts with 0, so the checksum of an empty input is 0x00000001
+        Self { a: 1, b: 0 }
+    }
+
+    // update method add input message
+    // Unlike sha1 and md5, adler-32 does not need the whole message, so the sums are updated in place.
+    fn update(&mut self, input: &[u8]) {
+        // Taking the modulo for each byte is slow, so it is deferred to every NMAX bytes.
+        for chunk in input.chunks(NMAX) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= BASE;
+            self.b %= BASE;
+        }
+    }
+
+    // finalize method return the Adler32 struct as it is
+    fn finalize(self) -> Self::Output {
+        self
+    }
+}
+
+impl Adler32 {
+    // from_checksum method restore the Adler32 struct from a 32bit checksum
+    // This is useful to continue the checksum of a zlib stream
+    pub fn from_checksum(checksum: u32) -> Self {
+        Self {
+            a: checksum & 0xffff,
+            b: checksum >> 16,
+        }
+    }
+
+    // checksum method return the 32bit checksum, B is upper 16bit and A is lower 16bit
+    pub fn checksum(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+
+    // roll method slide a window of window_len bytes by one byte
+    // old is the byte leaving the window and new is the byte entering the window.
+    // A = A - old + new
+    // B = B - window_len * old + A - 1
+    // The -1 comes from the initial value 1 of A, which is counted window_len times in B.
+    pub fn roll(&mut self, window_len: usize, old: u8, new: u8) {
+        let n = (window_len % BASE as usize) as u32;
+        self.a = (self.a + BASE - old as u32 + new as u32) % BASE;
+        self.b = (self.b + BASE - (n * old as u32) % BASE + self.a + BASE - 1) % BASE;
+    }
+
+    // combine method calculate the checksum of A || B from the checksum of A, the checksum of B and the length of B
+    // This is the same calculation as adler32_combine in zlib
+    pub fn combine(a: u32, b: u32, len_b: u64) -> u32 {
+        let rem = (len_b % BASE as u64) as u32;
+        let a1 = a & 0xffff;
+        let b1 = a >> 16;
+        let a2 = b & 0xffff;
+        let b2 = b >> 16;
+
+        // A of A || B is A1 + A2 - 1, because both A1 and A2 include the initial value 1
+        let sum_a = (a1 + a2 + BASE - 1) % BASE;
+        // B of A || B is B1 + B2 + len_b * A1 - len_b, because A1 is added to B for every byte of B
+        let sum_b = ((rem as u64 * a1 as u64) % BASE as u64) as u32;
+        let sum_b = (sum_b + b1 + b2 + BASE - rem) % BASE;
+
+        (sum_b << 16) | sum_a
+    }
+}
+
+// Implement the common Digest trait to Adler32 struct
+// The checksum is output in big endian, the same byte order as zlib stores it
+impl crate::Digest for Adler32 {
+    type Output = [u8; 4];
+
+    const OUTPUT_SIZE: usize = 4;
+
+    fn new() -> Self {
+        <Self as Adler32Digest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Adler32Digest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        self.checksum().to_be_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adler32::Adler32Digest;
+
+    #[test]
+    fn test_1() {
+        let message: &[u8] = "Wikipedia".as_bytes();
+        let mut hashtest = crate::adler32::Adler32::new();
+        hashtest.update(message);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("11e60398", result);
+    }
+
+    #[test]
+    fn test_2() {
+        let message: &[u8] = "".as_bytes();
+        let mut hashtest = crate::adler32::Adler32::new();
+        hashtest.update(message);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("00000001", result);
+    }
+
+    #[test]
+    fn test_3() {
+        // more than NMAX bytes of 0xff to check the deferred modulo
+        let input = [0xffu8; 100_000];
+        let mut hashtest = crate::adler32::Adler32::new();
+        hashtest.update(&input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("149a302c", result);
+    }
+
+    #[test]
+    fn test_roll() {
+        let input = b"The quick brown fox jumps over the lazy dog";
+        let window = 16;
+        let mut rolling = crate::adler32::Adler32::new();
+        rolling.update(&input[..window]);
+        for i in window..input.len() {
+            rolling.roll(window, input[i - window], input[i]);
+
+            let mut hashtest = crate::adler32::Adler32::new();
+            hashtest.update(&input[i + 1 - window..=i]);
+            assert_eq!(hashtest.checksum(), rolling.checksum());
+        }
+    }
+
+    #[test]
+    fn test_combine() {
+        let input = b"The quick brown fox jumps over the lazy dog";
+        let mut whole = crate::adler32::Adler32::new();
+        whole.update(input);
+        for split in 0..=input.len() {
+            let mut a = crate::adler32::Adler32::new();
+            a.update(&input[..split]);
+            let mut b = crate::adler32::Adler32::new();
+            b.update(&input[split..]);
+            let combined = crate::adler32::Adler32::combine(
+                a.checksum(),
+                b.checksum(),
+                (input.len() - split) as u64,
+            );
+            assert_eq!(whole.checksum(), combined);
+        }
+    }
+}
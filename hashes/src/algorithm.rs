@@ -0,0 +1,220 @@
+use crate::Digest;
+use std::fmt::Display;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+// Algorithm select a digest at runtime, for command line tools and checksum files
+// Keyed hashes (siphash) are not listed, because a checksum with a fixed key is not useful.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Adler32,
+    Fletcher16,
+    Fletcher32,
+    Fletcher64,
+    Fnv1a32,
+    Fnv1a64,
+    Murmur3x86_32,
+    Murmur3x64_128,
+    Xxh64,
+    Xxh3_64,
+    Xxh3_128,
+}
+
+impl Algorithm {
+    // ALL list every algorithm in the order of the enum
+    pub const ALL: [Algorithm; 13] = [
+        Algorithm::Md5,
+        Algorithm::Sha1,
+        Algorithm::Adler32,
+        Algorithm::Fletcher16,
+        Algorithm::Fletcher32,
+        Algorithm::Fletcher64,
+        Algorithm::Fnv1a32,
+        Algorithm::Fnv1a64,
+        Algorithm::Murmur3x86_32,
+        Algorithm::Murmur3x64_128,
+        Algorithm::Xxh64,
+        Algorithm::Xxh3_64,
+        Algorithm::Xxh3_128,
+    ];
+
+    // name method return the lowercase name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "md5",
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Adler32 => "adler32",
+            Algorithm::Fletcher16 => "fletcher16",
+            Algorithm::Fletcher32 => "fletcher32",
+            Algorithm::Fletcher64 => "fletcher64",
+            Algorithm::Fnv1a32 => "fnv1a32",
+            Algorithm::Fnv1a64 => "fnv1a64",
+            Algorithm::Murmur3x86_32 => "murmur3-32",
+            Algorithm::Murmur3x64_128 => "murmur3-128",
+            Algorithm::Xxh64 => "xxh64",
+            Algorithm::Xxh3_64 => "xxh3",
+            Algorithm::Xxh3_128 => "xxh128",
+        }
+    }
+
+    // tag method return the name used in BSD style checksum lines, "MD5 (file) = ..."
+    // MD5 and SHA1 are the names of coreutils --tag, the others are just uppercase.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Adler32 => "ADLER32",
+            Algorithm::Fletcher16 => "FLETCHER16",
+            Algorithm::Fletcher32 => "FLETCHER32",
+            Algorithm::Fletcher64 => "FLETCHER64",
+            Algorithm::Fnv1a32 => "FNV1A32",
+            Algorithm::Fnv1a64 => "FNV1A64",
+            Algorithm::Murmur3x86_32 => "MURMUR3-32",
+            Algorithm::Murmur3x64_128 => "MURMUR3-128",
+            Algorithm::Xxh64 => "XXH64",
+            Algorithm::Xxh3_64 => "XXH3",
+            Algorithm::Xxh3_128 => "XXH128",
+        }
+    }
+
+    // output_size method return the digest size in bytes, without making a digest
+    pub fn output_size(&self) -> usize {
+        match self {
+            Algorithm::Md5 => <crate::md5::MD5 as Digest>::OUTPUT_SIZE,
+            Algorithm::Sha1 => <crate::sha1::Sha1 as Digest>::OUTPUT_SIZE,
+            Algorithm::Adler32 => <crate::adler32::Adler32 as Digest>::OUTPUT_SIZE,
+            Algorithm::Fletcher16 => <crate::fletcher::Fletcher16 as Digest>::OUTPUT_SIZE,
+            Algorithm::Fletcher32 => <crate::fletcher::Fletcher32 as Digest>::OUTPUT_SIZE,
+            Algorithm::Fletcher64 => <crate::fletcher::Fletcher64 as Digest>::OUTPUT_SIZE,
+            Algorithm::Fnv1a32 => <crate::fast::fnv::Fnv1a32 as Digest>::OUTPUT_SIZE,
+            Algorithm::Fnv1a64 => <crate::fast::fnv::Fnv1a64 as Digest>::OUTPUT_SIZE,
+            Algorithm::Murmur3x86_32 => {
+                <crate::fast::murmur3::Murmur3x86_32 as Digest>::OUTPUT_SIZE
+            }
+            Algorithm::Murmur3x64_128 => {
+                <crate::fast::murmur3::Murmur3x64_128 as Digest>::OUTPUT_SIZE
+            }
+            Algorithm::Xxh64 => <crate::fast::xxhash::Xxh64 as Digest>::OUTPUT_SIZE,
+            Algorithm::Xxh3_64 => <crate::fast::xxhash::Xxh3_64 as Digest>::OUTPUT_SIZE,
+            Algorithm::Xxh3_128 => <crate::fast::xxhash::Xxh3_128 as Digest>::OUTPUT_SIZE,
+        }
+    }
+
+    // new_digest method return a new boxed digest of this algorithm
+    pub fn new_digest(&self) -> Box<dyn DynDigest + Send> {
+        match self {
+            Algorithm::Md5 => Box::new(<crate::md5::MD5 as Digest>::new()),
+            Algorithm::Sha1 => Box::new(<crate::sha1::Sha1 as Digest>::new()),
+            Algorithm::Adler32 => Box::new(<crate::adler32::Adler32 as Digest>::new()),
+            Algorithm::Fletcher16 => Box::new(<crate::fletcher::Fletcher16 as Digest>::new()),
+            Algorithm::Fletcher32 => Box::new(<crate::fletcher::Fletcher32 as Digest>::new()),
+            Algorithm::Fletcher64 => Box::new(<crate::fletcher::Fletcher64 as Digest>::new()),
+            Algorithm::Fnv1a32 => Box::new(<crate::fast::fnv::Fnv1a32 as Digest>::new()),
+            Algorithm::Fnv1a64 => Box::new(<crate::fast::fnv::Fnv1a64 as Digest>::new()),
+            Algorithm::Murmur3x86_32 => {
+                Box::new(<crate::fast::murmur3::Murmur3x86_32 as Digest>::new())
+            }
+            Algorithm::Murmur3x64_128 => {
+                Box::new(<crate::fast::murmur3::Murmur3x64_128 as Digest>::new())
+            }
+            Algorithm::Xxh64 => Box::new(<crate::fast::xxhash::Xxh64 as Digest>::new()),
+            Algorithm::Xxh3_64 => Box::new(<crate::fast::xxhash::Xxh3_64 as Digest>::new()),
+            Algorithm::Xxh3_128 => Box::new(<crate::fast::xxhash::Xxh3_128 as Digest>::new()),
+        }
+    }
+
+    // digest method hash the whole input at once
+    pub fn digest(&self, input: &[u8]) -> Vec<u8> {
+        let mut digest = self.new_digest();
+        digest.update(input);
+        digest.finalize_boxed()
+    }
+
+    // digest_reader method hash everything read from the reader until EOF
+    pub fn digest_reader<R: Read>(&self, reader: &mut R) -> io::Result<Vec<u8>> {
+        let mut digest = self.new_digest();
+        let mut buf = [0u8; 64 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            digest.update(&buf[..n]);
+        }
+        Ok(digest.finalize_boxed())
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    // from_str accept the name case insensitively, so "MD5" and "SHA1" of checksum files also work
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Algorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown algorithm: {}", s))
+    }
+}
+
+// DynDigest is the object safe version of Digest
+// Digest::new and Digest::finalize need Self: Sized, so Box<dyn Digest> is not possible.
+pub trait DynDigest {
+    fn update(&mut self, input: &[u8]);
+    fn finalize_boxed(self: Box<Self>) -> Vec<u8>;
+    fn output_size(&self) -> usize;
+}
+
+impl<D: Digest> DynDigest for D {
+    fn update(&mut self, input: &[u8]) {
+        Digest::update(self, input);
+    }
+
+    fn finalize_boxed(self: Box<Self>) -> Vec<u8> {
+        Digest::finalize(*self).as_ref().to_vec()
+    }
+
+    fn output_size(&self) -> usize {
+        D::OUTPUT_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithm::Algorithm;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Ok(Algorithm::Md5), "md5".parse());
+        assert_eq!(Ok(Algorithm::Sha1), "SHA1".parse());
+        assert!("sha3".parse::<Algorithm>().is_err());
+        for algorithm in Algorithm::ALL {
+            assert_eq!(Ok(algorithm), algorithm.name().parse());
+        }
+    }
+
+    #[test]
+    fn test_digest() {
+        let result = crate::hex::encode(&Algorithm::Sha1.digest(b"Hello"));
+        assert_eq!("f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0", result);
+
+        let result = crate::hex::encode(&Algorithm::Md5.digest_reader(&mut &b"Hello"[..]).unwrap());
+        assert_eq!("8b1a9953c4611296a827abf8c47804d7", result);
+
+        assert_eq!(16, Algorithm::Md5.output_size());
+        assert_eq!(20, Algorithm::Sha1.output_size());
+        for algorithm in Algorithm::ALL {
+            assert_eq!(algorithm.digest(b"").len(), algorithm.output_size());
+        }
+    }
+}
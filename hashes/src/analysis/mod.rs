@@ -0,0 +1,107 @@
+// Statistical tests of the hashes of this crate
+// They do not prove anything about security, they show how far an output is from random bits,
+// to compare the hashes with each other and to catch a broken round function.
+pub mod quality;
+pub mod sp800_22;
+
+// ln_gamma function return ln(Gamma(x)) for x > 0, Lanczos approximation (g = 7, 9 terms)
+fn ln_gamma(x: f64) -> f64 {
+    const G: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // reflection formula, Gamma(x) Gamma(1 - x) = pi / sin(pi x)
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = G[1..]
+        .iter()
+        .enumerate()
+        .fold(G[0], |sum, (i, g)| sum + g / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// igamc function return the upper regularized incomplete gamma function Q(a, x)
+// The p-value of a chi-square statistic with k degrees of freedom is igamc(k / 2, chi2 / 2).
+// A series is used below a + 1 and a continued fraction above, like Numerical Recipes.
+pub fn igamc(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let prefix = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        // P(a, x) = e^-x x^a / Gamma(a) * sum x^n / (a (a+1) ... (a+n))
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut n = a;
+        for _ in 0..1000 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        1.0 - sum * prefix
+    } else {
+        // modified Lentz method for the continued fraction of Q(a, x)
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        prefix * h
+    }
+}
+
+// erfc function return the complementary error function, erfc(x) = Q(1/2, x^2) for x >= 0
+pub fn erfc(x: f64) -> f64 {
+    if x >= 0.0 {
+        igamc(0.5, x * x)
+    } else {
+        2.0 - igamc(0.5, x * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::*;
+
+    #[test]
+    fn test_1() {
+        // values from a table, igamc(1, x) = e^-x and erfc(1) = 0.157299207...
+        assert!((igamc(1.0, 2.0) - (-2.0f64).exp()).abs() < 1e-12);
+        assert!((erfc(1.0) - 0.157_299_207_050_285_1).abs() < 1e-12);
+        assert!((erfc(-1.0) - 1.842_700_792_949_715).abs() < 1e-12);
+        // chi-square with 10 degrees of freedom, p = 0.05 at 18.307
+        assert!((igamc(5.0, 18.307 / 2.0) - 0.05).abs() < 1e-4);
+        assert!((igamc(0.5, 0.01) - erfc(0.1)).abs() < 1e-15);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::Algorithm;
+use std::fmt::Display;
+use std::fmt::Write;
+
+// Quality tests of a hash output, run on random inputs of a fixed length
+//   avalanche: flip each input bit and count the output bits which flip, ideal is half of them
+//   strict avalanche (SAC): every output bit must flip with probability 1/2 for every input bit,
+//     the worst |P(flip) - 1/2| of all (input bit, output bit) pairs is reported
+//   bit independence (BIC): the flips of two output bits must not be correlated,
+//     the worst |correlation| of all pairs of output bits is reported (over all input bits together)
+//   bias: every output bit must be 1 with probability 1/2, the worst |P(1) - 1/2| is reported
+//   chi-square: counters 0, 1, 2, ... (little endian u64) are put in buckets by the first output bytes,
+//     like a hash table would do, and the p-value of the chi-square statistic is reported
+// The limits of passed are about 6 standard deviations of a random function, so a good hash
+// does not fail by bad luck, and a hash with a broken round function fails by far.
+
+// Config is the size of the tests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    // number of random inputs of the avalanche tests, each one is hashed input_len * 8 + 1 times
+    pub samples: usize,
+    // length of the random inputs in bytes
+    pub input_len: usize,
+    // number of counters and of buckets of the chi-square test
+    pub keys: usize,
+    pub buckets: usize,
+    // the same seed give the same report
+    pub seed: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            samples: 256,
+            input_len: 8,
+            keys: 1 << 14,
+            buckets: 256,
+            seed: 1,
+        }
+    }
+}
+
+// Report is the result of the tests of one hash
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub name: String,
+    pub output_bits: usize,
+    // mean fraction of output bits flipped by one input bit, ideal 0.5
+    pub avalanche: f64,
+    // worst |P(flip) - 0.5| of an (input bit, output bit) pair, ideal 0
+    pub sac: f64,
+    // worst |correlation| of the flips of two output bits, ideal 0
+    pub bic: f64,
+    // worst |P(1) - 0.5| of an output bit, ideal 0
+    pub bias: f64,
+    // chi-square statistic of the buckets and its p-value
+    pub chi_square: f64,
+    pub p_value: f64,
+    // limits of the avalanche, sac, bic and bias values for passed
+    limits: [f64; 4],
+}
+
+impl Report {
+    // passed method return true when every value is within the limits of a random function
+    pub fn passed(&self) -> bool {
+        (self.avalanche - 0.5).abs() < self.limits[0]
+            && self.sac < self.limits[1]
+            && self.bic < self.limits[2]
+            && self.bias < self.limits[3]
+            && self.p_value > 1e-4
+    }
+}
+
+// Display print the report as one line of the table, see table
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<14} {:>4} {:>9.4} {:>7.4} {:>7.4} {:>7.4} {:>10.1} {:>8.4}  {}",
+            self.name,
+            self.output_bits,
+            self.avalanche,
+            self.sac,
+            self.bic,
+            self.bias,
+            self.chi_square,
+            self.p_value,
+            if self.passed() { "ok" } else { "FAILED" }
+        )
+    }
+}
+
+// table function return the reports side by side, one line per hash
+pub fn table(reports: &[Report]) -> String {
+    let mut table = format!(
+        "{:<14} {:>4} {:>9} {:>7} {:>7} {:>7} {:>10} {:>8}  result\n",
+        "hash", "bits", "avalanche", "sac", "bic", "bias", "chi-square", "p-value"
+    );
+    for report in reports {
+        writeln!(&mut table, "{}", report).unwrap();
+    }
+    table
+}
+
+// Rng is xorshift64, for the random inputs
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn fill(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            *byte = (self.0 >> 56) as u8;
+        }
+    }
+}
+
+// bit function return bit i of a digest, bit 0 is the lowest bit of the first byte
+fn bit(digest: &[u8], i: usize) -> bool {
+    digest[i / 8] >> (i % 8) & 1 == 1
+}
+
+// analyze function run every test on a hash given as a function, so variants can be tested too
+pub fn analyze<F: Fn(&[u8]) -> Vec<u8>>(name: &str, hash: F, config: &Config) -> Report {
+    let input_bits = config.input_len * 8;
+    let output_bits = hash(&[]).len() * 8;
+    let mut rng = Rng::new(config.seed);
+
+    // flips[i][j] count the flips of output bit j for input bit i
+    let mut flips = vec![vec![0u32; output_bits]; input_bits];
+    // ones[j] count the outputs whose bit j is 1
+    let mut ones = vec![0u64; output_bits];
+    // columns[j] has one bit for each (sample, input bit), set when output bit j flipped
+    let observations = config.samples * input_bits;
+    let mut columns = vec![vec![0u64; observations.div_ceil(64)]; output_bits];
+    let mut total = 0u64;
+
+    let mut input = vec![0u8; config.input_len];
+    for sample in 0..config.samples {
+        rng.fill(&mut input);
+        let base = hash(&input);
+        for (j, count) in ones.iter_mut().enumerate() {
+            *count += bit(&base, j) as u64;
+        }
+        for i in 0..input_bits {
+            input[i / 8] ^= 1 << (i % 8);
+            let digest = hash(&input);
+            input[i / 8] ^= 1 << (i % 8);
+
+            let observation = sample * input_bits + i;
+            for j in 0..output_bits {
+                ones[j] += bit(&digest, j) as u64;
+                if bit(&digest, j) != bit(&base, j) {
+                    flips[i][j] += 1;
+                    columns[j][observation / 64] |= 1 << (observation % 64);
+                    total += 1;
+                }
+            }
+        }
+    }
+
+    let samples = config.samples as f64;
+    let avalanche = total as f64 / (observations * output_bits) as f64;
+    let sac = flips
+        .iter()
+        .flatten()
+        .map(|&count| (count as f64 / samples - 0.5).abs())
+        .fold(0.0, f64::max);
+
+    // correlation of two columns of bits from the counts of ones
+    let n = observations as f64;
+    let counts: Vec<f64> = columns
+        .iter()
+        .map(|column| column.iter().map(|w| w.count_ones() as u64).sum::<u64>() as f64)
+        .collect();
+    let mut bic = 0.0f64;
+    for j in 0..output_bits {
+        for k in j + 1..output_bits {
+            let both = columns[j]
+                .iter()
+                .zip(columns[k].iter())
+                .map(|(a, b)| (a & b).count_ones() as u64)
+                .sum::<u64>() as f64;
+            let variance = counts[j] * (n - counts[j]) * counts[k] * (n - counts[k]);
+            // a bit which never or always flip is already a failure of sac
+            if variance > 0.0 {
+                bic = bic.max(((n * both - counts[j] * counts[k]) / variance.sqrt()).abs());
+            }
+        }
+    }
+
+    let outputs = (config.samples * (input_bits + 1)) as f64;
+    let bias = ones
+        .iter()
+        .map(|&count| (count as f64 / outputs - 0.5).abs())
+        .fold(0.0, f64::max);
+
+    // chi-square of the counters put in buckets by the first (up to) 4 bytes of the output
+    let mut buckets = vec![0u64; config.buckets];
+    for key in 0..config.keys as u64 {
+        let digest = hash(&key.to_le_bytes());
+        let mut first = [0u8; 4];
+        let len = digest.len().min(4);
+        first[..len].copy_from_slice(&digest[..len]);
+        buckets[u32::from_le_bytes(first) as usize % config.buckets] += 1;
+    }
+    let expected = config.keys as f64 / config.buckets as f64;
+    let chi_square = buckets
+        .iter()
+        .map(|&count| (count as f64 - expected).powi(2) / expected)
+        .sum::<f64>();
+    let p_value = super::igamc((config.buckets - 1) as f64 / 2.0, chi_square / 2.0);
+
+    // 6 standard deviations: 0.5 / sqrt(count) for a proportion and 1 / sqrt(n) for a correlation
+    let limits = [
+        3.0 / ((observations * output_bits) as f64).sqrt(),
+        3.0 / samples.sqrt(),
+        6.0 / n.sqrt(),
+        3.0 / outputs.sqrt(),
+    ];
+    Report {
+        name: name.to_string(),
+        output_bits,
+        avalanche,
+        sac,
+        bic,
+        bias,
+        chi_square,
+        p_value,
+        limits,
+    }
+}
+
+// analyze_algorithm function run every test on one of the algorithms of the crate
+pub fn analyze_algorithm(algorithm: Algorithm, config: &Config) -> Report {
+    analyze(algorithm.name(), |input| algorithm.digest(input), config)
+}
+
+// compare function run every test on several algorithms, print the result with table
+pub fn compare(algorithms: &[Algorithm], config: &Config) -> Vec<Report> {
+    algorithms
+        .iter()
+        .map(|algorithm| analyze_algorithm(*algorithm, config))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::quality::*;
+    use crate::md5::{MD5Digest, Params, MD5};
+
+    #[test]
+    fn test_1() {
+        // the cryptographic and the good fast hashes pass, the checksums do not
+        let config = Config {
+            samples: 64,
+            ..Config::default()
+        };
+        let reports = compare(
+            &[
+                Algorithm::Md5,
+                Algorithm::Sha1,
+                Algorithm::Xxh3_64,
+                Algorithm::Adler32,
+                Algorithm::Fletcher32,
+            ],
+            &config,
+        );
+        println!("{}", table(&reports));
+
+        assert!(reports[0].passed());
+        assert!(reports[1].passed());
+        assert!(reports[2].passed());
+        assert!(!reports[3].passed());
+        assert!(!reports[4].passed());
+        assert_eq!(160, reports[1].output_bits);
+    }
+
+    #[test]
+    fn test_2() {
+        // MD5 cut after 8 steps does not avalanche, a broken round function is caught
+        let config = Config {
+            samples: 64,
+            ..Config::default()
+        };
+        let reduced = |input: &[u8]| {
+            let mut hash = MD5::with_params(Params {
+                steps: 8,
+                ..Params::MD5
+            });
+            hash.update(input);
+            hash.finalize().digest().to_vec()
+        };
+        let report = analyze("md5-8-steps", reduced, &config);
+        println!("{}", report);
+
+        assert!(!report.passed());
+        assert!(report.sac > 0.4);
+    }
+}
@@ -0,0 +1,599 @@
+use super::{erfc, igamc};
+use crate::Algorithm;
+use std::fmt::Display;
+use std::fmt::Write;
+
+// Tests of NIST SP 800-22 rev. 1a, "A Statistical Test Suite for Random and Pseudorandom Number Generators"
+// Each test return the p-values of the sequence, a sequence looks random for the test when they are >= 0.01.
+// The formulas and the constants are the ones of the document, the examples of its section 2 are the unit tests.
+// The universal, template and random excursions tests are not here.
+// The sequence is one byte per bit (0 or 1) like the reference code, bits function convert bytes to it.
+// counter_stream and iterated_stream make the two usual hash based streams to test.
+
+// bits function return the bits of bytes, the highest bit of each byte first
+pub fn bits(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1))
+        .collect()
+}
+
+// counter_stream function return len bytes of H(seed || counter), the counter is a big endian u64 from 0
+pub fn counter_stream(algorithm: Algorithm, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut stream = Vec::with_capacity(len);
+    let mut counter = 0u64;
+    while stream.len() < len {
+        let mut input = seed.to_vec();
+        input.extend_from_slice(&counter.to_be_bytes());
+        stream.extend_from_slice(&algorithm.digest(&input));
+        counter += 1;
+    }
+    stream.truncate(len);
+    stream
+}
+
+// iterated_stream function return len bytes of H(seed), H(H(seed)), ...
+pub fn iterated_stream(algorithm: Algorithm, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut stream = Vec::with_capacity(len);
+    let mut digest = algorithm.digest(seed);
+    while stream.len() < len {
+        stream.extend_from_slice(&digest);
+        digest = algorithm.digest(&digest);
+    }
+    stream.truncate(len);
+    stream
+}
+
+// normal function return the cumulative distribution of the standard normal distribution
+fn normal(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+// chi_square function return sum (v - N pi)^2 / (N pi)
+fn chi_square(counts: &[u64], pi: &[f64], n: f64) -> f64 {
+    counts
+        .iter()
+        .zip(pi.iter())
+        .map(|(&v, &p)| (v as f64 - n * p).powi(2) / (n * p))
+        .sum()
+}
+
+// frequency function is the frequency (monobit) test, 2.1
+pub fn frequency(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let sum: i64 = bits.iter().map(|&bit| 2 * bit as i64 - 1).sum();
+    erfc(sum.abs() as f64 / n.sqrt() / std::f64::consts::SQRT_2)
+}
+
+// block_frequency function is the frequency test within blocks of m bits, 2.2
+pub fn block_frequency(bits: &[u8], m: usize) -> f64 {
+    let blocks = bits.len() / m;
+    let chi: f64 = bits
+        .chunks_exact(m)
+        .map(|block| {
+            let pi = block.iter().map(|&bit| bit as f64).sum::<f64>() / m as f64;
+            (pi - 0.5).powi(2)
+        })
+        .sum::<f64>()
+        * 4.0
+        * m as f64;
+    igamc(blocks as f64 / 2.0, chi / 2.0)
+}
+
+// runs function is the runs test, 2.3
+// It is not run (p-value 0) when the frequency is already too far from 1/2.
+pub fn runs(bits: &[u8]) -> f64 {
+    let n = bits.len() as f64;
+    let pi = bits.iter().map(|&bit| bit as f64).sum::<f64>() / n;
+    if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return 0.0;
+    }
+    let v = 1 + bits.windows(2).filter(|pair| pair[0] != pair[1]).count();
+    let expected = 2.0 * n * pi * (1.0 - pi);
+    erfc((v as f64 - expected).abs() / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi)))
+}
+
+// longest_run function is the test for the longest run of ones in a block, 2.4
+// The block size come from the length of the sequence, None below 128 bits.
+pub fn longest_run(bits: &[u8]) -> Option<f64> {
+    let (m, shortest, pi): (usize, usize, &[f64]) = match bits.len() {
+        0..=127 => return None,
+        128..=6271 => (8, 1, &[0.2148, 0.3672, 0.2305, 0.1875]),
+        6272..=749_999 => (128, 4, &[0.1174, 0.2430, 0.2493, 0.1752, 0.1027, 0.1124]),
+        _ => (
+            10_000,
+            10,
+            &[0.0882, 0.2092, 0.2483, 0.1933, 0.1208, 0.0675, 0.0727],
+        ),
+    };
+    let mut counts = vec![0u64; pi.len()];
+    for block in bits.chunks_exact(m) {
+        let (mut longest, mut run) = (0, 0);
+        for &bit in block {
+            run = if bit == 1 { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+        counts[longest.clamp(shortest, shortest + pi.len() - 1) - shortest] += 1;
+    }
+    let blocks = (bits.len() / m) as f64;
+    let k = (pi.len() - 1) as f64;
+    Some(igamc(k / 2.0, chi_square(&counts, pi, blocks) / 2.0))
+}
+
+// rank function return the rank over GF(2) of a matrix given as rows of bits
+fn rank(mut rows: Vec<u32>) -> usize {
+    let mut rank = 0;
+    for column in (0..32).rev() {
+        let bit = 1 << column;
+        if let Some(pivot) = (rank..rows.len()).find(|&i| rows[i] & bit != 0) {
+            rows.swap(rank, pivot);
+            for i in 0..rows.len() {
+                if i != rank && rows[i] & bit != 0 {
+                    rows[i] ^= rows[rank];
+                }
+            }
+            rank += 1;
+        }
+    }
+    rank
+}
+
+// matrix_rank function is the binary matrix rank test with 32 x 32 matrices, 2.5
+// None when the sequence has less than 38 matrices.
+pub fn matrix_rank(bits: &[u8]) -> Option<f64> {
+    let matrices = bits.len() / (32 * 32);
+    if matrices < 38 {
+        return None;
+    }
+    let mut counts = [0u64; 3];
+    for matrix in bits.chunks_exact(32 * 32) {
+        let rows = matrix
+            .chunks_exact(32)
+            .map(|row| row.iter().fold(0u32, |word, &bit| word << 1 | bit as u32))
+            .collect();
+        match rank(rows) {
+            32 => counts[0] += 1,
+            31 => counts[1] += 1,
+            _ => counts[2] += 1,
+        }
+    }
+    let chi = chi_square(&counts, &[0.2888, 0.5776, 0.1336], matrices as f64);
+    Some((-chi / 2.0).exp())
+}
+
+// fft function is the iterative radix-2 FFT, the length must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64], inverse: bool) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let angle = sign * 2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let (x, y) = (re[b] * cos - im[b] * sin, re[b] * sin + im[b] * cos);
+                re[b] = re[a] - x;
+                im[b] = im[a] - y;
+                re[a] += x;
+                im[a] += y;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// dft_magnitudes function return |S_j| of the discrete Fourier transform of x for j < n / 2
+// Any length is done with Bluestein's algorithm, the transform as a convolution of power of two length.
+fn dft_magnitudes(x: &[f64]) -> Vec<f64> {
+    let n = x.len();
+    let size = (2 * n - 1).next_power_of_two();
+    // chirp w_k = e^(-i pi k^2 / n), k^2 is taken mod 2n to keep the angle exact
+    let chirp: Vec<(f64, f64)> = (0..n)
+        .map(|k| {
+            let k2 = (k as u128 * k as u128 % (2 * n as u128)) as f64;
+            let (sin, cos) = (std::f64::consts::PI * k2 / n as f64).sin_cos();
+            (cos, -sin)
+        })
+        .collect();
+    let (mut a_re, mut a_im) = (vec![0.0; size], vec![0.0; size]);
+    let (mut b_re, mut b_im) = (vec![0.0; size], vec![0.0; size]);
+    for k in 0..n {
+        a_re[k] = x[k] * chirp[k].0;
+        a_im[k] = x[k] * chirp[k].1;
+        b_re[k] = chirp[k].0;
+        b_im[k] = -chirp[k].1;
+        if k > 0 {
+            b_re[size - k] = chirp[k].0;
+            b_im[size - k] = -chirp[k].1;
+        }
+    }
+    fft(&mut a_re, &mut a_im, false);
+    fft(&mut b_re, &mut b_im, false);
+    for k in 0..size {
+        let (re, im) = (
+            a_re[k] * b_re[k] - a_im[k] * b_im[k],
+            a_re[k] * b_im[k] + a_im[k] * b_re[k],
+        );
+        a_re[k] = re;
+        a_im[k] = im;
+    }
+    fft(&mut a_re, &mut a_im, true);
+    (0..n / 2)
+        .map(|k| {
+            let (re, im) = (a_re[k] / size as f64, a_im[k] / size as f64);
+            let (re, im) = (
+                re * chirp[k].0 - im * chirp[k].1,
+                re * chirp[k].1 + im * chirp[k].0,
+            );
+            (re * re + im * im).sqrt()
+        })
+        .collect()
+}
+
+// dft function is the discrete Fourier transform (spectral) test, 2.6
+// None for an empty sequence, there is no transform to take.
+pub fn dft(bits: &[u8]) -> Option<f64> {
+    if bits.is_empty() {
+        return None;
+    }
+    let n = bits.len() as f64;
+    let x: Vec<f64> = bits.iter().map(|&bit| 2.0 * bit as f64 - 1.0).collect();
+    let threshold = ((1.0f64 / 0.05).ln() * n).sqrt();
+    let n0 = 0.95 * n / 2.0;
+    let n1 = dft_magnitudes(&x)
+        .iter()
+        .filter(|&&magnitude| magnitude < threshold)
+        .count() as f64;
+    let d = (n1 - n0) / (n * 0.95 * 0.05 / 4.0).sqrt();
+    Some(erfc(d.abs() / std::f64::consts::SQRT_2))
+}
+
+// linear_complexity_of function return the length of the shortest LFSR which generate the bits (Berlekamp-Massey)
+fn linear_complexity_of(bits: &[u8]) -> usize {
+    let n = bits.len();
+    let (mut c, mut b) = (vec![0u8; n + 1], vec![0u8; n + 1]);
+    c[0] = 1;
+    b[0] = 1;
+    let (mut l, mut m) = (0usize, -1isize);
+    for i in 0..n {
+        let d = (1..=l).fold(bits[i], |d, j| d ^ (c[j] & bits[i - j]));
+        if d == 1 {
+            let t = c.clone();
+            let shift = (i as isize - m) as usize;
+            for j in 0..=n - shift {
+                c[j + shift] ^= b[j];
+            }
+            if 2 * l <= i {
+                l = i + 1 - l;
+                m = i as isize;
+                b = t;
+            }
+        }
+    }
+    l
+}
+
+// linear_complexity function is the linear complexity test with blocks of m bits (500 <= m <= 5000), 2.10
+pub fn linear_complexity(bits: &[u8], m: usize) -> f64 {
+    const PI: [f64; 7] = [0.010417, 0.03125, 0.125, 0.5, 0.25, 0.0625, 0.020833];
+    let mf = m as f64;
+    let sign = if m.is_multiple_of(2) { 1.0 } else { -1.0 };
+    let mu = mf / 2.0 + (9.0 - sign) / 36.0 - (mf / 3.0 + 2.0 / 9.0) / 2f64.powf(mf);
+    let mut counts = [0u64; 7];
+    for block in bits.chunks_exact(m) {
+        let t = sign * (linear_complexity_of(block) as f64 - mu) + 2.0 / 9.0;
+        let bin = match t {
+            t if t <= -2.5 => 0,
+            t if t <= -1.5 => 1,
+            t if t <= -0.5 => 2,
+            t if t <= 0.5 => 3,
+            t if t <= 1.5 => 4,
+            t if t <= 2.5 => 5,
+            _ => 6,
+        };
+        counts[bin] += 1;
+    }
+    let blocks = (bits.len() / m) as f64;
+    igamc(3.0, chi_square(&counts, &PI, blocks) / 2.0)
+}
+
+// pattern_counts function count every pattern of m bits, the patterns overlap and wrap around at the end
+// The serial and the approximate entropy tests are computed from these counts.
+fn pattern_counts(bits: &[u8], m: usize) -> Vec<u64> {
+    let mut counts = vec![0u64; 1 << m];
+    if m == 0 {
+        return counts;
+    }
+    let n = bits.len();
+    for i in 0..n {
+        let pattern = (0..m).fold(0usize, |pattern, j| {
+            pattern << 1 | bits[(i + j) % n] as usize
+        });
+        counts[pattern] += 1;
+    }
+    counts
+}
+
+// psi_square function return psi^2_m of the serial test
+fn psi_square(bits: &[u8], m: usize) -> f64 {
+    if m == 0 {
+        return 0.0;
+    }
+    let n = bits.len() as f64;
+    let sum: f64 = pattern_counts(bits, m)
+        .iter()
+        .map(|&v| (v as f64).powi(2))
+        .sum();
+    sum * (1u64 << m) as f64 / n - n
+}
+
+// serial function is the serial test with patterns of m bits, 2.11, it return two p-values
+// None when m is 0 or the sequence is empty.
+pub fn serial(bits: &[u8], m: usize) -> Option<[f64; 2]> {
+    if m == 0 || bits.is_empty() {
+        return None;
+    }
+    let psi = [
+        psi_square(bits, m),
+        psi_square(bits, m - 1),
+        psi_square(bits, m.saturating_sub(2)),
+    ];
+    let delta = psi[0] - psi[1];
+    let delta2 = psi[0] - 2.0 * psi[1] + psi[2];
+    Some([
+        igamc(2f64.powi(m as i32 - 2), delta / 2.0),
+        igamc(2f64.powi(m as i32 - 3), delta2 / 2.0),
+    ])
+}
+
+// approximate_entropy function is the approximate entropy test with patterns of m bits, 2.12
+pub fn approximate_entropy(bits: &[u8], m: usize) -> f64 {
+    let n = bits.len() as f64;
+    let phi = |m: usize| -> f64 {
+        pattern_counts(bits, m)
+            .iter()
+            .filter(|&&v| v > 0)
+            .map(|&v| v as f64 / n * (v as f64 / n).ln())
+            .sum()
+    };
+    let entropy = phi(m) - phi(m + 1);
+    let chi = 2.0 * n * (2f64.ln() - entropy);
+    igamc(2f64.powi(m as i32 - 1), chi / 2.0)
+}
+
+// cumulative_sums function is the cumulative sums test, 2.13, forward and backward p-values
+pub fn cumulative_sums(bits: &[u8]) -> [f64; 2] {
+    let n = bits.len() as i64;
+    let p_value = |z: i64| -> f64 {
+        let (nf, zf) = (n as f64, z as f64);
+        let sqrt = nf.sqrt();
+        // the bounds are divided like the reference code, integer division toward zero
+        let mut sum = 1.0;
+        for k in (-n / z + 1) / 4..=(n / z - 1) / 4 {
+            let k = k as f64;
+            sum -= normal((4.0 * k + 1.0) * zf / sqrt) - normal((4.0 * k - 1.0) * zf / sqrt);
+        }
+        for k in (-n / z - 3) / 4..=(n / z - 1) / 4 {
+            let k = k as f64;
+            sum += normal((4.0 * k + 3.0) * zf / sqrt) - normal((4.0 * k + 1.0) * zf / sqrt);
+        }
+        sum
+    };
+    let largest = |bits: &mut dyn Iterator<Item = &u8>| -> i64 {
+        let mut s = 0i64;
+        bits.map(|&bit| {
+            s += 2 * bit as i64 - 1;
+            s.abs()
+        })
+        .max()
+        .unwrap_or(0)
+        .max(1)
+    };
+    [
+        p_value(largest(&mut bits.iter())),
+        p_value(largest(&mut bits.iter().rev())),
+    ]
+}
+
+// TestResult is the p-values of one test of the battery
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResult {
+    pub name: &'static str,
+    pub p_values: Vec<f64>,
+}
+
+impl TestResult {
+    // passed method return true when every p-value is at least 0.01, the level of the document
+    pub fn passed(&self) -> bool {
+        self.p_values.iter().all(|&p| p >= 0.01)
+    }
+}
+
+// Display print the result as one line of the table, see table
+impl Display for TestResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let p_values = self.p_values.iter().fold(String::new(), |mut acc, p| {
+            write!(&mut acc, " {:.6}", p).unwrap();
+            acc
+        });
+        write!(
+            f,
+            "{:<22} {:<6}{}",
+            self.name,
+            if self.passed() { "ok" } else { "FAILED" },
+            p_values
+        )
+    }
+}
+
+// run_all function run every test with the parameters the document suggest for the length
+// The tests which need a longer sequence are left out. 10^6 bits is the length the document use.
+pub fn run_all(bits: &[u8]) -> Vec<TestResult> {
+    let n = bits.len();
+    let log2 = usize::BITS - 1 - n.max(1).leading_zeros();
+    let mut results = vec![
+        TestResult {
+            name: "frequency",
+            p_values: vec![frequency(bits)],
+        },
+        TestResult {
+            name: "block frequency",
+            p_values: vec![block_frequency(bits, 128.min(n.max(1)))],
+        },
+        TestResult {
+            name: "runs",
+            p_values: vec![runs(bits)],
+        },
+    ];
+    if let Some(p) = longest_run(bits) {
+        results.push(TestResult {
+            name: "longest run",
+            p_values: vec![p],
+        });
+    }
+    if let Some(p) = matrix_rank(bits) {
+        results.push(TestResult {
+            name: "matrix rank",
+            p_values: vec![p],
+        });
+    }
+    if let Some(p) = dft(bits) {
+        results.push(TestResult {
+            name: "dft",
+            p_values: vec![p],
+        });
+    }
+    // at least 200 blocks of 500 bits
+    if n >= 500 * 200 {
+        results.push(TestResult {
+            name: "linear complexity",
+            p_values: vec![linear_complexity(bits, 500)],
+        });
+    }
+    // m < log2(n) - 2 for serial and m < log2(n) - 5 for approximate entropy
+    let m = (log2 as usize).saturating_sub(3).clamp(2, 16);
+    if let Some(p) = serial(bits, m) {
+        results.push(TestResult {
+            name: "serial",
+            p_values: p.to_vec(),
+        });
+    }
+    let m = (log2 as usize).saturating_sub(6).clamp(1, 10);
+    results.push(TestResult {
+        name: "approximate entropy",
+        p_values: vec![approximate_entropy(bits, m)],
+    });
+    results.push(TestResult {
+        name: "cumulative sums",
+        p_values: cumulative_sums(bits).to_vec(),
+    });
+    results
+}
+
+// table function return the results one line per test
+pub fn table(results: &[TestResult]) -> String {
+    let mut table = format!("{:<22} {:<6} p-values\n", "test", "result");
+    for result in results {
+        writeln!(&mut table, "{}", result).unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::sp800_22::*;
+
+    // the examples of section 2 of the document, the first 100 bits of pi
+    const PI_100: &str = "1100100100001111110110101010001000100001011010001100\
+                          001000110100110001001100011001100010100010111000";
+
+    fn parse(bits: &str) -> Vec<u8> {
+        bits.bytes().map(|b| b - b'0').collect()
+    }
+
+    fn close(expected: f64, p: f64) -> bool {
+        println!("{} {}", expected, p);
+        (expected - p).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_1() {
+        let bits = parse(PI_100);
+        assert_eq!(100, bits.len());
+        assert!(close(0.109599, frequency(&bits)));
+        assert!(close(0.706438, block_frequency(&bits, 10)));
+        assert!(close(0.500798, runs(&bits)));
+        // the document print 0.168669 with N1 = 46 peaks below the threshold,
+        // an exact transform of these bits has 48 of them (checked with a naive DFT), so p = 0.646355
+        assert!(close(0.646355, dft(&bits).unwrap()));
+        assert!(close(0.235301, approximate_entropy(&bits, 2)));
+        let [forward, backward] = cumulative_sums(&bits);
+        assert!(close(0.219194, forward));
+        assert!(close(0.114866, backward));
+    }
+
+    #[test]
+    fn test_2() {
+        // the small examples
+        let bits = parse(
+            "11001100000101010110110001001100111000000000001001001101010100010001001111010110100000001101011111001100111001101101100010110010",
+        );
+        // the document print 0.180609, igamc(3/2, 4.882605 / 2) is 0.180598
+        assert!(close(0.180598, longest_run(&bits).unwrap()));
+        let [p1, p2] = serial(&parse("0011011101"), 3).unwrap();
+        assert!(close(0.808792, p1));
+        assert!(close(0.670320, p2));
+        assert!(close(
+            0.261961,
+            approximate_entropy(&parse("0100110101"), 3)
+        ));
+        assert!(close(0.4116588, cumulative_sums(&parse("1011010111"))[0]));
+        assert_eq!(4, linear_complexity_of(&parse("1101011110001")));
+        assert_eq!(2, rank(vec![0b010, 0b110, 0b100]));
+    }
+
+    #[test]
+    fn test_3() {
+        // a counter mode stream of sha1 passes, a constant stream does not
+        let stream = counter_stream(Algorithm::Sha1, b"seed", 1 << 14);
+        let results = run_all(&bits(&stream));
+        println!("{}", table(&results));
+        assert!(results.iter().all(|result| result.passed()));
+        assert_eq!(10, results.len());
+
+        let stream = iterated_stream(Algorithm::Md5, b"seed", 1 << 12);
+        assert!(run_all(&bits(&stream)).iter().all(|result| result.passed()));
+
+        let results = run_all(&bits(&[0x55; 1 << 12]));
+        assert!(!results.iter().all(|result| result.passed()));
+    }
+
+    #[test]
+    fn test_4() {
+        // too short inputs give None and no panic
+        assert_eq!(None, dft(&[]));
+        assert_eq!(None, serial(&[], 2));
+        assert_eq!(None, serial(&parse("0011011101"), 0));
+        assert!(dft(&[1]).is_some());
+        assert!(serial(&[1], 1).is_some());
+        let results = run_all(&[]);
+        println!("{}", table(&results));
+        assert!(results
+            .iter()
+            .all(|result| result.name != "dft" && result.name != "serial"));
+    }
+}
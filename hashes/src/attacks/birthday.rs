@@ -0,0 +1,404 @@
+use crate::Algorithm;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Birthday attack on the first n bits of a digest
+// Among about sqrt(pi / 2 * 2^n) random outputs of n bits, two are already the same (the birthday paradox).
+// So a digest of n bits give only n / 2 bits of collision resistance: 2^64 hashes for the 128 bits of MD5,
+// which is a lot of work but in reach of a large computation, and far less than it looks.
+//
+// Storing 2^(n/2) outputs needs a lot of memory, so the hash is iterated instead:
+// x -> f(x) = first n bits of H(salt and x as 8 bytes each) is a random function on n bits, its walk from
+// any start runs into a cycle after about 2^(n/2) steps, and the entrance of the cycle is reached from two
+// different points, which is a collision (Pollard rho).
+// With few bits f can be a permutation (for 1 bit it is one half of the time), then every walk is a cycle
+// and there is no collision at all. So a restart which found nothing take the next salt, that is a new f. Floyd's cycle finding need no memory at all,
+// but it compute each point about three times, so rho measure a few times the expected work.
+// The parallel version (van Oorschot and Wiener) let every thread walk until a distinguished point,
+// an output whose low bits are zero. Two walks which reach the same distinguished point have merged,
+// and walking them again from their starts find the point where they merged.
+
+// Collision is two different messages whose digests have the same first bits
+pub struct Collision {
+    pub algorithm: Algorithm,
+    pub bits: u32,
+    pub first: Vec<u8>,
+    pub second: Vec<u8>,
+    // number of hashes computed to find it, over all threads
+    pub evaluations: u64,
+}
+
+// expected_work function return the expected number of hashes before the first collision of n bits
+pub fn expected_work(bits: u32) -> f64 {
+    (std::f64::consts::PI / 2.0 * 2f64.powi(bits as i32)).sqrt()
+}
+
+impl Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let expected = expected_work(self.bits);
+        writeln!(
+            f,
+            "collision on the first {} bits of {}",
+            self.bits, self.algorithm
+        )?;
+        writeln!(
+            f,
+            "  {} -> {}",
+            crate::hex::encode(&self.first),
+            crate::hex::encode(&self.algorithm.digest(&self.first))
+        )?;
+        writeln!(
+            f,
+            "  {} -> {}",
+            crate::hex::encode(&self.second),
+            crate::hex::encode(&self.algorithm.digest(&self.second))
+        )?;
+        write!(
+            f,
+            "  measured {} hashes (2^{:.1}), expected about 2^{:.1} for {} bits",
+            self.evaluations,
+            (self.evaluations as f64).log2(),
+            expected.log2(),
+            self.bits
+        )
+    }
+}
+
+// Walk is the random function x -> first n bits of H(salt and x as 8 bytes each)
+#[derive(Clone, Copy)]
+struct Walk {
+    algorithm: Algorithm,
+    bits: u32,
+    salt: u64,
+}
+
+impl Walk {
+    fn new(algorithm: Algorithm, bits: u32) -> Self {
+        assert!(
+            (1..=64).contains(&bits) && bits as usize <= algorithm.output_size() * 8,
+            "bits must be 1..=64 and not more than the digest"
+        );
+        Walk {
+            algorithm,
+            bits,
+            salt: 0,
+        }
+    }
+
+    // salted method return the same walk with another salt
+    fn salted(&self, salt: u64) -> Walk {
+        Walk { salt, ..*self }
+    }
+
+    // message method return the message which is hashed for x
+    fn message(&self, x: u64) -> [u8; 16] {
+        let mut message = [0u8; 16];
+        message[..8].copy_from_slice(&self.salt.to_le_bytes());
+        message[8..].copy_from_slice(&x.to_le_bytes());
+        message
+    }
+
+    fn mask(&self) -> u64 {
+        u64::MAX >> (64 - self.bits)
+    }
+
+    // next method return the first bits of the digest of x, the highest bits of the first bytes
+    fn next(&self, x: u64) -> u64 {
+        let digest = self.algorithm.digest(&self.message(x));
+        let mut first = [0u8; 8];
+        let len = digest.len().min(8);
+        first[..len].copy_from_slice(&digest[..len]);
+        u64::from_be_bytes(first) >> (64 - self.bits)
+    }
+
+    fn collision(&self, a: u64, b: u64, evaluations: u64) -> Collision {
+        Collision {
+            algorithm: self.algorithm,
+            bits: self.bits,
+            first: self.message(a).to_vec(),
+            second: self.message(b).to_vec(),
+            evaluations,
+        }
+    }
+}
+
+// Rng is splitmix64, for the starting points
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+// cycle function find the entrance of the cycle of the walk from start with Floyd's cycle finding
+// and return the two points before it, None when the start is on the cycle (no tail and no collision).
+fn cycle(walk: &Walk, start: u64, evaluations: &mut u64) -> Option<(u64, u64)> {
+    // the tortoise take one step and the hare two, until they meet in the cycle
+    let mut tortoise = walk.next(start);
+    let mut hare = walk.next(tortoise);
+    *evaluations += 2;
+    while tortoise != hare {
+        tortoise = walk.next(tortoise);
+        hare = walk.next(walk.next(hare));
+        *evaluations += 3;
+    }
+    if start == hare {
+        return None;
+    }
+    // both go on one step at a time, from the start and from the meeting point,
+    // they reach the entrance of the cycle together and the points before it are the collision
+    let mut tortoise = start;
+    loop {
+        let (a, b) = (walk.next(tortoise), walk.next(hare));
+        *evaluations += 2;
+        if a == b {
+            return Some((tortoise, hare));
+        }
+        tortoise = a;
+        hare = b;
+    }
+}
+
+// rho function find a collision on the first bits of the digest with Floyd's cycle finding, on one thread
+pub fn rho(algorithm: Algorithm, bits: u32, seed: u64) -> Collision {
+    let mut walk = Walk::new(algorithm, bits);
+    let mut rng = Rng(seed);
+    let mut evaluations = 0;
+    loop {
+        let start = rng.next_u64() & walk.mask();
+        match cycle(&walk, start, &mut evaluations) {
+            Some((a, b)) => return walk.collision(a, b, evaluations),
+            // the start was on the cycle, the next restart use the next salt (f may be a permutation)
+            None => walk = walk.salted(walk.salt + 1),
+        }
+    }
+}
+
+// merge function walk two chains which reach the same distinguished point and return where they merge
+// None when one start is on the chain of the other, then the chains are the same and there is no collision.
+fn merge(walk: &Walk, a: (u64, u64), b: (u64, u64), evaluations: &AtomicU64) -> Option<(u64, u64)> {
+    let ((mut x, mut x_len), (mut y, mut y_len)) = (a, b);
+    while x_len > y_len {
+        x = walk.next(x);
+        x_len -= 1;
+        evaluations.fetch_add(1, Ordering::Relaxed);
+    }
+    while y_len > x_len {
+        y = walk.next(y);
+        y_len -= 1;
+        evaluations.fetch_add(1, Ordering::Relaxed);
+    }
+    while x != y {
+        let (next_x, next_y) = (walk.next(x), walk.next(y));
+        evaluations.fetch_add(2, Ordering::Relaxed);
+        if next_x == next_y {
+            return Some((x, y));
+        }
+        x = next_x;
+        y = next_y;
+    }
+    None
+}
+
+// Search is what the threads of distinguished share: the salt of the walk, the distinguished points reached
+// with it (mapped to the start and the length of the chain) and the number of chains which found nothing
+struct Search {
+    salt: u64,
+    points: HashMap<u64, (u64, u64)>,
+    failures: u64,
+}
+
+impl Search {
+    // fail method count a chain of the walk which found nothing
+    // When f is a permutation every chain fail and no new point is found, then the next salt is taken.
+    // For a random function of many bits a failed chain is rare, far less than the points.
+    fn fail(&mut self, walk: &Walk) {
+        if walk.salt != self.salt {
+            return;
+        }
+        self.failures += 1;
+        if self.failures > self.points.len() as u64 + 16 {
+            self.salt += 1;
+            self.points.clear();
+            self.failures = 0;
+        }
+    }
+}
+
+// distinguished function find a collision with the parallel distinguished point search on threads threads
+// A point is distinguished when its low bits / 4 bits are zero.
+pub fn distinguished(algorithm: Algorithm, bits: u32, threads: usize, seed: u64) -> Collision {
+    let base = Walk::new(algorithm, bits);
+    let zeros = (bits / 4).max(1);
+    let distinguished = (1u64 << zeros) - 1;
+    // a walk which is much longer than expected is in a cycle without distinguished point
+    let longest = 20 << zeros;
+
+    let search = Mutex::new(Search {
+        salt: 0,
+        points: HashMap::new(),
+        failures: 0,
+    });
+    let found = AtomicBool::new(false);
+    let result = Mutex::new(None);
+    let evaluations = AtomicU64::new(0);
+
+    std::thread::scope(|scope| {
+        for thread in 0..threads.max(1) {
+            let (base, search, found, result, evaluations) =
+                (&base, &search, &found, &result, &evaluations);
+            scope.spawn(move || {
+                let mut rng = Rng(seed ^ (thread as u64).wrapping_mul(0x2545_f491_4f6c_dd1d));
+                while !found.load(Ordering::Relaxed) {
+                    let walk = base.salted(search.lock().unwrap().salt);
+                    let start = rng.next_u64() & walk.mask();
+                    // at least one step, a start which is already distinguished would be a chain of
+                    // length 0 that no other chain can merge into (with few bits most starts are)
+                    let (mut x, mut len) = (walk.next(start), 1);
+                    while x & distinguished != 0 && len < longest {
+                        x = walk.next(x);
+                        len += 1;
+                    }
+                    evaluations.fetch_add(len, Ordering::Relaxed);
+                    if len == longest {
+                        // with few bits a cycle may have no distinguished point at all,
+                        // the entrance of the cycle is a collision too
+                        let mut count = 0;
+                        let pair = cycle(&walk, start, &mut count);
+                        evaluations.fetch_add(count, Ordering::Relaxed);
+                        match pair {
+                            Some((a, b)) => {
+                                *result.lock().unwrap() = Some(walk.collision(a, b, 0));
+                                found.store(true, Ordering::Relaxed);
+                            }
+                            None => search.lock().unwrap().fail(&walk),
+                        }
+                        continue;
+                    }
+                    let other = {
+                        let mut search = search.lock().unwrap();
+                        if search.salt != walk.salt {
+                            // another thread took the next salt meanwhile, this chain is of the old walk
+                            continue;
+                        }
+                        match search.points.get(&x) {
+                            Some(&other) if other.0 != start => Some(other),
+                            Some(_) => {
+                                // the same chain again
+                                search.fail(&walk);
+                                None
+                            }
+                            None => {
+                                search.points.insert(x, (start, len));
+                                None
+                            }
+                        }
+                    };
+                    if let Some(other) = other {
+                        if let Some((a, b)) = merge(&walk, (start, len), other, evaluations) {
+                            *result.lock().unwrap() = Some(walk.collision(a, b, 0));
+                            found.store(true, Ordering::Relaxed);
+                        } else {
+                            let mut search = search.lock().unwrap();
+                            if search.salt == walk.salt && len > other.1 {
+                                // the other start is on this chain, the longer chain is kept so that
+                                // the next one merge with it (else a short chain hide all the others)
+                                search.points.insert(x, (start, len));
+                            }
+                            search.fail(&walk);
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let mut collision: Collision = result.into_inner().unwrap().unwrap();
+    collision.evaluations = evaluations.load(Ordering::Relaxed);
+    collision
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attacks::birthday::*;
+
+    // first function return the first bits of the digest of a message, a short digest is padded with zeros
+    fn first(algorithm: Algorithm, bits: u32, message: &[u8]) -> u64 {
+        let digest = algorithm.digest(message);
+        let mut first = [0u8; 8];
+        let len = digest.len().min(8);
+        first[..len].copy_from_slice(&digest[..len]);
+        u64::from_be_bytes(first) >> (64 - bits)
+    }
+
+    #[test]
+    fn test_1() {
+        let collision = rho(Algorithm::Md5, 24, 1);
+        println!("{}", collision);
+
+        assert_ne!(collision.first, collision.second);
+        assert_eq!(
+            first(Algorithm::Md5, 24, &collision.first),
+            first(Algorithm::Md5, 24, &collision.second)
+        );
+        // the work is about 2^12, not 2^24
+        assert!(collision.evaluations < 1 << 16);
+    }
+
+    #[test]
+    fn test_2() {
+        let collision = distinguished(Algorithm::Sha1, 28, 4, 1);
+        println!("{}", collision);
+
+        assert_ne!(collision.first, collision.second);
+        assert_eq!(
+            first(Algorithm::Sha1, 28, &collision.first),
+            first(Algorithm::Sha1, 28, &collision.second)
+        );
+        assert!(collision.evaluations < 1 << 18);
+        assert_eq!(12.0, expected_work(24).log2().round());
+    }
+
+    #[test]
+    fn test_3() {
+        // with few bits most starts are distinguished already, it must still end
+        for bits in 1..=8 {
+            for seed in 0..16 {
+                let collision = distinguished(Algorithm::Md5, bits, 2, seed);
+                assert_ne!(collision.first, collision.second);
+                assert_eq!(
+                    first(Algorithm::Md5, bits, &collision.first),
+                    first(Algorithm::Md5, bits, &collision.second)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_4() {
+        // with few bits the walk of some digests is a permutation (no collision), the salt must change
+        for algorithm in Algorithm::ALL {
+            for bits in 1..=4 {
+                for seed in 0..4 {
+                    for collision in [
+                        rho(algorithm, bits, seed),
+                        distinguished(algorithm, bits, 2, seed),
+                    ] {
+                        assert_ne!(collision.first, collision.second);
+                        assert_eq!(
+                            first(algorithm, bits, &collision.first),
+                            first(algorithm, bits, &collision.second)
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
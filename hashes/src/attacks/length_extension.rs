@@ -0,0 +1,147 @@
+use crate::md5::{MD5Digest, MD5};
+use crate::sha1::{Sha1, Sha1Digest};
+
+// Length extension attack on Merkle-Damgard hashes (MD5, SHA-1)
+// The output of these hashes is the whole chaining value after the last block.
+// So from mac = H(secret || message) anyone can restore the hash state and keep hashing,
+// without knowing the secret. The forged message is
+//
+//   message || glue padding || extension
+//
+// where the glue padding is the padding finalize added after secret || message.
+// The server calculate H(secret || forged message), which is exactly the state after the glue padding
+// fed with the extension, so the forged mac is accepted. Only the length of the secret is needed,
+// and it can be guessed by trying every length.
+// HMAC (crate::hmac) is not affected, the outer hash hide the chaining value of the inner one.
+
+// Forgery is the result of the attack
+// message is what the attacker send instead of the original message, it does not contain the secret.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Forgery {
+    pub message: Vec<u8>,
+    pub mac: Vec<u8>,
+}
+
+// forged_message function make message || glue padding || extension
+fn forged_message(message: &[u8], glue: &[u8], extension: &[u8]) -> Vec<u8> {
+    let mut forged = Vec::with_capacity(message.len() + glue.len() + extension.len());
+    forged.extend_from_slice(message);
+    forged.extend_from_slice(glue);
+    forged.extend_from_slice(extension);
+    forged
+}
+
+// extend_sha1 function forge the mac of message || glue || extension from mac = SHA1(secret || message)
+pub fn extend_sha1(mac: &[u8; 20], secret_len: usize, message: &[u8], extension: &[u8]) -> Forgery {
+    let original_len = (secret_len + message.len()) as u64;
+    let glue = crate::sha1::padding(original_len);
+
+    // the hash state after secret || message || glue is the mac itself
+    let mut hash = Sha1::from_digest(mac, original_len + glue.len() as u64);
+    hash.update(extension);
+    Forgery {
+        message: forged_message(message, &glue, extension),
+        mac: hash.finalize().digest().to_vec(),
+    }
+}
+
+// extend_md5 function forge the mac of message || glue || extension from mac = MD5(secret || message)
+pub fn extend_md5(mac: &[u8; 16], secret_len: usize, message: &[u8], extension: &[u8]) -> Forgery {
+    let original_len = (secret_len + message.len()) as u64;
+    let glue = crate::md5::padding(original_len);
+
+    // the hash state after secret || message || glue is the mac itself
+    let mut hash = MD5::from_digest(mac, original_len + glue.len() as u64);
+    hash.update(extension);
+    Forgery {
+        message: forged_message(message, &glue, extension),
+        mac: hash.finalize().digest().to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attacks::length_extension::{extend_md5, extend_sha1};
+    use crate::hmac::hmac;
+    use crate::md5::{MD5Digest, MD5};
+    use crate::sha1::{Sha1, Sha1Digest};
+
+    const SECRET: &[u8] = b"server side secret";
+    const MESSAGE: &[u8] = b"user=alice&role=user";
+    const EXTENSION: &[u8] = b"&role=admin";
+
+    // naive_sha1 function is the broken MAC H(secret || message) of the server
+    fn naive_sha1(message: &[u8]) -> [u8; 20] {
+        let mut hash = Sha1::new();
+        hash.update(SECRET);
+        hash.update(message);
+        hash.finalize().digest()
+    }
+
+    fn naive_md5(message: &[u8]) -> [u8; 16] {
+        let mut hash = MD5::new();
+        hash.update(SECRET);
+        hash.update(message);
+        hash.finalize().digest()
+    }
+
+    #[test]
+    fn test_1() {
+        // the attacker know MESSAGE, its mac and the secret length, but not the secret
+        let mac = naive_sha1(MESSAGE);
+        let forgery = extend_sha1(&mac, SECRET.len(), MESSAGE, EXTENSION);
+        println!("{}", crate::hex::encode(&forgery.mac));
+
+        assert!(forgery.message.starts_with(MESSAGE));
+        assert!(forgery.message.ends_with(EXTENSION));
+        // the server accept the forged message
+        assert_eq!(naive_sha1(&forgery.message).to_vec(), forgery.mac);
+    }
+
+    #[test]
+    fn test_2() {
+        let mac = naive_md5(MESSAGE);
+        let forgery = extend_md5(&mac, SECRET.len(), MESSAGE, EXTENSION);
+        println!("{}", crate::hex::encode(&forgery.mac));
+
+        assert_eq!(naive_md5(&forgery.message).to_vec(), forgery.mac);
+    }
+
+    #[test]
+    fn test_3() {
+        // the glue padding of a message that fill the last block need one more block
+        let message = [b'a'; 50];
+        let mac = naive_sha1(&message);
+        let forgery = extend_sha1(&mac, SECRET.len(), &message, EXTENSION);
+
+        assert_eq!(naive_sha1(&forgery.message).to_vec(), forgery.mac);
+    }
+
+    #[test]
+    fn test_4() {
+        // a wrong guess of the secret length give a wrong mac, so the attacker try every length
+        let mac = naive_sha1(MESSAGE);
+        let found: Vec<usize> = (0..64)
+            .filter(|&secret_len| {
+                let forgery = extend_sha1(&mac, secret_len, MESSAGE, EXTENSION);
+                naive_sha1(&forgery.message).to_vec() == forgery.mac
+            })
+            .collect();
+
+        assert_eq!(vec![SECRET.len()], found);
+    }
+
+    #[test]
+    fn test_5() {
+        // HMAC is not broken, the same attack on a HMAC tag give a mac the server reject
+        let mac = hmac::<Sha1>(SECRET, MESSAGE);
+        let forgery = extend_sha1(&mac, 64 + SECRET.len(), MESSAGE, EXTENSION);
+        assert_ne!(hmac::<Sha1>(SECRET, &forgery.message).to_vec(), forgery.mac);
+
+        let mac = hmac::<MD5>(SECRET, MESSAGE);
+        for secret_len in 0..128 {
+            let forgery = extend_md5(&mac, secret_len, MESSAGE, EXTENSION);
+            assert_ne!(hmac::<MD5>(SECRET, &forgery.message).to_vec(), forgery.mac);
+        }
+    }
+}
@@ -0,0 +1,853 @@
+use crate::md5::{self, MD5Digest, MD5};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+// Identical-prefix MD5 collisions, the attack of Wang and Yu (EUROCRYPT 2005)
+// Two messages of two blocks each are searched, they differ only in 3 words of each block:
+//
+//   block 1: m4 + 2^31, m11 + 2^15, m14 + 2^31
+//   block 2: m4 + 2^31, m11 - 2^15, m14 + 2^31
+//
+// After block 1 the chaining values differ by (2^31, 2^31+2^25, 2^31+2^25, 2^31+2^25) (near-collision),
+// block 2 cancel this difference, so after block 2 both chaining values are the same
+// and anything appended after the blocks keep the collision.
+//
+// The search works on the states Q1..Q64, Q_{t+1} is the output of step t of the compression.
+// Q-3, Q0, Q-1, Q-2 are a, b, c, d of the chaining value. q[t + 3] hold Q_t in the arrays below.
+// The differential path say, for each Q_t, which bits are different between the two messages
+// and which conditions the bits of the first message must fulfill to make the difference go on.
+// Q1..Q16 are chosen freely (message modification): the message words are computed back from them,
+// so every condition of the first round hold for free. The rest is tried (with tunnels, see find_block).
+//
+// Path notation, one char per bit, bit 31 first:
+//   '.' no condition, '0' / '1' the bit value, '+' 0 -> 1 and '-' 1 -> 0 (the bit differ),
+//   '^' equal to the same bit of Q_{t-1}, '!' not equal to it, 'm' / '#' equal / not equal to Q_{t-2}
+// The sign of a difference on bit 31 does not matter, 2^31 = -2^31.
+// From Q17 only the differences are written, the conditions there are checked by computing both messages.
+// The paths were read off the colliding pair of Wang and Yu, with the tunnel conditions added.
+// Block 1 also has extra conditions on Q17 and Q18 (and the sign of bit 31 of Q17..Q19) in the spirit of
+// Stevens ("Fast Collision Attack on MD5", 2006): without them step 19 always fail for 7 choices of Q17
+// out of 8, whatever Q1 is, so they are checked as soon as Q17 is chosen.
+
+// message differences of block 1 and block 2, added to the words of the first message
+const DIFFERENCE_1: [u32; 16] = difference(1 << 15);
+const DIFFERENCE_2: [u32; 16] = difference(0xffff_8000); // -2^15
+
+const fn difference(m11: u32) -> [u32; 16] {
+    let mut m = [0u32; 16];
+    m[4] = 1 << 31;
+    m[11] = m11;
+    m[14] = 1 << 31;
+    m
+}
+
+// PATH_1 is the path of block 1, from the same chaining value to the near-collision
+#[rustfmt::skip]
+const PATH_1: [&str; 68] = [
+    "................................", // Q-3
+    "................................", // Q-2
+    "................................", // Q-1
+    "................................", // Q0
+    "................................", // Q1
+    "................................", // Q2
+    "......^.....0.......0....0......", // Q3
+    "1.......0^^^1^^^^^^^1^^^^0......", // Q4
+    "1...1.0.0-++++++++++++++++1..1.1", // Q5
+    "+^^^0^1^+1111111101111000-0^^0^1", // Q6
+    "++++++---11111101111-+++++-+++++", // Q7
+    "00000001-..-+++-+.0.01010100000+", // Q8
+    "-1111011...100000.1^111-++1111+-", // Q9
+    "+1......000111111.+-...001....00", // Q10
+    "++...0.0111.00011^00.0.011....10", // Q11
+    "+0....^^....-++++++1...-+.......", // Q12
+    "+1....+-....1111111....00...1...", // Q13
+    "+.0...00....1011111....11...1...", // Q14
+    "+.1...01........-...........+...", // Q15
+    "+.-.............................", // Q16
+    "+.............0.................", // Q17
+    "+.............1.................", // Q18
+    "+.............+.................", // Q19
+    "+...............................", // Q20
+    "+...............................", // Q21
+    "+...............................", // Q22
+    "................................", // Q23
+    "................................", // Q24
+    "................................", // Q25
+    "................................", // Q26
+    "................................", // Q27
+    "................................", // Q28
+    "................................", // Q29
+    "................................", // Q30
+    "................................", // Q31
+    "................................", // Q32
+    "................................", // Q33
+    "................................", // Q34
+    "+...............................", // Q35
+    "+...............................", // Q36
+    "+...............................", // Q37
+    "-...............................", // Q38
+    "-...............................", // Q39
+    "-...............................", // Q40
+    "+...............................", // Q41
+    "-...............................", // Q42
+    "-...............................", // Q43
+    "-...............................", // Q44
+    "+...............................", // Q45
+    "+...............................", // Q46
+    "-...............................", // Q47
+    "+...............................", // Q48
+    "-...............................", // Q49
+    "-...............................", // Q50
+    "-...............................", // Q51
+    "-...............................", // Q52
+    "-...............................", // Q53
+    "-...............................", // Q54
+    "-...............................", // Q55
+    "-...............................", // Q56
+    "-...............................", // Q57
+    "-...............................", // Q58
+    "-...............................", // Q59
+    "+...............................", // Q60
+    "-...............................", // Q61
+    "+.....+.........................", // Q62
+    "-.....+.........................", // Q63
+    "+.....+.........................", // Q64
+];
+
+// PATH_2 is the path of block 2, from the near-collision to the collision
+#[rustfmt::skip]
+const PATH_2: [&str; 68] = [
+    "+...............................", // Q-3
+    "+.....+.........................", // Q-2
+    "+....+-.........................", // Q-1
+    "+....0+...................0.....", // Q0
+    "-...01+...1.........0.....0.....", // Q1
+    "-^^^11+...0^^^^^...^1...^^+..00.", // Q2
+    "-+-----^^^+-----^^^+-..1+--^^11.", // Q3
+    "-0111+-...000100...00^^000+-+++^", // Q4
+    "+10010.000101111000+---++-01000+", // Q5
+    "+..0010111-+..-+1110110001010110", // Q6
+    "-..-+11^^.00..01^..111-+++.....1", // Q7
+    "-..00-+++.11..-+-.....1111....^0", // Q8
+    "-..111000.....010..^^^+---....+-", // Q9
+    "-00..1111000.0111..+..1111000000", // Q10
+    "-11......111^1011^^0001111111111", // Q11
+    "-^^^^^^^....-++++++1....-.......", // Q12
+    "++------....1111111.....0...1...", // Q13
+    "+1000000....1011111.....1...1...", // Q14
+    "+1111101........+...........+...", // Q15
+    "+.-.............................", // Q16
+    "+...............................", // Q17
+    "+...............................", // Q18
+    "+.............+.................", // Q19
+    "+...............................", // Q20
+    "+...............................", // Q21
+    "+...............................", // Q22
+    "................................", // Q23
+    "................................", // Q24
+    "................................", // Q25
+    "................................", // Q26
+    "................................", // Q27
+    "................................", // Q28
+    "................................", // Q29
+    "................................", // Q30
+    "................................", // Q31
+    "................................", // Q32
+    "................................", // Q33
+    "................................", // Q34
+    "-...............................", // Q35
+    "-...............................", // Q36
+    "+...............................", // Q37
+    "-...............................", // Q38
+    "-...............................", // Q39
+    "+...............................", // Q40
+    "+...............................", // Q41
+    "-...............................", // Q42
+    "-...............................", // Q43
+    "+...............................", // Q44
+    "-...............................", // Q45
+    "+...............................", // Q46
+    "+...............................", // Q47
+    "+...............................", // Q48
+    "+...............................", // Q49
+    "-...............................", // Q50
+    "+...............................", // Q51
+    "-...............................", // Q52
+    "+...............................", // Q53
+    "-...............................", // Q54
+    "+...............................", // Q55
+    "-...............................", // Q56
+    "+...............................", // Q57
+    "-...............................", // Q58
+    "+...............................", // Q59
+    "+...............................", // Q60
+    "+...............................", // Q61
+    "+.....-.........................", // Q62
+    "+.....-.........................", // Q63
+    "+..-+++.........................", // Q64
+];
+
+// Row is one line of a path as bit masks
+#[derive(Clone, Copy, Default)]
+struct Row {
+    diff: u32,
+    zero: u32,
+    one: u32,
+    equal: u32,
+    differ: u32,
+    equal2: u32,
+    differ2: u32,
+}
+
+impl Row {
+    // parse function read a path, the row t + 3 is Q_t
+    fn parse(path: &[&str; 68]) -> [Row; 68] {
+        let mut rows = [Row::default(); 68];
+        for (row, line) in rows.iter_mut().zip(path.iter()) {
+            for (i, c) in line.bytes().rev().enumerate() {
+                let bit = 1u32 << i;
+                match c {
+                    b'+' => {
+                        row.diff |= bit;
+                        row.zero |= bit;
+                    }
+                    b'-' => {
+                        row.diff |= bit;
+                        row.one |= bit;
+                    }
+                    b'0' => row.zero |= bit,
+                    b'1' => row.one |= bit,
+                    b'^' => row.equal |= bit,
+                    b'!' => row.differ |= bit,
+                    b'm' => row.equal2 |= bit,
+                    b'#' => row.differ2 |= bit,
+                    _ => {}
+                }
+            }
+        }
+        rows
+    }
+
+    // fixed method return the bits which have a condition
+    fn fixed(&self) -> u32 {
+        self.zero | self.one | self.equal | self.differ | self.equal2 | self.differ2
+    }
+
+    // modular method return the difference as a number, Q' - Q
+    fn modular(&self) -> u32 {
+        (self.diff & self.zero).wrapping_sub(self.diff & self.one)
+    }
+
+    // complement method swap the conditions 0 and 1 (and so + and -) of bit 31
+    fn complement(&mut self) {
+        let (zero, one) = (self.zero & 1 << 31, self.one & 1 << 31);
+        self.zero ^= zero | one;
+        self.one ^= zero | one;
+    }
+
+    // sample method return random bits fulfilling the conditions
+    // previous and previous2 are Q_{t-1} and Q_{t-2}
+    fn sample(&self, random: u32, previous: u32, previous2: u32) -> u32 {
+        (random & !self.fixed())
+            | self.one
+            | (previous & self.equal)
+            | (!previous & self.differ)
+            | (previous2 & self.equal2)
+            | (!previous2 & self.differ2)
+    }
+
+    // holds method check the conditions on the bits of the first message
+    fn holds(&self, q: u32, previous: u32, previous2: u32) -> bool {
+        q & (self.zero | self.one) == self.one
+            && (q ^ previous) & self.equal == 0
+            && !(q ^ previous) & self.differ == 0
+            && (q ^ previous2) & self.equal2 == 0
+            && !(q ^ previous2) & self.differ2 == 0
+    }
+
+    // follows method check that both messages differ as the path say
+    // the sign of bit 31 is not checked
+    #[inline(always)]
+    fn follows(&self, q: u32, q2: u32) -> bool {
+        q ^ q2 == self.diff && q & self.diff & 0x7fff_ffff == self.one & self.diff & 0x7fff_ffff
+    }
+}
+
+// Rng is xorshift64, the same seed give the same collision
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 32) as u32
+    }
+}
+
+// round function run step t of md5::MD5 with message word m and rotation s
+#[inline(always)]
+fn round(t: usize, q: &[u32; 68], m: u32, s: u32) -> u32 {
+    let (a, b, c, d) = (q[t], q[t + 3], q[t + 2], q[t + 1]);
+    match t / 16 {
+        0 => md5::f(a, b, c, d, m, s, md5::T[t + 1]),
+        1 => md5::g(a, b, c, d, m, s, md5::T[t + 1]),
+        2 => md5::h(a, b, c, d, m, s, md5::T[t + 1]),
+        _ => md5::i(a, b, c, d, m, s, md5::T[t + 1]),
+    }
+}
+
+// step function return Q_{t+1}
+#[inline(always)]
+fn step(t: usize, q: &[u32; 68], m: u32) -> u32 {
+    round(t, q, m, md5::SHIFT[t])
+}
+
+// reverse function return the message word which make step t give Q_{t+1}
+// with a zero word and no rotation the step is only F(b, c, d) + a + T + b
+#[inline(always)]
+fn reverse(t: usize, q: &[u32; 68]) -> u32 {
+    let b = q[t + 3];
+    let sum = round(t, q, 0, 0).wrapping_sub(b);
+    q[t + 4]
+        .wrapping_sub(b)
+        .rotate_right(md5::SHIFT[t])
+        .wrapping_sub(sum)
+}
+
+// states function put a chaining value in Q-3, Q0, Q-1, Q-2
+fn states(ihv: &[u32; 4]) -> [u32; 68] {
+    let mut q = [0u32; 68];
+    q[0] = ihv[0];
+    q[1] = ihv[3];
+    q[2] = ihv[2];
+    q[3] = ihv[1];
+    q
+}
+
+// second_message function add the message difference to the words of a block
+fn second_message(m: &[u32; 16], difference: &[u32; 16]) -> [u32; 16] {
+    let mut m2 = [0u32; 16];
+    for (word, (a, b)) in m2.iter_mut().zip(m.iter().zip(difference.iter())) {
+        *word = a.wrapping_add(*b);
+    }
+    m2
+}
+
+// to_bytes function return a block as md5 read it, words are little endian
+fn to_bytes(m: &[u32; 16]) -> [u8; 64] {
+    let mut bytes = [0u8; 64];
+    for (chunk, word) in bytes.chunks_mut(4).zip(m.iter()) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    bytes
+}
+
+// chain function return the chaining value after the block m, with the compression of md5::MD5
+fn chain(ihv: &[u32; 4], m: &[u32; 16]) -> [u32; 4] {
+    let mut hash = *ihv;
+    md5::compress(&mut hash, &to_bytes(m));
+    hash
+}
+
+// starts function check that the chaining values after block 1 are the Q-3..Q0 of the path of block 2
+// Q-3 is only added in step 0, so the sign of its bit 31 does not matter
+fn starts(rows: &[Row; 68], ihv: &[u32; 4], ihv2: &[u32; 4]) -> bool {
+    let (q, q2) = (states(ihv), states(ihv2));
+    let a = (q[0] & 0x7fff_ffff) | (rows[0].one & 0x8000_0000);
+    rows[0].follows(q[0], q2[0])
+        && rows[0].holds(a, 0, 0)
+        && rows[1].follows(q[1], q2[1])
+        && rows[1].holds(q[1], q[0], 0)
+        && (2..4).all(|r| rows[r].follows(q[r], q2[r]) && rows[r].holds(q[r], q[r - 1], q[r - 2]))
+}
+
+// start function return the path of block 2 for the chaining values after block 1, None when they do not fit
+// Complementing bit 31 of every state of the first round keep Q_{t+1} - Q_t and the conditions between states,
+// so when bit 31 of the chaining value is the other way, the path with bit 31 complemented is used.
+fn start(path: &[&str; 68], ihv: &[u32; 4], ihv2: &[u32; 4]) -> Option<[Row; 68]> {
+    let mut rows = Row::parse(path);
+    if (states(ihv)[3] ^ rows[3].one) >> 31 == 1 {
+        for row in rows[..20].iter_mut() {
+            row.complement();
+        }
+    }
+    starts(&rows, ihv, ihv2).then_some(rows)
+}
+
+// Tunnels (Klima, "Tunnels in Hash Functions", 2006) are bits of Q_t which can be flipped
+// without changing the steps before a late one, the words are computed again instead.
+//   Q9 with Q10 = 0 and Q11 = 1: F of steps 10 and 11 does not see the bit, m8, m9 and m12 change,
+//     the first of them used again is m9 at step 24
+//   Q4 with Q3 = Q2, Q5 = 0 and Q6 = 1: m3, m4 and m7 change, m4 is used at step 23
+//   Q10 with Q9 = Q8 and Q11 = 0: m9, m10, m12 and m13 change, m10 is used at step 21
+// So one success up to step 20 gives 2^(tunnel bits) tries of the rest.
+// Tunnel is what one tunnel touch: its state Q_t, the words computed again,
+// the steps of the second message to check again and the first step of round 2 using one of the words
+struct Tunnel {
+    q: usize,
+    words: &'static [usize],
+    steps: (usize, usize),
+    from: usize,
+}
+
+const TUNNELS: [Tunnel; 3] = [
+    Tunnel {
+        q: 9,
+        words: &[8, 9, 12],
+        steps: (8, 13),
+        from: 24,
+    },
+    Tunnel {
+        q: 4,
+        words: &[3, 4, 7],
+        steps: (3, 8),
+        from: 23,
+    },
+    Tunnel {
+        q: 10,
+        words: &[9, 10, 12, 13],
+        steps: (9, 14),
+        from: 21,
+    },
+];
+
+// Block is the search of one block of the collision
+struct Block {
+    rows: [Row; 68],
+    difference: [u32; 16],
+    // states of the first and the second message, q[t + 3] is Q_t
+    q: [u32; 68],
+    q2: [u32; 68],
+    // words of the first message, the second one is m + difference
+    m: [u32; 16],
+    // next is the path of the following block, its start must fit the chaining values after this one
+    next: Option<&'static [&'static str; 68]>,
+}
+
+impl Block {
+    fn new(rows: [Row; 68], difference: [u32; 16], ihv: &[u32; 4], ihv2: &[u32; 4]) -> Self {
+        Block {
+            rows,
+            difference,
+            q: states(ihv),
+            q2: states(ihv2),
+            m: [0; 16],
+            next: None,
+        }
+    }
+
+    // fits method check the chaining values after the block against the start of the next path
+    // They are the initial states plus Q61..Q64, so they are known as soon as last succeed
+    // and a block which does not fit is skipped without leaving the tunnels (the IHV conditions of Stevens).
+    fn fits(&self) -> bool {
+        let Some(next) = self.next else {
+            return true;
+        };
+        let chaining = |q: &[u32; 68]| {
+            [
+                q[0].wrapping_add(q[64]),
+                q[3].wrapping_add(q[67]),
+                q[2].wrapping_add(q[66]),
+                q[1].wrapping_add(q[65]),
+            ]
+        };
+        start(next, &chaining(&self.q), &chaining(&self.q2)).is_some()
+    }
+
+    // second method run step t on the second message and check it against the path
+    #[inline(always)]
+    fn second(&mut self, t: usize) -> bool {
+        let word = self.m[md5::WORD[t]].wrapping_add(self.difference[md5::WORD[t]]);
+        self.q2[t + 4] = step(t, &self.q2, word);
+        self.rows[t + 4].follows(self.q[t + 4], self.q2[t + 4])
+    }
+
+    // both method run step t on both messages
+    #[inline(always)]
+    fn both(&mut self, t: usize) -> bool {
+        self.q[t + 4] = step(t, &self.q, self.m[md5::WORD[t]]);
+        self.second(t)
+    }
+
+    // last method run step 63, Q64 is added to the chaining value so only its modular difference matter
+    fn last(&mut self) -> bool {
+        self.q[67] = step(63, &self.q, self.m[md5::WORD[63]]);
+        self.q2[67] = step(
+            63,
+            &self.q2,
+            self.m[md5::WORD[63]].wrapping_add(self.difference[md5::WORD[63]]),
+        );
+        (self.q2[67].wrapping_sub(self.q[67]) ^ self.rows[67].modular()) & 0x7fff_ffff == 0
+    }
+
+    // first_round method sample Q1..Q16 and compute m0..m15 back from them
+    // the second message is checked at each step, Q_t is sampled again when it does not follow
+    fn first_round(&mut self, rng: &mut Rng) -> bool {
+        for t in 1..=16 {
+            let mut tries = 0;
+            loop {
+                self.q[t + 3] =
+                    self.rows[t + 3].sample(rng.next_u32(), self.q[t + 2], self.q[t + 1]);
+                if t < 4 {
+                    break;
+                }
+                if t == 4 {
+                    for u in 0..3 {
+                        self.m[u] = reverse(u, &self.q);
+                        if !self.second(u) {
+                            return false;
+                        }
+                    }
+                }
+                self.m[t - 1] = reverse(t - 1, &self.q);
+                if self.second(t - 1) {
+                    break;
+                }
+                tries += 1;
+                if tries == 64 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    // modify_q1 method sample again the bits of Q1 which do not meet a difference of the second message
+    // m0..m4 change, steps 16..23 are tried
+    fn modify_q1(&mut self, base: u32, free: u32, rng: &mut Rng) -> bool {
+        self.q[4] = (base & !free) | (rng.next_u32() & free);
+        self.m[0] = reverse(0, &self.q);
+        self.m[1] = reverse(1, &self.q);
+        if !(16..20).all(|t| self.both(t)) {
+            return false;
+        }
+        for t in 2..5 {
+            self.m[t] = reverse(t, &self.q);
+        }
+        (0..5).all(|t| self.second(t)) && (20..24).all(|t| self.both(t))
+    }
+
+    // modify_q17 method choose Q17 when Q1 and Q2 are free (block 1), it fix m1 and so steps 16..18
+    fn modify_q17(&mut self, rng: &mut Rng) -> bool {
+        self.q[20] = self.rows[20].sample(rng.next_u32(), self.q[19], self.q[18]);
+        self.m[1] = reverse(16, &self.q);
+        self.second(16)
+            && (17..19).all(|t| self.both(t) && self.rows[t + 4].holds(self.q[t + 4], 0, 0))
+    }
+
+    // modify_q1_q2 method choose Q1, Q2 follows from m1, m0 and m2..m5 change, steps 19..23 are tried
+    fn modify_q1_q2(&mut self, rng: &mut Rng) -> bool {
+        self.q[4] = rng.next_u32();
+        self.q[5] = step(1, &self.q, self.m[1]);
+        if !(5..8).all(|r| self.rows[r].holds(self.q[r], self.q[r - 1], self.q[r - 2])) {
+            return false;
+        }
+        // each word is computed just before the step which use it first
+        self.m[0] = reverse(0, &self.q);
+        if !self.both(19) {
+            return false;
+        }
+        self.m[5] = reverse(5, &self.q);
+        if !(20..23).all(|t| self.both(t)) {
+            return false;
+        }
+        for t in 2..5 {
+            self.m[t] = reverse(t, &self.q);
+        }
+        self.both(23) && (0..6).all(|t| self.second(t))
+    }
+
+    // tunnels method return the tunnel bits of Q9, Q4 and Q10 the path allow
+    fn tunnels(&self) -> [u32; 3] {
+        let r = |t: usize| self.rows[t + 3];
+        let constant = |r: Row| r.zero | r.one;
+        // Q_t = Q_{t-1} by a condition, or by both constants
+        let equal = |t: usize| {
+            r(t).equal | (constant(r(t)) & constant(r(t - 1)) & !(r(t).one ^ r(t - 1).one))
+        };
+        let q9 = !r(9).fixed() & r(10).zero & r(11).one & !r(10).diff & !r(11).diff;
+        let q4 = !r(4).fixed() & r(5).zero & r(6).one & !r(5).diff & !r(6).diff & equal(3);
+        let q10 = !r(10).fixed()
+            & !q9
+            & r(11).zero
+            & !r(11).diff
+            & !(r(12).equal2 | r(12).differ2)
+            & equal(9);
+        [q9, q4, q10]
+    }
+
+    // tunnel method try every value of the tunnel bits and run the rest of the steps
+    // The values are taken in Gray code order, each try flip one bit and only the words
+    // and the steps of the second message around it are computed again.
+    fn tunnel(&mut self, masks: &[u32; 3]) -> bool {
+        let mut bits = Vec::new();
+        for (k, mask) in masks.iter().enumerate() {
+            for i in 0..32 {
+                if mask >> i & 1 == 1 {
+                    bits.push((k, 1u32 << i));
+                }
+            }
+        }
+        let base = TUNNELS.map(|tunnel| self.q[tunnel.q + 3]);
+        // dirty has a bit for each tunnel whose steps of the second message do not follow yet
+        let mut dirty = 0;
+        // from is the first step of round 2 not computed with the current words
+        let mut from = 24;
+        for i in 0..1u64 << bits.len() {
+            if i > 0 {
+                let (k, bit) = bits[i.trailing_zeros() as usize];
+                let tunnel = &TUNNELS[k];
+                self.q[tunnel.q + 3] ^= bit;
+                for &word in tunnel.words {
+                    self.m[word] = reverse(word, &self.q);
+                }
+                dirty |= 1 << k;
+                from = from.min(tunnel.from);
+
+                let (first, last) =
+                    (0..3)
+                        .filter(|k| dirty >> k & 1 == 1)
+                        .fold((14, 3), |(first, last), k| {
+                            (first.min(TUNNELS[k].steps.0), last.max(TUNNELS[k].steps.1))
+                        });
+                if !(first..last).all(|t| self.second(t)) {
+                    // put back the states the path expect, the next try start from them
+                    for t in first..last {
+                        self.q2[t + 4] = self.q[t + 4] ^ self.rows[t + 4].diff;
+                    }
+                    continue;
+                }
+                dirty = 0;
+            }
+            let mut t = from;
+            while t < 63 && self.both(t) {
+                t += 1;
+            }
+            if t == 63 && self.last() && self.fits() {
+                return true;
+            }
+            from = t.min(24);
+        }
+        // back to the state before, the loops of the caller go on from it
+        for (tunnel, q) in TUNNELS.iter().zip(base) {
+            self.q[tunnel.q + 3] = q;
+        }
+        for t in 3..14 {
+            self.m[t] = reverse(t, &self.q);
+            self.q2[t + 4] = self.q[t + 4] ^ self.rows[t + 4].diff;
+        }
+        false
+    }
+
+    // search method return the words of the first message of a block which follows the whole path
+    // None is returned after tries samples of the first round without success.
+    fn search(&mut self, rng: &mut Rng, tries: u64) -> Option<[u32; 16]> {
+        let masks = self.tunnels();
+        // block 1 start from the same chaining value and has no condition on Q1 and Q2
+        let free = (0..6).all(|r| self.rows[r].diff == 0)
+            && self.rows[4].fixed() == 0
+            && self.rows[5].fixed() == 0;
+        for _ in 0..tries {
+            if !self.first_round(rng) {
+                continue;
+            }
+            if free {
+                for _ in 0..64 {
+                    if self.modify_q17(rng) {
+                        for _ in 0..1 << 12 {
+                            if self.modify_q1_q2(rng) && self.tunnel(&masks) {
+                                return Some(self.m);
+                            }
+                        }
+                        break;
+                    }
+                }
+            } else {
+                let base = self.q[4];
+                let bits = !self.rows[4].fixed()
+                    & !(self.rows[2].diff
+                        | self.rows[3].diff
+                        | self.rows[5].diff
+                        | self.rows[6].diff);
+                for _ in 0..1 << 12 {
+                    if self.modify_q1(base, bits, rng) && self.tunnel(&masks) {
+                        return Some(self.m);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+// ATTEMPT is the number of first round samples of one attempt at block 1, about a tenth of a second
+const ATTEMPT: u64 = 1 << 14;
+
+// first_block function search block 1 on every core, it return the block and the random generator after it
+// The search is cut in attempts 0, 1, 2, ... each with its own generator, and the first attempt
+// which succeed win, even when a later one finish sooner on another thread.
+// So the result only depend on the seed, not on the number of threads.
+fn first_block(iv: &[u32; 4], seed: u64) -> ([u32; 16], Rng) {
+    let rows = Row::parse(&PATH_1);
+    let next = AtomicU64::new(0);
+    let found: Mutex<Option<(u64, [u32; 16], Rng)>> = Mutex::new(None);
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let attempt = next.fetch_add(1, Ordering::Relaxed);
+                // stop when an earlier attempt already succeeded
+                if found
+                    .lock()
+                    .unwrap()
+                    .as_ref()
+                    .is_some_and(|f| f.0 < attempt)
+                {
+                    return;
+                }
+                let mut rng = Rng::new(seed ^ attempt.rotate_right(16));
+                let mut block = Block::new(rows, DIFFERENCE_1, iv, iv);
+                block.next = Some(&PATH_2);
+                if let Some(m) = block.search(&mut rng, ATTEMPT) {
+                    let mut found = found.lock().unwrap();
+                    if found.as_ref().is_none_or(|f| attempt < f.0) {
+                        *found = Some((attempt, m, rng));
+                    }
+                }
+            });
+        }
+    });
+    let (_, m, rng) = found.into_inner().unwrap().unwrap();
+    (m, rng)
+}
+
+// collision_blocks function return two different pairs of blocks, which give the same chaining value from ihv
+// ihv is the chaining value before the blocks, MD5::digest of a hash fed with whole blocks only.
+// Only a block 1 whose near-collision fit the start of the path of block 2 is kept (about 1 in 64),
+// that is most of the work. Block 2 takes well under a second.
+pub fn collision_blocks(ihv: &[u8; 16], seed: u64) -> ([u8; 128], [u8; 128]) {
+    let mut iv = [0u32; 4];
+    for (word, bytes) in iv.iter_mut().zip(ihv.chunks(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    let (m1, mut rng) = first_block(&iv, seed);
+    let near = chain(&iv, &m1);
+    let near2 = chain(&iv, &second_message(&m1, &DIFFERENCE_1));
+    let rows_2 = start(&PATH_2, &near, &near2).unwrap();
+    let m2 = Block::new(rows_2, DIFFERENCE_2, &near, &near2)
+        .search(&mut rng, u64::MAX)
+        .unwrap();
+
+    let mut first = [0u8; 128];
+    let mut second = [0u8; 128];
+    first[..64].copy_from_slice(&to_bytes(&m1));
+    first[64..].copy_from_slice(&to_bytes(&m2));
+    second[..64].copy_from_slice(&to_bytes(&second_message(&m1, &DIFFERENCE_1)));
+    second[64..].copy_from_slice(&to_bytes(&second_message(&m2, &DIFFERENCE_2)));
+    (first, second)
+}
+
+// collide function return two different messages with the same MD5, both start with prefix
+// The prefix is padded with zeros to whole blocks, then come the colliding blocks.
+// Anything appended to both messages keep the collision. The same seed give the same messages on any machine.
+// It takes about two minutes on one core with a release build, less with more cores
+// (the search is random, some seeds take a few times longer and some are much faster).
+pub fn collide(prefix: &[u8], seed: u64) -> (Vec<u8>, Vec<u8>) {
+    let mut first = prefix.to_vec();
+    first.resize(prefix.len().next_multiple_of(MD5::BLOCK_SIZE), 0);
+    let mut hash = MD5::new();
+    hash.update(&first);
+    let (block, block2) = collision_blocks(&hash.digest(), seed);
+
+    let mut second = first.clone();
+    first.extend_from_slice(&block);
+    second.extend_from_slice(&block2);
+    (first, second)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attacks::md5_collision::*;
+    use crate::md5::{MD5Digest, MD5};
+
+    // the colliding pair published by Wang and Yu, the second message is the first one + the differences
+    const WANG: &str = "d131dd02c5e6eec4693d9a0698aff95c2fcab58712467eab4004583eb8fb7f89\
+                        55ad340609f4b30283e488832571415a085125e8f7cdc99fd91dbdf280373c5b\
+                        d8823e3156348f5bae6dacd436c919c6dd53e2b487da03fd02396306d248cda0\
+                        e99f33420f577ee8ce54b67080a80d1ec69821bcb6a8839396f9652b6ff72a70";
+    const IV: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    fn words(bytes: &[u8]) -> [u32; 16] {
+        let mut m = [0u32; 16];
+        for (word, chunk) in m.iter_mut().zip(bytes.chunks(4)) {
+            *word = u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+        m
+    }
+
+    fn md5(message: &[u8]) -> String {
+        let mut hash = MD5::new();
+        hash.update(message);
+        format!("{}", hash.finalize())
+    }
+
+    // follows function run every step of the block m and check it against the path
+    fn follows(
+        path: &[&str; 68],
+        difference: [u32; 16],
+        ihv: &[u32; 4],
+        ihv2: &[u32; 4],
+        m: [u32; 16],
+    ) -> bool {
+        let mut block = Block::new(Row::parse(path), difference, ihv, ihv2);
+        block.m = m;
+        (0..63).all(|t| block.both(t))
+            && block.last()
+            && (20..23).all(|r| block.rows[r].holds(block.q[r], 0, 0))
+    }
+
+    #[test]
+    fn test_1() {
+        // the published pair follows both paths
+        let message = crate::hex::decode(WANG).unwrap();
+        let m1 = words(&message[..64]);
+        let m2 = words(&message[64..]);
+        assert!(follows(&PATH_1, DIFFERENCE_1, &IV, &IV, m1));
+
+        let near = chain(&IV, &m1);
+        let near2 = chain(&IV, &second_message(&m1, &DIFFERENCE_1));
+        assert!(start(&PATH_2, &near, &near2).is_some());
+        assert!(follows(&PATH_2, DIFFERENCE_2, &near, &near2, m2));
+
+        let mut message2 = to_bytes(&second_message(&m1, &DIFFERENCE_1)).to_vec();
+        message2.extend_from_slice(&to_bytes(&second_message(&m2, &DIFFERENCE_2)));
+        assert_ne!(message, message2);
+        assert_eq!("79054025255fb1a26e4bc422aef54eb4", md5(&message));
+        assert_eq!("79054025255fb1a26e4bc422aef54eb4", md5(&message2));
+    }
+
+    #[test]
+    fn test_2() {
+        // a new block 2 after the block 1 of Wang and Yu
+        let message = crate::hex::decode(WANG).unwrap();
+        let m1 = words(&message[..64]);
+        let near = chain(&IV, &m1);
+        let near2 = chain(&IV, &second_message(&m1, &DIFFERENCE_1));
+        let rows = start(&PATH_2, &near, &near2).unwrap();
+        let m2 = Block::new(rows, DIFFERENCE_2, &near, &near2)
+            .search(&mut Rng::new(20), u64::MAX)
+            .unwrap();
+        let m2_ = second_message(&m2, &DIFFERENCE_2);
+        println!("{:08x?}", m2);
+
+        assert_ne!(words(&message[64..]), m2);
+        assert_ne!(m2, m2_);
+        assert!(follows(&PATH_2, DIFFERENCE_2, &near, &near2, m2));
+        assert_eq!(chain(&near, &m2), chain(&near2, &m2_));
+    }
+}
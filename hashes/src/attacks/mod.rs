@@ -0,0 +1,6 @@
+// Attacks on the hash functions of this crate, for learning how they break
+// Nothing here is needed to use the hashes, it show why MD5 and SHA-1 must not be used naively.
+pub mod birthday;
+pub mod length_extension;
+pub mod md5_collision;
+pub mod rainbow;
@@ -0,0 +1,390 @@
+use crate::Algorithm;
+use std::io::{self, Read, Write};
+
+// Rainbow tables (Oechslin, 2003) for unsalted password hashes
+// A chain start from a password, hash it, reduce the digest to another password, hash it again, ...
+// Only the first and the last password of each chain are stored. To look up a digest, it is reduced
+// and hashed as if it were at each column of a chain, and when the end matches a stored end,
+// the chain is computed again from its start to find the password.
+// The reduce function of each column is different (it add the column), so two chains which meet
+// in different columns do not merge, this is the "rainbow".
+//
+// A table is built once for a hash and a password space and then cracks every unsalted digest of it,
+// so the same password give the same digest for every user. A random salt per user, hashed with
+// the password, would need one table for each salt, which is why passwords must be salted
+// (and hashed with a slow function, not MD5 or SHA-1 alone).
+
+// Space is the set of passwords of 1 to max_length characters of the charset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Space {
+    pub charset: Vec<u8>,
+    pub max_length: usize,
+}
+
+impl Space {
+    pub fn new(charset: &[u8], max_length: usize) -> Self {
+        assert!(!charset.is_empty(), "charset must not be empty");
+        assert!(max_length >= 1, "max_length must be at least 1");
+        let space = Space {
+            charset: charset.to_vec(),
+            max_length,
+        };
+        assert!(
+            space.checked_size().is_some(),
+            "the password space must fit in 64 bits"
+        );
+        space
+    }
+
+    // size method return the number of passwords
+    pub fn size(&self) -> u64 {
+        self.checked_size().unwrap()
+    }
+
+    fn checked_size(&self) -> Option<u64> {
+        (1..=self.max_length).try_fold(0u64, |total, length| {
+            total.checked_add((self.charset.len() as u64).checked_pow(length as u32)?)
+        })
+    }
+
+    // password method return the password of an index, the short ones first
+    pub fn password(&self, mut index: u64) -> Vec<u8> {
+        let base = self.charset.len() as u64;
+        let mut length = 1;
+        while index >= base.pow(length as u32) {
+            index -= base.pow(length as u32);
+            length += 1;
+        }
+        let mut password = vec![0u8; length];
+        for c in password.iter_mut().rev() {
+            *c = self.charset[(index % base) as usize];
+            index /= base;
+        }
+        password
+    }
+}
+
+// Table is a rainbow table, the chains are sorted by their end
+pub struct Table {
+    pub algorithm: Algorithm,
+    pub space: Space,
+    pub chain_length: usize,
+    // (start, end) of each chain as indexes of passwords
+    chains: Vec<(u64, u64)>,
+}
+
+// invalid function make the error for a broken table file
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("rainbow table: {}", message),
+    )
+}
+
+impl Table {
+    // reduce method turn the digest at a column into the index of a password
+    fn reduce(&self, digest: &[u8], column: usize) -> u64 {
+        let mut first = [0u8; 8];
+        let len = digest.len().min(8);
+        first[..len].copy_from_slice(&digest[..len]);
+        u64::from_le_bytes(first).wrapping_add(column as u64) % self.space.size()
+    }
+
+    // walk method hash and reduce from the index at column from to column to
+    fn walk(&self, mut index: u64, from: usize, to: usize) -> u64 {
+        for column in from..to {
+            let digest = self.algorithm.digest(&self.space.password(index));
+            index = self.reduce(&digest, column);
+        }
+        index
+    }
+
+    // generate method build a table of chains chains of chain_length passwords
+    // The starts are spread over the space. Chains which merged into the same end are all kept,
+    // their passwords before the merge are still different.
+    pub fn generate(
+        algorithm: Algorithm,
+        space: Space,
+        chain_length: usize,
+        chains: usize,
+    ) -> Table {
+        let mut table = Table {
+            algorithm,
+            space,
+            chain_length,
+            chains: Vec::with_capacity(chains),
+        };
+        let size = table.space.size();
+        let step = (size / chains.max(1) as u64).max(1);
+        for i in 0..chains as u64 {
+            let start = (i * step) % size;
+            let end = table.walk(start, 0, chain_length);
+            table.chains.push((start, end));
+        }
+        table.chains.sort_by_key(|&(_, end)| end);
+        table
+    }
+
+    // len method return the number of chains kept
+    pub fn len(&self) -> usize {
+        self.chains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+
+    // lookup method return a password whose digest is digest, if a chain of the table has it
+    pub fn lookup(&self, digest: &[u8]) -> Option<Vec<u8>> {
+        // the digest is tried at the last column first, it is the cheapest
+        for column in (0..self.chain_length).rev() {
+            let end = self.walk(self.reduce(digest, column), column + 1, self.chain_length);
+            let first = self.chains.partition_point(|&(_, e)| e < end);
+            for &(start, _) in self.chains[first..].iter().take_while(|&&(_, e)| e == end) {
+                // the chain is computed again up to the column, it may be a false alarm
+                let index = self.walk(start, 0, column);
+                let password = self.space.password(index);
+                if self.algorithm.digest(&password) == digest {
+                    return Some(password);
+                }
+            }
+        }
+        None
+    }
+
+    // write_to method save the table in a compact binary format
+    //   "RBT" version(1) | algorithm name length(1) name | max_length(1) | charset length(1) charset
+    //   | chain_length(4) | chains(8) | index width(1) | (start, end) of each chain in width bytes
+    // All numbers are little endian, an index take only the bytes the size of the space need.
+    // A table whose max_length, charset length or chain_length do not fit their field is not written.
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let too_large = |field: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("rainbow table: {} is too large for the file format", field),
+            )
+        };
+        let name = self.algorithm.name();
+        let name_len = u8::try_from(name.len()).map_err(|_| too_large("algorithm name"))?;
+        let max_length =
+            u8::try_from(self.space.max_length).map_err(|_| too_large("max_length"))?;
+        let charset_len =
+            u8::try_from(self.space.charset.len()).map_err(|_| too_large("charset"))?;
+        let chain_length =
+            u32::try_from(self.chain_length).map_err(|_| too_large("chain_length"))?;
+        let width = self.width();
+        out.write_all(b"RBT\x01")?;
+        out.write_all(&[name_len])?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(&[max_length, charset_len])?;
+        out.write_all(&self.space.charset)?;
+        out.write_all(&chain_length.to_le_bytes())?;
+        out.write_all(&(self.chains.len() as u64).to_le_bytes())?;
+        out.write_all(&[width as u8])?;
+        for &(start, end) in &self.chains {
+            out.write_all(&start.to_le_bytes()[..width])?;
+            out.write_all(&end.to_le_bytes()[..width])?;
+        }
+        Ok(())
+    }
+
+    // read_from method load a table saved by write_to
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Table> {
+        let byte = |reader: &mut R| -> io::Result<u8> {
+            let mut byte = [0u8; 1];
+            reader.read_exact(&mut byte)?;
+            Ok(byte[0])
+        };
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic[..3] != b"RBT" {
+            return Err(invalid("not a rainbow table"));
+        }
+        if magic[3] != 1 {
+            return Err(invalid(&format!("unsupported version {}", magic[3])));
+        }
+        let mut name = vec![0u8; byte(reader)? as usize];
+        reader.read_exact(&mut name)?;
+        let algorithm: Algorithm = std::str::from_utf8(&name)
+            .map_err(|_| invalid("bad algorithm name"))?
+            .parse()
+            .map_err(|e: String| invalid(&e))?;
+        let max_length = byte(reader)? as usize;
+        let mut charset = vec![0u8; byte(reader)? as usize];
+        reader.read_exact(&mut charset)?;
+        if charset.is_empty() || max_length == 0 {
+            return Err(invalid("empty password space"));
+        }
+        let mut number = [0u8; 8];
+        reader.read_exact(&mut number[..4])?;
+        let chain_length = u32::from_le_bytes(number[..4].try_into().unwrap()) as usize;
+        reader.read_exact(&mut number)?;
+        let count = u64::from_le_bytes(number);
+        let width = byte(reader)? as usize;
+
+        let space = Space {
+            charset,
+            max_length,
+        };
+        if space.checked_size().is_none() {
+            return Err(invalid("the password space does not fit in 64 bits"));
+        }
+        let mut table = Table {
+            algorithm,
+            space,
+            chain_length,
+            chains: Vec::new(),
+        };
+        if width != table.width() {
+            return Err(invalid("bad index width"));
+        }
+        let index = |reader: &mut R| -> io::Result<u64> {
+            let mut bytes = [0u8; 8];
+            reader.read_exact(&mut bytes[..width])?;
+            Ok(u64::from_le_bytes(bytes))
+        };
+        for _ in 0..count {
+            let start = index(reader)?;
+            let end = index(reader)?;
+            table.chains.push((start, end));
+        }
+        if !table.chains.is_sorted_by_key(|&(_, end)| end) {
+            return Err(invalid("chains are not sorted"));
+        }
+        Ok(table)
+    }
+
+    // width method return the number of bytes of an index
+    fn width(&self) -> usize {
+        let bits = 64 - (self.space.size() - 1).leading_zeros() as usize;
+        bits.div_ceil(8).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attacks::rainbow::*;
+
+    fn table() -> Table {
+        // every password of 1 to 5 digits, 111110 of them
+        Table::generate(Algorithm::Md5, Space::new(b"0123456789", 5), 100, 4000)
+    }
+
+    #[test]
+    fn test_1() {
+        let space = Space::new(b"ab", 3);
+        assert_eq!(14, space.size());
+        assert_eq!(b"a".to_vec(), space.password(0));
+        assert_eq!(b"bb".to_vec(), space.password(5));
+        assert_eq!(b"aaa".to_vec(), space.password(6));
+        assert_eq!(b"bbb".to_vec(), space.password(13));
+    }
+
+    #[test]
+    fn test_2() {
+        let table = table();
+        let passwords: [&[u8]; 8] = [
+            b"1234", b"12345", b"0000", b"31337", b"2024", b"7", b"90210", b"55555",
+        ];
+        let mut found = 0;
+        for password in passwords {
+            let digest = Algorithm::Md5.digest(password);
+            if let Some(result) = table.lookup(&digest) {
+                println!(
+                    "{} -> {}",
+                    crate::hex::encode(&digest),
+                    String::from_utf8_lossy(&result)
+                );
+                assert_eq!(digest, Algorithm::Md5.digest(&result));
+                found += 1;
+            }
+        }
+        // a table does not cover the whole space, but most of it
+        println!(
+            "{} of {} found, {} chains",
+            found,
+            passwords.len(),
+            table.len()
+        );
+        assert!(found >= 6);
+
+        // a salt work only when the salted passwords are out of the table: the passwords 100..120 salted
+        // with "20" are digits of length 5, the table cover them and find about as many as without salt
+        // (the salt is not secret, the password is the rest), with "2024" they are 7 digits and none is found
+        let (mut unsalted, mut short, mut long) = (0, 0, 0);
+        for password in 100..120 {
+            let password = password.to_string();
+            if table
+                .lookup(&Algorithm::Md5.digest(password.as_bytes()))
+                .is_some()
+            {
+                unsalted += 1;
+            }
+            let salted = format!("20{}", password);
+            if let Some(result) = table.lookup(&Algorithm::Md5.digest(salted.as_bytes())) {
+                assert_eq!(salted.as_bytes(), result);
+                short += 1;
+            }
+            let salted = format!("2024{}", password);
+            if table
+                .lookup(&Algorithm::Md5.digest(salted.as_bytes()))
+                .is_some()
+            {
+                long += 1;
+            }
+        }
+        println!(
+            "of 20 passwords: {} unsalted, {} with a short salt, {} with a long salt",
+            unsalted, short, long
+        );
+        assert!(unsalted >= 14);
+        assert!(short >= 14);
+        assert_eq!(0, long);
+    }
+
+    #[test]
+    fn test_3() {
+        // a saved table give the same answers
+        let table = Table::generate(Algorithm::Sha1, Space::new(b"0123456789", 4), 50, 500);
+        let mut file = Vec::new();
+        table.write_to(&mut file).unwrap();
+        // 2 bytes for each index of the 11110 passwords
+        assert_eq!(4 + 5 + 2 + 10 + 4 + 8 + 1 + table.len() * 4, file.len());
+
+        let loaded = Table::read_from(&mut &file[..]).unwrap();
+        assert_eq!(table.chains, loaded.chains);
+        assert_eq!(table.space, loaded.space);
+        let digest = Algorithm::Sha1.digest(b"1234");
+        assert_eq!(table.lookup(&digest), loaded.lookup(&digest));
+
+        file[0] = b'X';
+        assert!(Table::read_from(&mut &file[..]).is_err());
+    }
+
+    #[test]
+    fn test_4() {
+        // fields too large for their byte are refused, not cut
+        let charset: Vec<u8> = (0..=255).collect();
+        let table = Table::generate(Algorithm::Md5, Space::new(&charset, 1), 10, 10);
+        let error = table.write_to(&mut Vec::new()).unwrap_err();
+        println!("{}", error);
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+
+        let table = Table::generate(Algorithm::Md5, Space::new(b"a", 300), 10, 10);
+        let error = table.write_to(&mut Vec::new()).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+
+        // 255 still fit
+        let table = Table::generate(Algorithm::Md5, Space::new(b"a", 255), 10, 10);
+        let mut file = Vec::new();
+        table.write_to(&mut file).unwrap();
+        let loaded = Table::read_from(&mut &file[..]).unwrap();
+        assert_eq!(table.space, loaded.space);
+    }
+
+    #[test]
+    #[should_panic(expected = "max_length must be at least 1")]
+    fn test_5() {
+        Space::new(b"ab", 0);
+    }
+}
@@ -0,0 +1,572 @@
+// hashsum print or check checksums like md5sum and sha1sum of GNU coreutils
+// The output format and the messages of --check are the same as coreutils,
+// so scripts written for md5sum/sha1sum keep working with "hashsum -a md5".
+extern crate hashes;
+use hashes::manifest::{self, Manifest};
+use hashes::sums;
+use hashes::Algorithm;
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: hashsum [OPTION]... [FILE]...
+  or:  hashsum manifest [-a NAME[,NAME]...] DIR
+  or:  hashsum audit -k MANIFEST DIR
+Print or check checksums.
+With no FILE, or when FILE is -, read standard input.
+manifest write a hashdeep-style manifest of every file under DIR (default: md5,sha1),
+audit compare DIR with the manifest and report matched, moved, new, changed and missing files.
+A file named manifest or audit can be hashed as ./manifest or after --.
+
+  -a, --algorithm NAME  digest algorithm to use (default: sha1)
+  -b, --binary          read in binary mode
+  -c, --check           read checksums from the FILEs and check them
+      --tag             create a BSD-style checksum
+  -t, --text            read in text mode (default)
+      --help            display this help and exit
+
+The following five options are useful only when verifying checksums:
+      --ignore-missing  don't fail or report status for missing files
+      --quiet           don't print OK for each successfully verified file
+      --status          don't output anything, status code shows success
+      --strict          exit non-zero for improperly formatted checksum lines
+  -w, --warn            warn about improperly formatted checksum lines
+";
+
+// Options hold the parsed command line
+struct Options {
+    algorithm: Algorithm,
+    binary: bool,
+    check: bool,
+    tag: bool,
+    ignore_missing: bool,
+    quiet: bool,
+    status: bool,
+    strict: bool,
+    warn: bool,
+    files: Vec<OsString>,
+}
+
+// The file names are OsString and the checksum lines are bytes, like coreutils a name which is not
+// UTF-8 is hashed, printed and checked as it is. Only the options and algorithm names must be UTF-8.
+
+// name_bytes function return the bytes of a file name for a checksum line
+#[cfg(unix)]
+fn name_bytes(name: &OsStr) -> &[u8] {
+    std::os::unix::ffi::OsStrExt::as_bytes(name)
+}
+
+#[cfg(not(unix))]
+fn name_bytes(name: &OsStr) -> &[u8] {
+    name.as_encoded_bytes()
+}
+
+// bytes_name function is the reverse of name_bytes for a name read from a checksum file
+#[cfg(unix)]
+fn bytes_name(bytes: &[u8]) -> OsString {
+    <OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(bytes).to_os_string()
+}
+
+#[cfg(not(unix))]
+fn bytes_name(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
+
+// text function return an argument as a string, for options and algorithm names
+fn text(arg: &OsStr) -> Result<&str, String> {
+    arg.to_str()
+        .ok_or_else(|| format!("invalid argument '{}'", arg.to_string_lossy()))
+}
+
+// parse_args function parse the command line, Err is the message for a usage error
+fn parse_args(args: Vec<OsString>) -> Result<Options, String> {
+    let mut options = Options {
+        algorithm: Algorithm::Sha1,
+        binary: false,
+        check: false,
+        tag: false,
+        ignore_missing: false,
+        quiet: false,
+        status: false,
+        strict: false,
+        warn: false,
+        files: Vec::new(),
+    };
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // a name which is not UTF-8 is never an option
+        let Some(flag) = arg.to_str() else {
+            options.files.push(arg);
+            continue;
+        };
+        match flag {
+            "-a" | "--algorithm" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| format!("option '{}' requires an argument", flag))?;
+                options.algorithm = text(&name)?.parse()?;
+            }
+            "-b" | "--binary" => options.binary = true,
+            "-t" | "--text" => options.binary = false,
+            "-c" | "--check" => options.check = true,
+            "--tag" => options.tag = true,
+            "-w" | "--warn" => options.warn = true,
+            "--ignore-missing" => options.ignore_missing = true,
+            "--quiet" => options.quiet = true,
+            "--status" => options.status = true,
+            "--strict" => options.strict = true,
+            "--" => {
+                options.files.extend(args);
+                break;
+            }
+            _ => {
+                if let Some(name) = flag.strip_prefix("--algorithm=") {
+                    options.algorithm = name.parse()?;
+                } else if let Some(name) = flag.strip_prefix("-a").filter(|name| !name.is_empty()) {
+                    options.algorithm = name.parse()?;
+                } else if flag.starts_with('-') && flag != "-" {
+                    return Err(format!("unrecognized option '{}'", flag));
+                } else {
+                    options.files.push(arg);
+                }
+            }
+        }
+    }
+
+    // the options of --check are an error without it, like coreutils
+    if !options.check {
+        let verify_only = [
+            ("--ignore-missing", options.ignore_missing),
+            ("--status", options.status),
+            ("--warn", options.warn),
+            ("--quiet", options.quiet),
+            ("--strict", options.strict),
+        ];
+        if let Some((flag, _)) = verify_only.iter().find(|(_, set)| *set) {
+            return Err(format!(
+                "the {} option is meaningful only when verifying checksums",
+                flag
+            ));
+        }
+    }
+
+    if options.files.is_empty() {
+        options.files.push(OsString::from("-"));
+    }
+    Ok(options)
+}
+
+// describe function return the error message without the " (os error N)" part, like coreutils
+fn describe(e: &impl std::fmt::Display) -> String {
+    let message = e.to_string();
+    match message.find(" (os error") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
+}
+
+// open function open the file, "-" is standard input
+fn open(name: &OsStr) -> io::Result<Box<dyn Read>> {
+    if name == "-" {
+        Ok(Box::new(io::stdin()))
+    } else {
+        Ok(Box::new(File::open(name)?))
+    }
+}
+
+// hash_file function calculate the digest of a file
+fn hash_file(algorithm: Algorithm, name: &OsStr) -> io::Result<Vec<u8>> {
+    algorithm.digest_reader(&mut open(name)?)
+}
+
+// display_name function escape the name for messages of --check
+// coreutils escape it here only when it has a newline, a backslash alone is printed as it is.
+fn display_name(name: &[u8]) -> Vec<u8> {
+    if name.iter().any(|&b| b == b'\n' || b == b'\r') {
+        [b"\\".as_slice(), &sums::escape(name)].concat()
+    } else {
+        name.to_vec()
+    }
+}
+
+// print_sums function print a checksum line for every file, false is returned if a file could not be read
+// Err is an error of standard output, the errors of the files are only reported.
+fn print_sums(options: &Options) -> io::Result<bool> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut ok = true;
+    for name in &options.files {
+        match hash_file(options.algorithm, name) {
+            Ok(digest) => {
+                let line = if options.tag {
+                    sums::format_bsd(options.algorithm, &digest, name_bytes(name))
+                } else {
+                    sums::format_gnu(&digest, name_bytes(name), options.binary)
+                };
+                out.write_all(&line)?;
+                out.write_all(b"\n")?;
+            }
+            Err(e) => {
+                out.flush()?;
+                eprintln!("hashsum: {}: {}", name.to_string_lossy(), describe(&e));
+                ok = false;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(ok)
+}
+
+// plural function choose the singular or the plural form of a warning
+fn plural<'a>(count: usize, singular: &'a str, plural: &'a str) -> &'a str {
+    if count == 1 {
+        singular
+    } else {
+        plural
+    }
+}
+
+// check_file function verify every line of a checksum file, false is returned if anything failed
+// Err is an error of standard output, like print_sums.
+fn check_file(options: &Options, sums_name: &OsStr) -> io::Result<bool> {
+    let mut content = Vec::new();
+    if let Err(e) = open(sums_name).and_then(|mut file| file.read_to_end(&mut content)) {
+        eprintln!("hashsum: {}: {}", sums_name.to_string_lossy(), describe(&e));
+        return Ok(false);
+    }
+    let sums_name = sums_name.to_string_lossy();
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut improper = 0;
+    let mut properly_formatted = false;
+    let mut read_failures = 0;
+    let mut mismatches = 0;
+    let mut verified = false;
+
+    // a trailing newline does not make an empty last line
+    let content = content.strip_suffix(b"\n").unwrap_or(&content);
+    for (index, line) in content.split(|&b| b == b'\n').enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.starts_with(b"#") {
+            continue;
+        }
+
+        // A BSD style line name its own algorithm and its hash length is checked by the parser,
+        // a GNU line use the algorithm of -a.
+        let entry = sums::parse_line(line).filter(|entry| {
+            entry.algorithm.is_some() || entry.digest.len() == options.algorithm.output_size()
+        });
+        let entry = match entry {
+            Some(entry) => entry,
+            None => {
+                improper += 1;
+                if options.warn {
+                    out.flush()?;
+                    eprintln!(
+                        "hashsum: {}: {}: improperly formatted {} checksum line",
+                        sums_name,
+                        index + 1,
+                        options.algorithm.tag()
+                    );
+                }
+                continue;
+            }
+        };
+        properly_formatted = true;
+
+        let algorithm = entry.algorithm.unwrap_or(options.algorithm);
+        let name = display_name(&entry.name);
+        let digest = match hash_file(algorithm, &bytes_name(&entry.name)) {
+            Ok(digest) => digest,
+            Err(e) => {
+                if options.ignore_missing && e.kind() == io::ErrorKind::NotFound {
+                    continue;
+                }
+                read_failures += 1;
+                if !options.status {
+                    out.flush()?;
+                    eprintln!(
+                        "hashsum: {}: {}",
+                        String::from_utf8_lossy(&entry.name),
+                        describe(&e)
+                    );
+                    out.write_all(&name)?;
+                    out.write_all(b": FAILED open or read\n")?;
+                }
+                continue;
+            }
+        };
+        verified = true;
+
+        if digest == entry.digest {
+            if !options.quiet && !options.status {
+                out.write_all(&name)?;
+                out.write_all(b": OK\n")?;
+            }
+        } else {
+            mismatches += 1;
+            if !options.status {
+                out.write_all(&name)?;
+                out.write_all(b": FAILED\n")?;
+            }
+        }
+    }
+    out.flush()?;
+
+    if !properly_formatted {
+        if !options.status {
+            eprintln!(
+                "hashsum: {}: no properly formatted checksum lines found",
+                sums_name
+            );
+        }
+        return Ok(false);
+    }
+
+    if !options.status {
+        if improper > 0 {
+            eprintln!(
+                "hashsum: WARNING: {} {} improperly formatted",
+                improper,
+                plural(improper, "line is", "lines are")
+            );
+        }
+        if read_failures > 0 {
+            eprintln!(
+                "hashsum: WARNING: {} listed {} could not be read",
+                read_failures,
+                plural(read_failures, "file", "files")
+            );
+        }
+        if mismatches > 0 {
+            eprintln!(
+                "hashsum: WARNING: {} computed {} did NOT match",
+                mismatches,
+                plural(mismatches, "checksum", "checksums")
+            );
+        }
+        if options.ignore_missing && !verified {
+            eprintln!("hashsum: {}: no file was verified", sums_name);
+        }
+    }
+
+    Ok(read_failures == 0
+        && mismatches == 0
+        && !(options.strict && improper > 0)
+        && (verified || !options.ignore_missing))
+}
+
+// manifest_args function parse the arguments of manifest, Err is the message for a usage error
+fn manifest_args(args: Vec<OsString>) -> Result<(Vec<Algorithm>, PathBuf), String> {
+    let mut algorithms = vec![Algorithm::Md5, Algorithm::Sha1];
+    let mut dir = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some(flag @ ("-a" | "--algorithm")) => {
+                let names = args
+                    .next()
+                    .ok_or_else(|| format!("option '{}' requires an argument", flag))?;
+                algorithms = text(&names)?
+                    .split(',')
+                    .map(|name| name.parse())
+                    .collect::<Result<_, _>>()?;
+            }
+            Some(flag) if flag.starts_with('-') => {
+                return Err(format!("unrecognized option '{}'", flag))
+            }
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => return Err(format!("extra operand '{}'", arg.to_string_lossy())),
+        }
+    }
+    let dir = dir.ok_or("missing directory operand")?;
+    Ok((algorithms, dir))
+}
+
+// run_manifest function write the manifest of a directory to standard output
+fn run_manifest(algorithms: &[Algorithm], dir: &Path) -> io::Result<bool> {
+    let manifest = match Manifest::build(dir, algorithms) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("hashsum: {}: {}", dir.display(), describe(&e));
+            return Ok(false);
+        }
+    };
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    manifest.write_to(&mut out)?;
+    out.flush()?;
+    report_errors(dir, &manifest.errors);
+    Ok(manifest.errors.is_empty())
+}
+
+// report_errors function print the files of the tree which could not be read
+fn report_errors(dir: &Path, errors: &[(PathBuf, String)]) {
+    for (path, message) in errors {
+        eprintln!(
+            "hashsum: {}: {}",
+            dir.join(path).display(),
+            describe(message)
+        );
+    }
+}
+
+// audit_args function parse the arguments of audit, the manifest and the directory
+fn audit_args(args: Vec<OsString>) -> Result<(PathBuf, PathBuf), String> {
+    let mut known = None;
+    let mut dir = None;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some(flag @ ("-k" | "--known")) => {
+                known =
+                    Some(PathBuf::from(args.next().ok_or_else(|| {
+                        format!("option '{}' requires an argument", flag)
+                    })?));
+            }
+            Some(flag) if flag.starts_with('-') => {
+                return Err(format!("unrecognized option '{}'", flag))
+            }
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => return Err(format!("extra operand '{}'", arg.to_string_lossy())),
+        }
+    }
+    let known = known.ok_or("missing manifest, use -k MANIFEST")?;
+    let dir = dir.ok_or("missing directory operand")?;
+    Ok((known, dir))
+}
+
+// run_audit function compare a directory with a manifest, true is returned if the audit passed
+fn run_audit(known: &Path, dir: &Path) -> io::Result<bool> {
+    let manifest =
+        match File::open(known).and_then(|file| Manifest::read_from(BufReader::new(file))) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                eprintln!("hashsum: {}: {}", known.display(), describe(&e));
+                return Ok(false);
+            }
+        };
+    let report = match manifest.audit(dir) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("hashsum: {}: {}", dir.display(), describe(&e));
+            return Ok(false);
+        }
+    };
+
+    report_errors(dir, &report.errors);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    // the paths are written as in the manifest, as their bytes
+    let mut line = |label: &[u8], paths: &[&PathBuf]| -> io::Result<()> {
+        out.write_all(label)?;
+        for (i, path) in paths.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b" -> ")?;
+            }
+            out.write_all(&manifest::path_bytes(path))?;
+        }
+        out.write_all(b"\n")
+    };
+    for (from, to) in &report.moved {
+        line(b"moved: ", &[from, to])?;
+    }
+    for path in &report.new {
+        line(b"new: ", &[path])?;
+    }
+    for path in &report.changed {
+        line(b"changed: ", &[path])?;
+    }
+    for path in &report.missing {
+        line(b"missing: ", &[path])?;
+    }
+    writeln!(out, "Files matched: {}", report.matched.len())?;
+    writeln!(out, "Files moved: {}", report.moved.len())?;
+    writeln!(out, "New files found: {}", report.new.len())?;
+    writeln!(out, "Files changed: {}", report.changed.len())?;
+    writeln!(out, "Known files not found: {}", report.missing.len())?;
+    if !report.errors.is_empty() {
+        writeln!(out, "Files not read: {}", report.errors.len())?;
+    }
+    if report.passed() {
+        writeln!(out, "Audit passed")?;
+    } else {
+        writeln!(out, "Audit failed")?;
+    }
+    out.flush()?;
+    Ok(report.passed())
+}
+
+// usage_error function print the message of a usage error
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("hashsum: {}", message);
+    eprintln!("Try 'hashsum --help' for more information.");
+    ExitCode::FAILURE
+}
+
+// exit_code function turn the result of a command into the exit status
+// A closed pipe ("hashsum * | head -1") is a normal end and exit quietly,
+// any other error of standard output is reported.
+fn exit_code(result: io::Result<bool>) -> ExitCode {
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("hashsum: write error: {}", describe(&e));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    // after "--" every argument is a file, a file named --help is hashed
+    if args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--help")
+    {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    // the subcommands are only recognized as the first argument
+    match args.first().and_then(|arg| arg.to_str()) {
+        Some("manifest") => {
+            args.remove(0);
+            return match manifest_args(args) {
+                Ok((algorithms, dir)) => exit_code(run_manifest(&algorithms, &dir)),
+                Err(message) => usage_error(&message),
+            };
+        }
+        Some("audit") => {
+            args.remove(0);
+            return match audit_args(args) {
+                Ok((known, dir)) => exit_code(run_audit(&known, &dir)),
+                Err(message) => usage_error(&message),
+            };
+        }
+        _ => {}
+    }
+
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(message) => return usage_error(&message),
+    };
+
+    let result = if options.check {
+        // every checksum file is checked even if an earlier one failed
+        options.files.iter().try_fold(true, |ok, name| {
+            check_file(&options, name).map(|checked| ok & checked)
+        })
+    } else {
+        print_sums(&options)
+    };
+    exit_code(result)
+}
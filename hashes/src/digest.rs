@@ -0,0 +1,15 @@
+// Digest is the common streaming interface of every hash and checksum in this crate
+// Each algorithm keep its own trait (Sha1Digest, MD5Digest, FastDigest, ...) whose finalize return the struct itself.
+// This trait return the raw bytes instead, so generic code like the io adapters can work with any of them.
+// The bytes are in the same order as the Display output, so hex of the bytes is the same string.
+pub trait Digest {
+    // type of the raw output bytes, a fixed size array
+    type Output: AsRef<[u8]> + Clone + PartialEq + core::fmt::Debug;
+
+    // const for the output size in bytes
+    const OUTPUT_SIZE: usize;
+
+    fn new() -> Self;
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self) -> Self::Output;
+}
@@ -0,0 +1,231 @@
+use crate::fast::FastDigest;
+use core::fmt::Display;
+use core::hash::Hasher;
+
+// FNV offset basis and prime
+// The offset basis is the FNV-0 hash of "chongo <Landon Curt Noll> /\../\"
+const OFFSET_32: u32 = 0x811c_9dc5;
+const PRIME_32: u32 = 0x0100_0193;
+const OFFSET_64: u64 = 0xcbf2_9ce4_8422_2325;
+const PRIME_64: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Clone, Copy)]
+pub struct Fnv1a32 {
+    hash: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Fnv1a64 {
+    hash: u64,
+}
+
+impl Display for Fnv1a32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:08x}", self.hash)
+    }
+}
+
+impl Display for Fnv1a64 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:016x}", self.hash)
+    }
+}
+
+// Implement FastDigest trait to Fnv1a32 struct
+// FNV-1a xor the byte first and then multiply, FNV-1 is the opposite order.
+impl FastDigest for Fnv1a32 {
+    type Output = Self;
+
+    const HASH_SIZE: usize = 4;
+
+    fn new() -> Self {
+        Self { hash: OFFSET_32 }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        for &byte in input {
+            self.hash ^= byte as u32;
+            self.hash = self.hash.wrapping_mul(PRIME_32);
+        }
+    }
+
+    fn finalize(self) -> Self::Output {
+        self
+    }
+}
+
+// Implement FastDigest trait to Fnv1a64 struct
+impl FastDigest for Fnv1a64 {
+    type Output = Self;
+
+    const HASH_SIZE: usize = 8;
+
+    fn new() -> Self {
+        Self { hash: OFFSET_64 }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        for &byte in input {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(PRIME_64);
+        }
+    }
+
+    fn finalize(self) -> Self::Output {
+        self
+    }
+}
+
+impl Fnv1a32 {
+    // hash method return the 32bit hash value
+    pub fn hash(&self) -> u32 {
+        self.hash
+    }
+}
+
+impl Fnv1a64 {
+    // hash method return the 64bit hash value
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Default for Fnv1a32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Fnv1a64 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implement Hasher trait so that FNV-1a can be used with HashMap and HashSet
+impl Hasher for Fnv1a32 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+}
+
+impl Hasher for Fnv1a64 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// fnv1a_32 function calculate FNV-1a 32bit hash of input at once
+pub fn fnv1a_32(input: &[u8]) -> u32 {
+    let mut hasher = Fnv1a32::new();
+    hasher.update(input);
+    hasher.finalize().hash()
+}
+
+// fnv1a_64 function calculate FNV-1a 64bit hash of input at once
+pub fn fnv1a_64(input: &[u8]) -> u64 {
+    let mut hasher = Fnv1a64::new();
+    hasher.update(input);
+    hasher.finalize().hash()
+}
+
+// Implement the common Digest trait with seed 0
+// The hash value is output in big endian, the same as the Display output
+impl crate::Digest for Fnv1a32 {
+    type Output = [u8; 4];
+
+    const OUTPUT_SIZE: usize = 4;
+
+    fn new() -> Self {
+        <Self as FastDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FastDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FastDigest::finalize(self).hash().to_be_bytes()
+    }
+}
+
+impl crate::Digest for Fnv1a64 {
+    type Output = [u8; 8];
+
+    const OUTPUT_SIZE: usize = 8;
+
+    fn new() -> Self {
+        <Self as FastDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FastDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FastDigest::finalize(self).hash().to_be_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fast::FastDigest;
+
+    #[test]
+    fn test_1() {
+        let mut hashtest = crate::fast::fnv::Fnv1a32::new();
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("811c9dc5", result);
+    }
+
+    #[test]
+    fn test_2() {
+        let mut hashtest = crate::fast::fnv::Fnv1a32::new();
+        hashtest.update(b"foobar");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("bf9cf968", result);
+    }
+
+    #[test]
+    fn test_3() {
+        let mut hashtest = crate::fast::fnv::Fnv1a64::new();
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("cbf29ce484222325", result);
+    }
+
+    #[test]
+    fn test_4() {
+        let mut hashtest = crate::fast::fnv::Fnv1a64::new();
+        hashtest.update(b"foobar");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("85944171f73967e8", result);
+    }
+
+    #[test]
+    fn test_oneshot() {
+        assert_eq!(0xe40c292c, crate::fast::fnv::fnv1a_32(b"a"));
+        assert_eq!(0xaf63dc4c8601ec8c, crate::fast::fnv::fnv1a_64(b"a"));
+    }
+}
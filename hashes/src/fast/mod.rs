@@ -0,0 +1,24 @@
+// Non-cryptographic fast hashes
+// These hashes are designed for hash tables, sharding and cache keys.
+// They are much faster than md5 and sha1, but they are NOT collision resistant,
+// so never use them for anything an attacker can choose the input of.
+#[cfg(feature = "fnv")]
+pub mod fnv;
+#[cfg(feature = "murmur3")]
+pub mod murmur3;
+#[cfg(feature = "xxhash")]
+pub mod xxhash;
+
+// FastDigest calculate non-cryptographic hash
+// Seeded hashes have a with_seed method, new is the same as seed 0.
+pub trait FastDigest {
+    // type related to trait
+    type Output;
+
+    // const for the output size in bytes
+    const HASH_SIZE: usize;
+
+    fn new() -> Self;
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self) -> Self::Output;
+}
@@ -0,0 +1,469 @@
+use crate::fast::FastDigest;
+use core::fmt::Display;
+use core::hash::Hasher;
+
+// constants of MurmurHash3_x86_32
+const C1_32: u32 = 0xcc9e_2d51;
+const C2_32: u32 = 0x1b87_3593;
+
+// constants of MurmurHash3_x64_128
+const C1_64: u64 = 0x87c3_7b91_1142_53d5;
+const C2_64: u64 = 0x4cf5_ad43_2745_937f;
+
+// fmix32 force all bits of a hash block to avalanche
+#[inline(always)]
+fn fmix32(mut h: u32) -> u32 {
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+// fmix64 is the 64bit version of fmix32
+#[inline(always)]
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^= k >> 33;
+    k
+}
+
+#[inline(always)]
+fn mix_k1_32(k1: u32) -> u32 {
+    k1.wrapping_mul(C1_32).rotate_left(15).wrapping_mul(C2_32)
+}
+
+#[inline(always)]
+fn mix_k1_64(k1: u64) -> u64 {
+    k1.wrapping_mul(C1_64).rotate_left(31).wrapping_mul(C2_64)
+}
+
+#[inline(always)]
+fn mix_k2_64(k2: u64) -> u64 {
+    k2.wrapping_mul(C2_64).rotate_left(33).wrapping_mul(C1_64)
+}
+
+#[derive(Clone, Copy)]
+pub struct Murmur3x86_32 {
+    h1: u32,
+    tail: [u8; 4],
+    tail_len: usize,
+    // MurmurHash3 mix only the lowest 32bit of the length
+    length: u64,
+    hash: u32,
+}
+
+#[derive(Clone, Copy)]
+pub struct Murmur3x64_128 {
+    h1: u64,
+    h2: u64,
+    tail: [u8; 16],
+    tail_len: usize,
+    length: u64,
+    hash: u128,
+}
+
+impl Display for Murmur3x86_32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:08x}", self.hash)
+    }
+}
+
+// The 128bit value is h2 in the upper 64bit and h1 in the lower 64bit, the same as the reference
+impl Display for Murmur3x64_128 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:032x}", self.hash)
+    }
+}
+
+impl Murmur3x86_32 {
+    // with_seed method return the Murmur3x86_32 struct initialized with seed
+    pub fn with_seed(seed: u32) -> Self {
+        Self {
+            h1: seed,
+            tail: [0; 4],
+            tail_len: 0,
+            length: 0,
+            hash: 0,
+        }
+    }
+
+    // hash method return the 32bit hash value
+    pub fn hash(&self) -> u32 {
+        self.hash
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        let k1 = u32::from_le_bytes(block.try_into().unwrap());
+        self.h1 ^= mix_k1_32(k1);
+        self.h1 = self
+            .h1
+            .rotate_left(13)
+            .wrapping_mul(5)
+            .wrapping_add(0xe654_6b64);
+    }
+
+    // digest method calculate the hash without consuming the struct, for Hasher::finish
+    fn digest(&self) -> u32 {
+        let mut h1 = self.h1;
+        // The tail is the remaining 1 to 3 bytes in little endian
+        if self.tail_len > 0 {
+            let mut tail = [0u8; 4];
+            tail[..self.tail_len].copy_from_slice(&self.tail[..self.tail_len]);
+            h1 ^= mix_k1_32(u32::from_le_bytes(tail));
+        }
+        h1 ^= self.length as u32;
+        fmix32(h1)
+    }
+}
+
+impl Murmur3x64_128 {
+    // with_seed method return the Murmur3x64_128 struct initialized with seed
+    pub fn with_seed(seed: u32) -> Self {
+        Self {
+            h1: seed as u64,
+            h2: seed as u64,
+            tail: [0; 16],
+            tail_len: 0,
+            length: 0,
+            hash: 0,
+        }
+    }
+
+    // hash method return the 128bit hash value
+    pub fn hash(&self) -> u128 {
+        self.hash
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        let k1 = u64::from_le_bytes(block[0..8].try_into().unwrap());
+        let k2 = u64::from_le_bytes(block[8..16].try_into().unwrap());
+
+        self.h1 ^= mix_k1_64(k1);
+        self.h1 = self
+            .h1
+            .rotate_left(27)
+            .wrapping_add(self.h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+
+        self.h2 ^= mix_k2_64(k2);
+        self.h2 = self
+            .h2
+            .rotate_left(31)
+            .wrapping_add(self.h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+
+    fn digest(&self) -> u128 {
+        let mut h1 = self.h1;
+        let mut h2 = self.h2;
+        // The tail is the remaining 1 to 15 bytes, the first 8 bytes go to k1 and the rest to k2
+        if self.tail_len > 0 {
+            let mut tail = [0u8; 16];
+            tail[..self.tail_len].copy_from_slice(&self.tail[..self.tail_len]);
+            if self.tail_len > 8 {
+                h2 ^= mix_k2_64(u64::from_le_bytes(tail[8..16].try_into().unwrap()));
+            }
+            h1 ^= mix_k1_64(u64::from_le_bytes(tail[0..8].try_into().unwrap()));
+        }
+
+        h1 ^= self.length;
+        h2 ^= self.length;
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+        h1 = fmix64(h1);
+        h2 = fmix64(h2);
+        h1 = h1.wrapping_add(h2);
+        h2 = h2.wrapping_add(h1);
+
+        (h2 as u128) << 64 | h1 as u128
+    }
+}
+
+// Implement FastDigest trait to Murmur3x86_32 struct
+impl FastDigest for Murmur3x86_32 {
+    type Output = Self;
+
+    const HASH_SIZE: usize = 4;
+
+    fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // complete the block left over from the previous update first
+        if self.tail_len > 0 {
+            let take = (4 - self.tail_len).min(input.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&input[..take]);
+            self.tail_len += take;
+            input = &input[take..];
+            if self.tail_len < 4 {
+                return;
+            }
+            let tail = self.tail;
+            self.block(&tail);
+            self.tail_len = 0;
+        }
+
+        let mut chunks = input.chunks_exact(4);
+        for chunk in &mut chunks {
+            self.block(chunk);
+        }
+
+        let rest = chunks.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.hash = self.digest();
+        self
+    }
+}
+
+// Implement FastDigest trait to Murmur3x64_128 struct
+impl FastDigest for Murmur3x64_128 {
+    type Output = Self;
+
+    const HASH_SIZE: usize = 16;
+
+    fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // complete the block left over from the previous update first
+        if self.tail_len > 0 {
+            let take = (16 - self.tail_len).min(input.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&input[..take]);
+            self.tail_len += take;
+            input = &input[take..];
+            if self.tail_len < 16 {
+                return;
+            }
+            let tail = self.tail;
+            self.block(&tail);
+            self.tail_len = 0;
+        }
+
+        let mut chunks = input.chunks_exact(16);
+        for chunk in &mut chunks {
+            self.block(chunk);
+        }
+
+        let rest = chunks.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.hash = self.digest();
+        self
+    }
+}
+
+impl Default for Murmur3x86_32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Murmur3x64_128 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implement Hasher trait so that MurmurHash3 can be used with HashMap and HashSet
+impl Hasher for Murmur3x86_32 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.digest() as u64
+    }
+}
+
+// Hasher has a 64bit output, the 128bit hash is cut to its low 64bit
+impl Hasher for Murmur3x64_128 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.digest() as u64
+    }
+}
+
+// murmur3_x86_32 function calculate MurmurHash3_x86_32 of input at once
+pub fn murmur3_x86_32(input: &[u8], seed: u32) -> u32 {
+    let mut hasher = Murmur3x86_32::with_seed(seed);
+    hasher.update(input);
+    hasher.finalize().hash()
+}
+
+// murmur3_x64_128 function calculate MurmurHash3_x64_128 of input at once
+pub fn murmur3_x64_128(input: &[u8], seed: u32) -> u128 {
+    let mut hasher = Murmur3x64_128::with_seed(seed);
+    hasher.update(input);
+    hasher.finalize().hash()
+}
+
+// Implement the common Digest trait with seed 0
+// The hash value is output in big endian, the same as the Display output
+impl crate::Digest for Murmur3x86_32 {
+    type Output = [u8; 4];
+
+    const OUTPUT_SIZE: usize = 4;
+
+    fn new() -> Self {
+        <Self as FastDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FastDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FastDigest::finalize(self).hash().to_be_bytes()
+    }
+}
+
+impl crate::Digest for Murmur3x64_128 {
+    type Output = [u8; 16];
+
+    const OUTPUT_SIZE: usize = 16;
+
+    fn new() -> Self {
+        <Self as FastDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FastDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FastDigest::finalize(self).hash().to_be_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fast::FastDigest;
+
+    #[test]
+    fn test_1() {
+        let mut hashtest = crate::fast::murmur3::Murmur3x86_32::new();
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("00000000", result);
+    }
+
+    #[test]
+    fn test_2() {
+        let mut hashtest = crate::fast::murmur3::Murmur3x86_32::new();
+        hashtest.update(b"hello");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("248bfa47", result);
+    }
+
+    #[test]
+    fn test_3() {
+        let mut hashtest = crate::fast::murmur3::Murmur3x86_32::with_seed(0x9747b28c);
+        hashtest.update(b"The quick brown fox jumps over the lazy dog");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("2fa826cd", result);
+    }
+
+    #[test]
+    fn test_4() {
+        let mut hashtest = crate::fast::murmur3::Murmur3x64_128::new();
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("00000000000000000000000000000000", result);
+    }
+
+    #[test]
+    fn test_5() {
+        let mut hashtest = crate::fast::murmur3::Murmur3x64_128::new();
+        hashtest.update(b"The quick brown fox jumps over the lazy dog");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("7a433ca9c49a9347e34bbc7bbc071b6c", result);
+    }
+
+    #[test]
+    fn test_6() {
+        let mut hashtest = crate::fast::murmur3::Murmur3x64_128::with_seed(42);
+        hashtest.update(b"hello, world");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("d5d139a55afe6150b91864d797caa956", result);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let input: Vec<u8> = (0..=255).collect();
+        for split in [1, 3, 5, 7, 15, 16, 17] {
+            let mut hashtest = crate::fast::murmur3::Murmur3x86_32::with_seed(7);
+            let mut wide = crate::fast::murmur3::Murmur3x64_128::with_seed(7);
+            for chunk in input.chunks(split) {
+                hashtest.update(chunk);
+                wide.update(chunk);
+            }
+            assert_eq!(
+                crate::fast::murmur3::murmur3_x86_32(&input, 7),
+                hashtest.finalize().hash()
+            );
+            assert_eq!(
+                crate::fast::murmur3::murmur3_x64_128(&input, 7),
+                wide.finalize().hash()
+            );
+        }
+    }
+
+    #[test]
+    fn test_hasher() {
+        use std::hash::{BuildHasherDefault, Hasher};
+
+        // finish of the 128bit hash is its low 64bit
+        let mut hasher = crate::fast::murmur3::Murmur3x64_128::default();
+        hasher.write(b"hello, world");
+        assert_eq!(
+            crate::fast::murmur3::murmur3_x64_128(b"hello, world", 0) as u64,
+            hasher.finish()
+        );
+
+        let build = BuildHasherDefault::<crate::fast::murmur3::Murmur3x64_128>::default();
+        let mut map = std::collections::HashMap::with_hasher(build);
+        map.insert("shard", 1);
+        assert_eq!(Some(&1), map.get("shard"));
+    }
+}
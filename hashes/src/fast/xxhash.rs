@@ -0,0 +1,964 @@
+use crate::fast::FastDigest;
+use core::fmt::Display;
+use core::hash::Hasher;
+
+// 32bit primes of xxHash32, XXH3 use some of them
+const PRIME32_1: u64 = 0x9e37_79b1;
+const PRIME32_2: u64 = 0x85eb_ca77;
+const PRIME32_3: u64 = 0xc2b2_ae3d;
+
+// 64bit primes of xxHash64
+const PRIME64_1: u64 = 0x9e37_79b1_85eb_ca87;
+const PRIME64_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const PRIME64_3: u64 = 0x1656_67b1_9e37_79f9;
+const PRIME64_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const PRIME64_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+// XXH3 parameters
+// XXH3 process the input in 64 byte stripes, and every stripe use the secret shifted by 8 bytes.
+// After (SECRET_SIZE - STRIPE_LEN) / 8 = 16 stripes, the accumulators are scrambled.
+const STRIPE_LEN: usize = 64;
+const SECRET_CONSUME_RATE: usize = 8;
+const SECRET_SIZE: usize = 192;
+const SECRET_SIZE_MIN: usize = 136;
+const SECRET_MERGEACCS_START: usize = 11;
+const SECRET_LASTACC_START: usize = 7;
+const STRIPES_PER_BLOCK: usize = (SECRET_SIZE - STRIPE_LEN) / SECRET_CONSUME_RATE;
+// Input up to 240 bytes is hashed with the short input algorithms
+const MID_SIZE_MAX: usize = 240;
+// The streaming buffer hold 4 stripes
+const BUFFER_SIZE: usize = 256;
+
+#[rustfmt::skip]
+const DEFAULT_SECRET: [u8; SECRET_SIZE] = [
+    0xb8, 0xfe, 0x6c, 0x39, 0x23, 0xa4, 0x4b, 0xbe, 0x7c, 0x01, 0x81, 0x2c, 0xf7, 0x21, 0xad, 0x1c,
+    0xde, 0xd4, 0x6d, 0xe9, 0x83, 0x90, 0x97, 0xdb, 0x72, 0x40, 0xa4, 0xa4, 0xb7, 0xb3, 0x67, 0x1f,
+    0xcb, 0x79, 0xe6, 0x4e, 0xcc, 0xc0, 0xe5, 0x78, 0x82, 0x5a, 0xd0, 0x7d, 0xcc, 0xff, 0x72, 0x21,
+    0xb8, 0x08, 0x46, 0x74, 0xf7, 0x43, 0x24, 0x8e, 0xe0, 0x35, 0x90, 0xe6, 0x81, 0x3a, 0x26, 0x4c,
+    0x3c, 0x28, 0x52, 0xbb, 0x91, 0xc3, 0x00, 0xcb, 0x88, 0xd0, 0x65, 0x8b, 0x1b, 0x53, 0x2e, 0xa3,
+    0x71, 0x64, 0x48, 0x97, 0xa2, 0x0d, 0xf9, 0x4e, 0x38, 0x19, 0xef, 0x46, 0xa9, 0xde, 0xac, 0xd8,
+    0xa8, 0xfa, 0x76, 0x3f, 0xe3, 0x9c, 0x34, 0x3f, 0xf9, 0xdc, 0xbb, 0xc7, 0xc7, 0x0b, 0x4f, 0x1d,
+    0x8a, 0x51, 0xe0, 0x4b, 0xcd, 0xb4, 0x59, 0x31, 0xc8, 0x9f, 0x7e, 0xc9, 0xd9, 0x78, 0x73, 0x64,
+    0xea, 0xc5, 0xac, 0x83, 0x34, 0xd3, 0xeb, 0xc3, 0xc5, 0x81, 0xa0, 0xff, 0xfa, 0x13, 0x63, 0xeb,
+    0x17, 0x0d, 0xdd, 0x51, 0xb7, 0xf0, 0xda, 0x49, 0xd3, 0x16, 0x55, 0x26, 0x29, 0xd4, 0x68, 0x9e,
+    0x2b, 0x16, 0xbe, 0x58, 0x7d, 0x47, 0xa1, 0xfc, 0x8f, 0xf8, 0xb8, 0xd1, 0x7a, 0xd0, 0x31, 0xce,
+    0x45, 0xcb, 0x3a, 0x8f, 0x95, 0x16, 0x04, 0x28, 0xaf, 0xd7, 0xfb, 0xca, 0xbb, 0x4b, 0x40, 0x7e,
+];
+
+const INITIAL_ACC: [u64; 8] = [
+    PRIME32_3, PRIME64_1, PRIME64_2, PRIME64_3, PRIME64_4, PRIME32_2, PRIME64_5, PRIME32_1,
+];
+
+#[inline(always)]
+fn read_u32(input: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(input[offset..offset + 4].try_into().unwrap())
+}
+
+#[inline(always)]
+fn read_u64(input: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(input[offset..offset + 8].try_into().unwrap())
+}
+
+// xxh64_round mix one 64bit lane into an accumulator
+#[inline(always)]
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+        .rotate_left(31)
+        .wrapping_mul(PRIME64_1)
+}
+
+#[inline(always)]
+fn xxh64_merge_round(acc: u64, value: u64) -> u64 {
+    (acc ^ xxh64_round(0, value))
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(PRIME64_4)
+}
+
+#[inline(always)]
+fn xxh64_avalanche(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(PRIME64_2);
+    h ^= h >> 29;
+    h = h.wrapping_mul(PRIME64_3);
+    h ^= h >> 32;
+    h
+}
+
+#[derive(Clone, Copy)]
+pub struct Xxh64 {
+    seed: u64,
+    v: [u64; 4],
+    buffer: [u8; 32],
+    buffered: usize,
+    length: u64,
+    hash: u64,
+}
+
+impl Display for Xxh64 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:016x}", self.hash)
+    }
+}
+
+impl Xxh64 {
+    // with_seed method return the Xxh64 struct initialized with seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            v: [
+                seed.wrapping_add(PRIME64_1).wrapping_add(PRIME64_2),
+                seed.wrapping_add(PRIME64_2),
+                seed,
+                seed.wrapping_sub(PRIME64_1),
+            ],
+            buffer: [0; 32],
+            buffered: 0,
+            length: 0,
+            hash: 0,
+        }
+    }
+
+    // hash method return the 64bit hash value
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // stripe method mix a 32 byte stripe into the 4 accumulators
+    fn stripe(&mut self, stripe: &[u8]) {
+        for (i, v) in self.v.iter_mut().enumerate() {
+            *v = xxh64_round(*v, read_u64(stripe, i * 8));
+        }
+    }
+
+    // digest method calculate the hash without consuming the struct, for Hasher::finish
+    fn digest(&self) -> u64 {
+        let mut h = if self.length >= 32 {
+            let [v1, v2, v3, v4] = self.v;
+            let mut h = v1
+                .rotate_left(1)
+                .wrapping_add(v2.rotate_left(7))
+                .wrapping_add(v3.rotate_left(12))
+                .wrapping_add(v4.rotate_left(18));
+            for v in self.v {
+                h = xxh64_merge_round(h, v);
+            }
+            h
+        } else {
+            // The accumulators are not used for input shorter than 1 stripe
+            self.seed.wrapping_add(PRIME64_5)
+        };
+        h = h.wrapping_add(self.length);
+
+        // remaining bytes are mixed 8, 4 and 1 byte at a time
+        let mut rest = &self.buffer[..self.buffered];
+        while rest.len() >= 8 {
+            h ^= xxh64_round(0, read_u64(rest, 0));
+            h = h
+                .rotate_left(27)
+                .wrapping_mul(PRIME64_1)
+                .wrapping_add(PRIME64_4);
+            rest = &rest[8..];
+        }
+        if rest.len() >= 4 {
+            h ^= (read_u32(rest, 0) as u64).wrapping_mul(PRIME64_1);
+            h = h
+                .rotate_left(23)
+                .wrapping_mul(PRIME64_2)
+                .wrapping_add(PRIME64_3);
+            rest = &rest[4..];
+        }
+        for &byte in rest {
+            h ^= (byte as u64).wrapping_mul(PRIME64_5);
+            h = h.rotate_left(11).wrapping_mul(PRIME64_1);
+        }
+
+        xxh64_avalanche(h)
+    }
+}
+
+// Implement FastDigest trait to Xxh64 struct
+impl FastDigest for Xxh64 {
+    type Output = Self;
+
+    const HASH_SIZE: usize = 8;
+
+    fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // complete the stripe left over from the previous update first
+        if self.buffered > 0 {
+            let take = (32 - self.buffered).min(input.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&input[..take]);
+            self.buffered += take;
+            input = &input[take..];
+            if self.buffered < 32 {
+                return;
+            }
+            let buffer = self.buffer;
+            self.stripe(&buffer);
+            self.buffered = 0;
+        }
+
+        let mut chunks = input.chunks_exact(32);
+        for chunk in &mut chunks {
+            self.stripe(chunk);
+        }
+
+        let rest = chunks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffered = rest.len();
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.hash = self.digest();
+        self
+    }
+}
+
+// XXH3 helper functions
+
+#[inline(always)]
+fn xorshift64(value: u64, shift: u32) -> u64 {
+    value ^ (value >> shift)
+}
+
+// mul128_fold64 multiply two 64bit values to 128bit and xor the upper and lower halves
+#[inline(always)]
+fn mul128_fold64(lhs: u64, rhs: u64) -> u64 {
+    let product = lhs as u128 * rhs as u128;
+    product as u64 ^ (product >> 64) as u64
+}
+
+#[inline(always)]
+fn mul64_to128(lhs: u64, rhs: u64) -> (u64, u64) {
+    let product = lhs as u128 * rhs as u128;
+    (product as u64, (product >> 64) as u64)
+}
+
+#[inline(always)]
+fn xxh3_avalanche(mut h: u64) -> u64 {
+    h = xorshift64(h, 37);
+    h = h.wrapping_mul(0x1656_6791_9e37_79f9);
+    xorshift64(h, 32)
+}
+
+// rrmxmx is the stronger avalanche used for 4 to 8 byte input
+#[inline(always)]
+fn rrmxmx(mut h: u64, len: u64) -> u64 {
+    h ^= h.rotate_left(49) ^ h.rotate_left(24);
+    h = h.wrapping_mul(0x9fb2_1c65_1e98_df25);
+    h ^= (h >> 35).wrapping_add(len);
+    h = h.wrapping_mul(0x9fb2_1c65_1e98_df25);
+    xorshift64(h, 28)
+}
+
+#[inline(always)]
+fn mix16(input: &[u8], secret: &[u8], seed: u64) -> u64 {
+    let lo = read_u64(input, 0) ^ read_u64(secret, 0).wrapping_add(seed);
+    let hi = read_u64(input, 8) ^ read_u64(secret, 8).wrapping_sub(seed);
+    mul128_fold64(lo, hi)
+}
+
+// mix32 is mix16 for the 128bit output, two 16 byte inputs are mixed into two accumulators
+#[inline(always)]
+fn mix32(acc: (u64, u64), input1: &[u8], input2: &[u8], secret: &[u8], seed: u64) -> (u64, u64) {
+    let mut lo = acc.0.wrapping_add(mix16(input1, secret, seed));
+    lo ^= read_u64(input2, 0).wrapping_add(read_u64(input2, 8));
+    let mut hi = acc.1.wrapping_add(mix16(input2, &secret[16..], seed));
+    hi ^= read_u64(input1, 0).wrapping_add(read_u64(input1, 8));
+    (lo, hi)
+}
+
+// custom_secret derive the secret for long input from the seed
+fn custom_secret(seed: u64) -> [u8; SECRET_SIZE] {
+    let mut secret = DEFAULT_SECRET;
+    for i in 0..SECRET_SIZE / 16 {
+        let lo = read_u64(&DEFAULT_SECRET, i * 16).wrapping_add(seed);
+        let hi = read_u64(&DEFAULT_SECRET, i * 16 + 8).wrapping_sub(seed);
+        secret[i * 16..i * 16 + 8].copy_from_slice(&lo.to_le_bytes());
+        secret[i * 16 + 8..i * 16 + 16].copy_from_slice(&hi.to_le_bytes());
+    }
+    secret
+}
+
+fn xxh3_64_short(input: &[u8], seed: u64) -> u64 {
+    let secret = &DEFAULT_SECRET;
+    let len = input.len();
+    match len {
+        0 => xxh64_avalanche(seed ^ read_u64(secret, 56) ^ read_u64(secret, 64)),
+        1..=3 => {
+            let combo = (input[0] as u32) << 16
+                | (input[len >> 1] as u32) << 24
+                | input[len - 1] as u32
+                | (len as u32) << 8;
+            let flip = ((read_u32(secret, 0) ^ read_u32(secret, 4)) as u64).wrapping_add(seed);
+            xxh64_avalanche(combo as u64 ^ flip)
+        }
+        4..=8 => {
+            let seed = seed ^ (((seed as u32).swap_bytes() as u64) << 32);
+            let input1 = read_u32(input, 0) as u64;
+            let input2 = read_u32(input, len - 4) as u64;
+            let flip = (read_u64(secret, 8) ^ read_u64(secret, 16)).wrapping_sub(seed);
+            let keyed = input2.wrapping_add(input1 << 32) ^ flip;
+            rrmxmx(keyed, len as u64)
+        }
+        9..=16 => {
+            let flip1 = (read_u64(secret, 24) ^ read_u64(secret, 32)).wrapping_add(seed);
+            let flip2 = (read_u64(secret, 40) ^ read_u64(secret, 48)).wrapping_sub(seed);
+            let lo = read_u64(input, 0) ^ flip1;
+            let hi = read_u64(input, len - 8) ^ flip2;
+            let acc = (len as u64)
+                .wrapping_add(lo.swap_bytes())
+                .wrapping_add(hi)
+                .wrapping_add(mul128_fold64(lo, hi));
+            xxh3_avalanche(acc)
+        }
+        17..=128 => {
+            // 16 byte pairs are taken from both ends of the input toward the middle
+            let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+            let pairs = (len - 1) / 32;
+            for i in (0..=pairs).rev() {
+                acc = acc.wrapping_add(mix16(&input[16 * i..], &secret[32 * i..], seed));
+                acc = acc.wrapping_add(mix16(
+                    &input[len - 16 * (i + 1)..],
+                    &secret[32 * i + 16..],
+                    seed,
+                ));
+            }
+            xxh3_avalanche(acc)
+        }
+        _ => {
+            let mut acc = (len as u64).wrapping_mul(PRIME64_1);
+            for i in 0..8 {
+                acc = acc.wrapping_add(mix16(&input[16 * i..], &secret[16 * i..], seed));
+            }
+            acc = xxh3_avalanche(acc);
+            for i in 8..len / 16 {
+                acc = acc.wrapping_add(mix16(&input[16 * i..], &secret[16 * (i - 8) + 3..], seed));
+            }
+            acc = acc.wrapping_add(mix16(
+                &input[len - 16..],
+                &secret[SECRET_SIZE_MIN - 17..],
+                seed,
+            ));
+            xxh3_avalanche(acc)
+        }
+    }
+}
+
+fn xxh3_128_short(input: &[u8], seed: u64) -> u128 {
+    let secret = &DEFAULT_SECRET;
+    let len = input.len();
+    let (lo, hi) = match len {
+        0 => {
+            let flip_lo = read_u64(secret, 64) ^ read_u64(secret, 72);
+            let flip_hi = read_u64(secret, 80) ^ read_u64(secret, 88);
+            (
+                xxh64_avalanche(seed ^ flip_lo),
+                xxh64_avalanche(seed ^ flip_hi),
+            )
+        }
+        1..=3 => {
+            let combo_lo = (input[0] as u32) << 16
+                | (input[len >> 1] as u32) << 24
+                | input[len - 1] as u32
+                | (len as u32) << 8;
+            let combo_hi = combo_lo.swap_bytes().rotate_left(13);
+            let flip_lo = ((read_u32(secret, 0) ^ read_u32(secret, 4)) as u64).wrapping_add(seed);
+            let flip_hi = ((read_u32(secret, 8) ^ read_u32(secret, 12)) as u64).wrapping_sub(seed);
+            (
+                xxh64_avalanche(combo_lo as u64 ^ flip_lo),
+                xxh64_avalanche(combo_hi as u64 ^ flip_hi),
+            )
+        }
+        4..=8 => {
+            let seed = seed ^ (((seed as u32).swap_bytes() as u64) << 32);
+            let input_lo = read_u32(input, 0) as u64;
+            let input_hi = read_u32(input, len - 4) as u64;
+            let flip = (read_u64(secret, 16) ^ read_u64(secret, 24)).wrapping_add(seed);
+            let keyed = input_lo.wrapping_add(input_hi << 32) ^ flip;
+
+            let (mut lo, mut hi) = mul64_to128(keyed, PRIME64_1.wrapping_add((len as u64) << 2));
+            hi = hi.wrapping_add(lo << 1);
+            lo ^= hi >> 3;
+            lo = xorshift64(lo, 35).wrapping_mul(0x9fb2_1c65_1e98_df25);
+            lo = xorshift64(lo, 28);
+            (lo, xxh3_avalanche(hi))
+        }
+        9..=16 => {
+            let flip_lo = (read_u64(secret, 32) ^ read_u64(secret, 40)).wrapping_sub(seed);
+            let flip_hi = (read_u64(secret, 48) ^ read_u64(secret, 56)).wrapping_add(seed);
+            let input_lo = read_u64(input, 0);
+            let mut input_hi = read_u64(input, len - 8);
+
+            let (mut mul_lo, mut mul_hi) = mul64_to128(input_lo ^ input_hi ^ flip_lo, PRIME64_1);
+            mul_lo = mul_lo.wrapping_add((len as u64 - 1) << 54);
+            input_hi ^= flip_hi;
+            mul_hi = mul_hi.wrapping_add(
+                input_hi.wrapping_add((input_hi & 0xffff_ffff).wrapping_mul(PRIME32_2 - 1)),
+            );
+            mul_lo ^= mul_hi.swap_bytes();
+
+            let (lo, mut hi) = mul64_to128(mul_lo, PRIME64_2);
+            hi = hi.wrapping_add(mul_hi.wrapping_mul(PRIME64_2));
+            (xxh3_avalanche(lo), xxh3_avalanche(hi))
+        }
+        17..=128 => {
+            let mut acc = ((len as u64).wrapping_mul(PRIME64_1), 0u64);
+            let pairs = (len - 1) / 32;
+            for i in (0..=pairs).rev() {
+                acc = mix32(
+                    acc,
+                    &input[16 * i..],
+                    &input[len - 16 * (i + 1)..],
+                    &secret[32 * i..],
+                    seed,
+                );
+            }
+            xxh3_128_merge(acc, len, seed)
+        }
+        _ => {
+            let mut acc = ((len as u64).wrapping_mul(PRIME64_1), 0u64);
+            for i in 0..4 {
+                acc = mix32(
+                    acc,
+                    &input[32 * i..],
+                    &input[32 * i + 16..],
+                    &secret[32 * i..],
+                    seed,
+                );
+            }
+            acc = (xxh3_avalanche(acc.0), xxh3_avalanche(acc.1));
+            for i in 4..len / 32 {
+                acc = mix32(
+                    acc,
+                    &input[32 * i..],
+                    &input[32 * i + 16..],
+                    &secret[32 * (i - 4) + 3..],
+                    seed,
+                );
+            }
+            acc = mix32(
+                acc,
+                &input[len - 16..],
+                &input[len - 32..],
+                &secret[SECRET_SIZE_MIN - 17 - 16..],
+                0u64.wrapping_sub(seed),
+            );
+            xxh3_128_merge(acc, len, seed)
+        }
+    };
+    (hi as u128) << 64 | lo as u128
+}
+
+// xxh3_128_merge is the common finalization of 17 to 240 byte input for the 128bit output
+fn xxh3_128_merge(acc: (u64, u64), len: usize, seed: u64) -> (u64, u64) {
+    let lo = acc.0.wrapping_add(acc.1);
+    let hi = acc
+        .0
+        .wrapping_mul(PRIME64_1)
+        .wrapping_add(acc.1.wrapping_mul(PRIME64_4))
+        .wrapping_add((len as u64).wrapping_sub(seed).wrapping_mul(PRIME64_2));
+    (xxh3_avalanche(lo), 0u64.wrapping_sub(xxh3_avalanche(hi)))
+}
+
+// Xxh3State is the streaming state shared by the 64bit and 128bit XXH3
+// Input up to 240 bytes stay in the buffer and are hashed by the short input algorithms at the end.
+// Longer input is accumulated in 64 byte stripes, but a stripe is only consumed when more input follows,
+// because the last stripe of the input is processed differently.
+#[derive(Clone, Copy)]
+struct Xxh3State {
+    seed: u64,
+    secret: [u8; SECRET_SIZE],
+    acc: [u64; 8],
+    buffer: [u8; BUFFER_SIZE],
+    buffered: usize,
+    stripes: usize,
+    length: u64,
+}
+
+impl Xxh3State {
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            secret: custom_secret(seed),
+            acc: INITIAL_ACC,
+            buffer: [0; BUFFER_SIZE],
+            buffered: 0,
+            stripes: 0,
+            length: 0,
+        }
+    }
+
+    // accumulate_512 mix a 64 byte stripe into the 8 accumulators
+    fn accumulate_512(acc: &mut [u64; 8], stripe: &[u8], secret: &[u8]) {
+        for i in 0..8 {
+            let data = read_u64(stripe, i * 8);
+            let key = data ^ read_u64(secret, i * 8);
+            acc[i ^ 1] = acc[i ^ 1].wrapping_add(data);
+            acc[i] = acc[i].wrapping_add((key & 0xffff_ffff).wrapping_mul(key >> 32));
+        }
+    }
+
+    fn scramble(acc: &mut [u64; 8], secret: &[u8]) {
+        for (i, a) in acc.iter_mut().enumerate() {
+            *a = (xorshift64(*a, 47) ^ read_u64(secret, i * 8)).wrapping_mul(PRIME32_1);
+        }
+    }
+
+    // consume_stripes accumulate stripes that are known not to be the last one
+    fn consume_stripes(
+        acc: &mut [u64; 8],
+        stripes: &mut usize,
+        input: &[u8],
+        secret: &[u8; SECRET_SIZE],
+    ) {
+        for stripe in input.chunks_exact(STRIPE_LEN) {
+            Self::accumulate_512(acc, stripe, &secret[*stripes * SECRET_CONSUME_RATE..]);
+            *stripes += 1;
+            if *stripes == STRIPES_PER_BLOCK {
+                Self::scramble(acc, &secret[SECRET_SIZE - STRIPE_LEN..]);
+                *stripes = 0;
+            }
+        }
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // fill the buffer, the full buffer is consumed only when more input follows
+        while !input.is_empty() {
+            if self.buffered == BUFFER_SIZE {
+                Self::consume_stripes(&mut self.acc, &mut self.stripes, &self.buffer, &self.secret);
+                self.buffered = 0;
+            }
+            let take = (BUFFER_SIZE - self.buffered).min(input.len());
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&input[..take]);
+            self.buffered += take;
+            input = &input[take..];
+        }
+    }
+
+    // long_acc finish the accumulators for input longer than 240 bytes
+    fn long_acc(&self) -> [u64; 8] {
+        let mut acc = self.acc;
+        let mut stripes = self.stripes;
+        // every full stripe before the last byte is a normal stripe
+        let full = (self.buffered - 1) / STRIPE_LEN * STRIPE_LEN;
+        Self::consume_stripes(&mut acc, &mut stripes, &self.buffer[..full], &self.secret);
+
+        // The last stripe is the last 64 bytes of the input, it may overlap the already consumed stripes.
+        // If fewer than 64 bytes are buffered, the rest come from the end of the previous buffer.
+        let mut last = [0u8; STRIPE_LEN];
+        if self.buffered >= STRIPE_LEN {
+            last.copy_from_slice(&self.buffer[self.buffered - STRIPE_LEN..self.buffered]);
+        } else {
+            let from_previous = STRIPE_LEN - self.buffered;
+            last[..from_previous].copy_from_slice(&self.buffer[BUFFER_SIZE - from_previous..]);
+            last[from_previous..].copy_from_slice(&self.buffer[..self.buffered]);
+        }
+        Self::accumulate_512(
+            &mut acc,
+            &last,
+            &self.secret[SECRET_SIZE - STRIPE_LEN - SECRET_LASTACC_START..],
+        );
+        acc
+    }
+
+    fn merge_accs(acc: &[u64; 8], secret: &[u8], start: u64) -> u64 {
+        let mut result = start;
+        for i in 0..4 {
+            result = result.wrapping_add(mul128_fold64(
+                acc[2 * i] ^ read_u64(secret, 16 * i),
+                acc[2 * i + 1] ^ read_u64(secret, 16 * i + 8),
+            ));
+        }
+        xxh3_avalanche(result)
+    }
+
+    fn digest_64(&self) -> u64 {
+        if self.length <= MID_SIZE_MAX as u64 {
+            return xxh3_64_short(&self.buffer[..self.buffered], self.seed);
+        }
+        let acc = self.long_acc();
+        Self::merge_accs(
+            &acc,
+            &self.secret[SECRET_MERGEACCS_START..],
+            self.length.wrapping_mul(PRIME64_1),
+        )
+    }
+
+    fn digest_128(&self) -> u128 {
+        if self.length <= MID_SIZE_MAX as u64 {
+            return xxh3_128_short(&self.buffer[..self.buffered], self.seed);
+        }
+        let acc = self.long_acc();
+        let lo = Self::merge_accs(
+            &acc,
+            &self.secret[SECRET_MERGEACCS_START..],
+            self.length.wrapping_mul(PRIME64_1),
+        );
+        let hi = Self::merge_accs(
+            &acc,
+            &self.secret[SECRET_SIZE - 64 - SECRET_MERGEACCS_START..],
+            !self.length.wrapping_mul(PRIME64_2),
+        );
+        (hi as u128) << 64 | lo as u128
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Xxh3_64 {
+    state: Xxh3State,
+    hash: u64,
+}
+
+#[derive(Clone, Copy)]
+pub struct Xxh3_128 {
+    state: Xxh3State,
+    hash: u128,
+}
+
+impl Display for Xxh3_64 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:016x}", self.hash)
+    }
+}
+
+// The 128bit value is printed with the high 64bit first, the same as the canonical form of xxhsum
+impl Display for Xxh3_128 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:032x}", self.hash)
+    }
+}
+
+impl Xxh3_64 {
+    // with_seed method return the Xxh3_64 struct initialized with seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: Xxh3State::new(seed),
+            hash: 0,
+        }
+    }
+
+    // hash method return the 64bit hash value
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Xxh3_128 {
+    // with_seed method return the Xxh3_128 struct initialized with seed
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: Xxh3State::new(seed),
+            hash: 0,
+        }
+    }
+
+    // hash method return the 128bit hash value
+    pub fn hash(&self) -> u128 {
+        self.hash
+    }
+}
+
+// Implement FastDigest trait to Xxh3_64 struct
+impl FastDigest for Xxh3_64 {
+    type Output = Self;
+
+    const HASH_SIZE: usize = 8;
+
+    fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.hash = self.state.digest_64();
+        self
+    }
+}
+
+// Implement FastDigest trait to Xxh3_128 struct
+impl FastDigest for Xxh3_128 {
+    type Output = Self;
+
+    const HASH_SIZE: usize = 16;
+
+    fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.hash = self.state.digest_128();
+        self
+    }
+}
+
+impl Default for Xxh64 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Xxh3_64 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Xxh3_128 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Implement Hasher trait so that xxHash can be used with HashMap and HashSet
+impl Hasher for Xxh64 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.digest()
+    }
+}
+
+impl Hasher for Xxh3_64 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.state.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state.digest_64()
+    }
+}
+
+// Hasher has a 64bit output, the 128bit hash is cut to its low 64bit
+impl Hasher for Xxh3_128 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.state.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state.digest_128() as u64
+    }
+}
+
+// xxh64 function calculate xxHash64 of input at once
+pub fn xxh64(input: &[u8], seed: u64) -> u64 {
+    let mut hasher = Xxh64::with_seed(seed);
+    hasher.update(input);
+    hasher.finalize().hash()
+}
+
+// xxh3_64 function calculate XXH3 64bit hash of input at once
+pub fn xxh3_64(input: &[u8], seed: u64) -> u64 {
+    let mut hasher = Xxh3_64::with_seed(seed);
+    hasher.update(input);
+    hasher.finalize().hash()
+}
+
+// xxh3_128 function calculate XXH3 128bit hash of input at once
+pub fn xxh3_128(input: &[u8], seed: u64) -> u128 {
+    let mut hasher = Xxh3_128::with_seed(seed);
+    hasher.update(input);
+    hasher.finalize().hash()
+}
+
+// Implement the common Digest trait with seed 0
+// The hash value is output in big endian, the same as the Display output
+impl crate::Digest for Xxh64 {
+    type Output = [u8; 8];
+
+    const OUTPUT_SIZE: usize = 8;
+
+    fn new() -> Self {
+        <Self as FastDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FastDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FastDigest::finalize(self).hash().to_be_bytes()
+    }
+}
+
+impl crate::Digest for Xxh3_64 {
+    type Output = [u8; 8];
+
+    const OUTPUT_SIZE: usize = 8;
+
+    fn new() -> Self {
+        <Self as FastDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FastDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FastDigest::finalize(self).hash().to_be_bytes()
+    }
+}
+
+impl crate::Digest for Xxh3_128 {
+    type Output = [u8; 16];
+
+    const OUTPUT_SIZE: usize = 16;
+
+    fn new() -> Self {
+        <Self as FastDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FastDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FastDigest::finalize(self).hash().to_be_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fast::FastDigest;
+
+    const FOX: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+    #[test]
+    fn test_1() {
+        let mut hashtest = crate::fast::xxhash::Xxh64::new();
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("ef46db3751d8e999", result);
+    }
+
+    #[test]
+    fn test_2() {
+        let mut hashtest = crate::fast::xxhash::Xxh64::new();
+        hashtest.update(FOX);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("0b242d361fda71bc", result);
+    }
+
+    #[test]
+    fn test_3() {
+        let mut hashtest = crate::fast::xxhash::Xxh3_64::new();
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("2d06800538d394c2", result);
+    }
+
+    #[test]
+    fn test_4() {
+        let mut hashtest = crate::fast::xxhash::Xxh3_64::with_seed(42);
+        hashtest.update(FOX);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("b4a3f3c36b3c7d26", result);
+    }
+
+    #[test]
+    fn test_5() {
+        let mut hashtest = crate::fast::xxhash::Xxh3_128::new();
+        hashtest.update(b"abc");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("06b05ab6733a618578af5f94892f3950", result);
+    }
+
+    #[test]
+    fn test_6() {
+        let mut hashtest = crate::fast::xxhash::Xxh3_128::new();
+        hashtest.update(FOX);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("ddd650205ca3e7fa24a1cc2e3a8a7651", result);
+    }
+
+    #[test]
+    fn test_long() {
+        // 1000 bytes go through the stripe accumulation of XXH3
+        let input: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        assert_eq!(0x6ef436b00eba4078, crate::fast::xxhash::xxh64(&input, 0));
+        assert_eq!(0xd33dd80b46f60e50, crate::fast::xxhash::xxh3_64(&input, 0));
+        assert_eq!(
+            0x076f7e02b7120d2ad33dd80b46f60e50,
+            crate::fast::xxhash::xxh3_128(&input, 0)
+        );
+    }
+
+    #[test]
+    fn test_streaming() {
+        // split at every stripe and buffer boundary, including the last stripe that overlap the previous buffer
+        let input: Vec<u8> = (0..=255u8).cycle().take(2049).collect();
+        for len in [0, 16, 128, 240, 241, 256, 257, 320, 1024, 1025, 2049] {
+            let input = &input[..len];
+            for split in [1, 63, 64, 65, 256, 257] {
+                let mut a = crate::fast::xxhash::Xxh64::with_seed(3);
+                let mut b = crate::fast::xxhash::Xxh3_64::with_seed(3);
+                let mut c = crate::fast::xxhash::Xxh3_128::with_seed(3);
+                for chunk in input.chunks(split) {
+                    a.update(chunk);
+                    b.update(chunk);
+                    c.update(chunk);
+                }
+                assert_eq!(crate::fast::xxhash::xxh64(input, 3), a.finalize().hash());
+                assert_eq!(crate::fast::xxhash::xxh3_64(input, 3), b.finalize().hash());
+                assert_eq!(crate::fast::xxhash::xxh3_128(input, 3), c.finalize().hash());
+            }
+        }
+    }
+
+    #[test]
+    fn test_hasher() {
+        use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
+
+        let mut hasher = crate::fast::xxhash::Xxh3_64::new();
+        hasher.write(FOX);
+        assert_eq!(crate::fast::xxhash::xxh3_64(FOX, 0), hasher.finish());
+
+        // finish of the 128bit hash is its low 64bit
+        let mut hasher = crate::fast::xxhash::Xxh3_128::default();
+        hasher.write(FOX);
+        assert_eq!(
+            crate::fast::xxhash::xxh3_128(FOX, 0) as u64,
+            hasher.finish()
+        );
+
+        let build = BuildHasherDefault::<crate::fast::xxhash::Xxh64>::default();
+        let mut map = std::collections::HashMap::with_hasher(build.clone());
+        map.insert("shard", 1);
+        assert_eq!(Some(&1), map.get("shard"));
+        assert_eq!(build.hash_one("shard"), build.hash_one("shard"));
+    }
+}
@@ -0,0 +1,576 @@
+// the safety rules of the functions are written once below, not on each function
+#![allow(clippy::missing_safety_doc)]
+
+use crate::adler32::Adler32;
+use crate::fast::fnv::{Fnv1a32, Fnv1a64};
+use crate::fast::murmur3::{Murmur3x64_128, Murmur3x86_32};
+use crate::fast::xxhash::{Xxh3_128, Xxh3_64, Xxh64};
+use crate::fletcher::{Fletcher16, Fletcher32, Fletcher64};
+use crate::md5::MD5;
+use crate::sha1::{Sha1, Sha1Digest};
+use crate::sha1dc::Sha1DC;
+use crate::siphash::{SipHash128_13, SipHash128_24, SipHash13, SipHash24, SipHashDigest};
+use crate::Digest;
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+// C ABI of every digest, the hashes-ffi crate build it as a shared and a static library
+// Each algorithm NAME has the same functions, with an opaque context:
+//   hashes_NAME_ctx *hashes_NAME_new(void);
+//   int hashes_NAME_update(hashes_NAME_ctx *ctx, const uint8_t *data, size_t len);
+//   int hashes_NAME_final(const hashes_NAME_ctx *ctx, uint8_t *out, size_t out_len);
+//   void hashes_NAME_free(hashes_NAME_ctx *ctx);
+//   int hashes_NAME(const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+// The keyed algorithms (SipHash) have no default key, their new and one shot functions take it first:
+//   hashes_NAME_ctx *hashes_NAME_new(const uint8_t key[16]);
+//   int hashes_NAME(const uint8_t key[16], const uint8_t *data, size_t len, uint8_t *out, size_t out_len);
+// The context is the Rust struct in a Box, C only see an incomplete struct type.
+// final write the digest of the input so far and does not change the context, so more input can be added
+// (Go hash.Hash.Sum work the same), free it when it is not needed anymore.
+// out must have room for HASHES_NAME_SIZE bytes, only these bytes are written.
+//
+// Nothing panic across the boundary: a NULL pointer or a small buffer return an error code,
+// and a panic inside the crate is caught and returned as HASHES_ERR_PANIC.
+// The header is made by header() from the same list as the functions, so the two can not drift apart.
+//
+// Safety of all the functions: ctx must come from the new function of the same algorithm and not be freed,
+// key must be readable for 16 bytes, data must be readable for len bytes, out writable for out_len bytes (NULL is fine when the length is 0),
+// and a context must not be used by two threads at the same time.
+
+pub const HASHES_OK: c_int = 0;
+// a NULL context, or a NULL data or out pointer with a length
+pub const HASHES_ERR_NULL: c_int = -1;
+// out_len is smaller than the digest size
+pub const HASHES_ERR_BUFFER: c_int = -2;
+// a panic was caught, it is a bug of the crate
+pub const HASHES_ERR_PANIC: c_int = -3;
+
+// the messages end with NUL for hashes_strerror
+const ERRORS: [(&str, c_int, &str); 4] = [
+    ("HASHES_OK", HASHES_OK, "success\0"),
+    ("HASHES_ERR_NULL", HASHES_ERR_NULL, "null pointer\0"),
+    (
+        "HASHES_ERR_BUFFER",
+        HASHES_ERR_BUFFER,
+        "output buffer too small\0",
+    ),
+    ("HASHES_ERR_PANIC", HASHES_ERR_PANIC, "internal error\0"),
+];
+
+// hashes_strerror return a static NUL terminated message for an error code
+#[no_mangle]
+pub extern "C" fn hashes_strerror(code: c_int) -> *const c_char {
+    let message = ERRORS
+        .iter()
+        .find(|(_, c, _)| *c == code)
+        .map_or("unknown error\0", |(_, _, message)| message);
+    message.as_ptr().cast()
+}
+
+// input function make a slice of the C buffer, None for NULL with a length
+unsafe fn input<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(std::slice::from_raw_parts(data, len))
+    }
+}
+
+// Context is what update and final need of a context
+// Every Digest has it, the SipHash structs have it too but are no Digest, because Digest::new has no key.
+trait Context: Clone {
+    type Output: AsRef<[u8]>;
+
+    const OUTPUT_SIZE: usize;
+
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self) -> Self::Output;
+}
+
+impl<D: Digest + Clone> Context for D {
+    type Output = D::Output;
+
+    const OUTPUT_SIZE: usize = D::OUTPUT_SIZE;
+
+    fn update(&mut self, input: &[u8]) {
+        Digest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Digest::finalize(self)
+    }
+}
+
+// keyed_context macro implement Context to a SipHash struct, the output is the little endian hash
+macro_rules! keyed_context {
+    ($($digest:ty => $size:expr),*) => {
+        $(
+            impl Context for $digest {
+                type Output = [u8; $size];
+
+                const OUTPUT_SIZE: usize = $size;
+
+                fn update(&mut self, input: &[u8]) {
+                    SipHashDigest::update(self, input);
+                }
+
+                fn finalize(self) -> Self::Output {
+                    SipHashDigest::finalize(self).hash().to_le_bytes()
+                }
+            }
+        )*
+    };
+}
+
+keyed_context!(SipHash24 => 8, SipHash13 => 8, SipHash128_24 => 16, SipHash128_13 => 16);
+
+// output function check out, run digest and copy its bytes
+unsafe fn output<D: Context>(
+    out: *mut u8,
+    out_len: usize,
+    digest: impl FnOnce() -> D::Output,
+) -> c_int {
+    if out.is_null() {
+        return HASHES_ERR_NULL;
+    }
+    if out_len < D::OUTPUT_SIZE {
+        return HASHES_ERR_BUFFER;
+    }
+    match catch_unwind(AssertUnwindSafe(digest)) {
+        Ok(bytes) => {
+            ptr::copy_nonoverlapping(bytes.as_ref().as_ptr(), out, D::OUTPUT_SIZE);
+            HASHES_OK
+        }
+        Err(_) => HASHES_ERR_PANIC,
+    }
+}
+
+fn boxed<D>(make: impl FnOnce() -> D) -> *mut D {
+    catch_unwind(AssertUnwindSafe(|| Box::into_raw(Box::new(make())))).unwrap_or(ptr::null_mut())
+}
+
+unsafe fn update<D: Context>(ctx: *mut D, data: *const u8, len: usize) -> c_int {
+    let (Some(ctx), Some(data)) = (ctx.as_mut(), input(data, len)) else {
+        return HASHES_ERR_NULL;
+    };
+    catch_unwind(AssertUnwindSafe(|| ctx.update(data)))
+        .map(|_| HASHES_OK)
+        .unwrap_or(HASHES_ERR_PANIC)
+}
+
+unsafe fn finalize<D: Context>(ctx: *const D, out: *mut u8, out_len: usize) -> c_int {
+    let Some(ctx) = ctx.as_ref() else {
+        return HASHES_ERR_NULL;
+    };
+    output::<D>(out, out_len, || ctx.clone().finalize())
+}
+
+unsafe fn free<D>(ctx: *mut D) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+unsafe fn oneshot<D: Digest + Clone>(
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> c_int {
+    let Some(data) = input(data, len) else {
+        return HASHES_ERR_NULL;
+    };
+    output::<D>(out, out_len, || {
+        let mut digest = D::new();
+        Digest::update(&mut digest, data);
+        Digest::finalize(digest)
+    })
+}
+
+unsafe fn oneshot_with_key<D: Context + SipHashDigest<Output = D>>(
+    key: *const [u8; 16],
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+    out_len: usize,
+) -> c_int {
+    let (Some(key), Some(data)) = (key.as_ref(), input(data, len)) else {
+        return HASHES_ERR_NULL;
+    };
+    output::<D>(out, out_len, || {
+        let mut digest = D::new_with_key(key);
+        Context::update(&mut digest, data);
+        Context::finalize(digest)
+    })
+}
+
+// new_with_key function box a keyed digest, NULL for a NULL key
+unsafe fn new_with_key<D: SipHashDigest<Output = D>>(key: *const [u8; 16]) -> *mut D {
+    match key.as_ref() {
+        Some(key) => boxed(|| D::new_with_key(key)),
+        None => ptr::null_mut(),
+    }
+}
+
+// FfiDigest describe the functions of one algorithm for header()
+pub struct FfiDigest {
+    pub name: &'static str,
+    pub size: usize,
+    // new and the one shot take the key first
+    pub keyed: bool,
+    pub new: &'static str,
+    pub update: &'static str,
+    pub finalize: &'static str,
+    pub free: &'static str,
+    pub oneshot: &'static str,
+    // declarations of the functions only this algorithm has
+    pub extra: &'static [&'static str],
+}
+
+// ffi_digests macro define the functions of each algorithm and the DIGESTS list
+// macro_rules can not build the function names, so they are all written in the list.
+// The algorithms of the keyed list take a key in new and the one shot.
+macro_rules! ffi_digests {
+    (digests [$($name:ident: $digest:ty => $new:ident, $update:ident, $final:ident, $free:ident, $oneshot:ident
+        $(, extra: $extra:expr)?;)*]
+     keyed [$($kname:ident: $kdigest:ty => $knew:ident, $kupdate:ident, $kfinal:ident, $kfree:ident, $koneshot:ident;)*]) => {
+        $(
+            #[no_mangle]
+            pub extern "C" fn $new() -> *mut $digest {
+                boxed(<$digest as Digest>::new)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn $oneshot(
+                data: *const u8,
+                len: usize,
+                out: *mut u8,
+                out_len: usize,
+            ) -> c_int {
+                oneshot::<$digest>(data, len, out, out_len)
+            }
+
+            ffi_context!($digest, $update, $final, $free);
+        )*
+
+        $(
+            #[no_mangle]
+            pub unsafe extern "C" fn $knew(key: *const [u8; 16]) -> *mut $kdigest {
+                new_with_key(key)
+            }
+
+            #[no_mangle]
+            pub unsafe extern "C" fn $koneshot(
+                key: *const [u8; 16],
+                data: *const u8,
+                len: usize,
+                out: *mut u8,
+                out_len: usize,
+            ) -> c_int {
+                oneshot_with_key::<$kdigest>(key, data, len, out, out_len)
+            }
+
+            ffi_context!($kdigest, $kupdate, $kfinal, $kfree);
+        )*
+
+        // DIGESTS list every algorithm of the C API in the order of the header
+        pub const DIGESTS: &[FfiDigest] = &[
+            $(FfiDigest {
+                name: stringify!($name),
+                size: <$digest as Digest>::OUTPUT_SIZE,
+                keyed: false,
+                new: stringify!($new),
+                update: stringify!($update),
+                finalize: stringify!($final),
+                free: stringify!($free),
+                oneshot: stringify!($oneshot),
+                extra: &[$($extra)?],
+            },)*
+            $(FfiDigest {
+                name: stringify!($kname),
+                size: <$kdigest as Context>::OUTPUT_SIZE,
+                keyed: true,
+                new: stringify!($knew),
+                update: stringify!($kupdate),
+                finalize: stringify!($kfinal),
+                free: stringify!($kfree),
+                oneshot: stringify!($koneshot),
+                extra: &[],
+            },)*
+        ];
+    };
+}
+
+// ffi_context macro define update, final and free, the same for the keyed algorithms
+macro_rules! ffi_context {
+    ($digest:ty, $update:ident, $final:ident, $free:ident) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $update(ctx: *mut $digest, data: *const u8, len: usize) -> c_int {
+            update(ctx, data, len)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn $final(
+            ctx: *const $digest,
+            out: *mut u8,
+            out_len: usize,
+        ) -> c_int {
+            finalize(ctx, out, out_len)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn $free(ctx: *mut $digest) {
+            free(ctx)
+        }
+    };
+}
+
+#[rustfmt::skip]
+ffi_digests! {
+    digests [
+        md5: MD5 => hashes_md5_new, hashes_md5_update, hashes_md5_final, hashes_md5_free, hashes_md5;
+        sha1: Sha1 => hashes_sha1_new, hashes_sha1_update, hashes_sha1_final, hashes_sha1_free, hashes_sha1;
+        sha1dc: Sha1DC => hashes_sha1dc_new, hashes_sha1dc_update, hashes_sha1dc_final, hashes_sha1dc_free, hashes_sha1dc,
+            extra: "int hashes_sha1dc_collision_detected(const hashes_sha1dc_ctx *ctx, int *detected);";
+        adler32: Adler32 => hashes_adler32_new, hashes_adler32_update, hashes_adler32_final, hashes_adler32_free, hashes_adler32;
+        fletcher16: Fletcher16 => hashes_fletcher16_new, hashes_fletcher16_update, hashes_fletcher16_final, hashes_fletcher16_free, hashes_fletcher16;
+        fletcher32: Fletcher32 => hashes_fletcher32_new, hashes_fletcher32_update, hashes_fletcher32_final, hashes_fletcher32_free, hashes_fletcher32;
+        fletcher64: Fletcher64 => hashes_fletcher64_new, hashes_fletcher64_update, hashes_fletcher64_final, hashes_fletcher64_free, hashes_fletcher64;
+        fnv1a32: Fnv1a32 => hashes_fnv1a32_new, hashes_fnv1a32_update, hashes_fnv1a32_final, hashes_fnv1a32_free, hashes_fnv1a32;
+        fnv1a64: Fnv1a64 => hashes_fnv1a64_new, hashes_fnv1a64_update, hashes_fnv1a64_final, hashes_fnv1a64_free, hashes_fnv1a64;
+        murmur3_32: Murmur3x86_32 => hashes_murmur3_32_new, hashes_murmur3_32_update, hashes_murmur3_32_final, hashes_murmur3_32_free, hashes_murmur3_32;
+        murmur3_128: Murmur3x64_128 => hashes_murmur3_128_new, hashes_murmur3_128_update, hashes_murmur3_128_final, hashes_murmur3_128_free, hashes_murmur3_128;
+        xxh64: Xxh64 => hashes_xxh64_new, hashes_xxh64_update, hashes_xxh64_final, hashes_xxh64_free, hashes_xxh64;
+        xxh3_64: Xxh3_64 => hashes_xxh3_64_new, hashes_xxh3_64_update, hashes_xxh3_64_final, hashes_xxh3_64_free, hashes_xxh3_64;
+        xxh3_128: Xxh3_128 => hashes_xxh3_128_new, hashes_xxh3_128_update, hashes_xxh3_128_final, hashes_xxh3_128_free, hashes_xxh3_128;
+    ]
+    keyed [
+        siphash24: SipHash24 => hashes_siphash24_new, hashes_siphash24_update, hashes_siphash24_final, hashes_siphash24_free, hashes_siphash24;
+        siphash13: SipHash13 => hashes_siphash13_new, hashes_siphash13_update, hashes_siphash13_final, hashes_siphash13_free, hashes_siphash13;
+        siphash128_24: SipHash128_24 => hashes_siphash128_24_new, hashes_siphash128_24_update, hashes_siphash128_24_final, hashes_siphash128_24_free, hashes_siphash128_24;
+        siphash128_13: SipHash128_13 => hashes_siphash128_13_new, hashes_siphash128_13_update, hashes_siphash128_13_final, hashes_siphash128_13_free, hashes_siphash128_13;
+    ]
+}
+
+// hashes_sha1dc_collision_detected write 1 to detected if a block of the input so far looked like
+// one half of a collision, else 0, and return HASHES_OK or an error like the other functions.
+// The last block is compressed only when the digest is finalized, so a copy is finalized here too.
+#[no_mangle]
+pub unsafe extern "C" fn hashes_sha1dc_collision_detected(
+    ctx: *const Sha1DC,
+    detected: *mut c_int,
+) -> c_int {
+    let (Some(ctx), Some(detected)) = (ctx.as_ref(), detected.as_mut()) else {
+        return HASHES_ERR_NULL;
+    };
+    match catch_unwind(AssertUnwindSafe(|| {
+        Sha1Digest::finalize(ctx.clone()).collision_detected()
+    })) {
+        Ok(found) => {
+            *detected = found as c_int;
+            HASHES_OK
+        }
+        Err(_) => HASHES_ERR_PANIC,
+    }
+}
+
+// header function return the C header of the API, hashes-ffi/include/hashes.h is its output
+pub fn header() -> String {
+    let mut h = String::new();
+    h.push_str(
+        "/* hashes.h - C API of the hashes crate\n\
+         \x20* Generated by hashes::ffi::header(), do not edit.\n\
+         \x20* Regenerate: HASHES_FFI_BLESS=1 cargo test -p hashes-ffi --test header */\n\
+         #ifndef HASHES_H\n\
+         #define HASHES_H\n\
+         \n\
+         #include <stddef.h>\n\
+         #include <stdint.h>\n\
+         \n\
+         #ifdef __cplusplus\n\
+         extern \"C\" {\n\
+         #endif\n\
+         \n\
+         /* Every function return HASHES_OK or one of the errors, new return NULL on failure.\n\
+         \x20* final does not change the context, free it with the free function. */\n",
+    );
+    for (name, code, message) in ERRORS {
+        let message = message.trim_end_matches('\0');
+        writeln!(h, "#define {} {} /* {} */", name, code, message).unwrap();
+    }
+    h.push_str("const char *hashes_strerror(int code);\n");
+
+    for d in DIGESTS {
+        let ctx = format!("hashes_{}_ctx", d.name);
+        writeln!(h, "\n/* {} */", d.name).unwrap();
+        writeln!(
+            h,
+            "#define HASHES_{}_SIZE {}",
+            d.name.to_uppercase(),
+            d.size
+        )
+        .unwrap();
+        writeln!(h, "typedef struct {} {};", ctx, ctx).unwrap();
+        // a keyed algorithm take the key first in new and the one shot
+        let (key, key_arg) = if d.keyed {
+            ("const uint8_t key[16]", "const uint8_t key[16], ")
+        } else {
+            ("void", "")
+        };
+        writeln!(h, "{} *{}({});", ctx, d.new, key).unwrap();
+        writeln!(
+            h,
+            "int {}({} *ctx, const uint8_t *data, size_t len);",
+            d.update, ctx
+        )
+        .unwrap();
+        writeln!(
+            h,
+            "int {}(const {} *ctx, uint8_t *out, size_t out_len);",
+            d.finalize, ctx
+        )
+        .unwrap();
+        writeln!(h, "void {}({} *ctx);", d.free, ctx).unwrap();
+        writeln!(
+            h,
+            "int {}({}const uint8_t *data, size_t len, uint8_t *out, size_t out_len);",
+            d.oneshot, key_arg
+        )
+        .unwrap();
+        for extra in d.extra {
+            writeln!(h, "{}", extra).unwrap();
+        }
+    }
+
+    h.push_str(
+        "\n#ifdef __cplusplus\n\
+         }\n\
+         #endif\n\
+         \n\
+         #endif /* HASHES_H */\n",
+    );
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ffi::*;
+
+    #[test]
+    fn test_1() {
+        // the context and the one shot give the same digest, final does not change the context
+        let mut out = [0u8; 20];
+        let mut again = [0u8; 20];
+        let mut oneshot = [0u8; 20];
+        unsafe {
+            let ctx = hashes_sha1_new();
+            assert_eq!(HASHES_OK, hashes_sha1_update(ctx, b"ab".as_ptr(), 2));
+            assert_eq!(HASHES_OK, hashes_sha1_update(ctx, b"c".as_ptr(), 1));
+            assert_eq!(HASHES_OK, hashes_sha1_final(ctx, out.as_mut_ptr(), 20));
+            assert_eq!(HASHES_OK, hashes_sha1_final(ctx, again.as_mut_ptr(), 20));
+            hashes_sha1_free(ctx);
+            assert_eq!(
+                HASHES_OK,
+                hashes_sha1(b"abc".as_ptr(), 3, oneshot.as_mut_ptr(), 20)
+            );
+        }
+        let result = crate::hex::encode(&out);
+        println!("{}", result);
+        assert_eq!("a9993e364706816aba3e25717850c26c9cd0d89d", result);
+        assert_eq!(out, again);
+        assert_eq!(out, oneshot);
+    }
+
+    #[test]
+    fn test_2() {
+        // errors instead of panics
+        let mut out = [0u8; 16];
+        unsafe {
+            assert_eq!(
+                HASHES_ERR_NULL,
+                hashes_md5_update(ptr::null_mut(), b"a".as_ptr(), 1)
+            );
+            assert_eq!(
+                HASHES_ERR_NULL,
+                hashes_md5(ptr::null(), 1, out.as_mut_ptr(), 16)
+            );
+            assert_eq!(
+                HASHES_ERR_NULL,
+                hashes_md5(b"a".as_ptr(), 1, ptr::null_mut(), 16)
+            );
+            assert_eq!(
+                HASHES_ERR_BUFFER,
+                hashes_md5(b"a".as_ptr(), 1, out.as_mut_ptr(), 15)
+            );
+            assert!(hashes_siphash24_new(ptr::null()).is_null());
+            hashes_md5_free(ptr::null_mut());
+            // NULL data is fine for an empty input
+            assert_eq!(HASHES_OK, hashes_md5(ptr::null(), 0, out.as_mut_ptr(), 16));
+        }
+        assert_eq!("d41d8cd98f00b204e9800998ecf8427e", crate::hex::encode(&out));
+    }
+
+    #[test]
+    fn test_3() {
+        // the header declare every function with its size
+        let h = header();
+        for d in DIGESTS {
+            assert!(h.contains(&format!(
+                "#define HASHES_{}_SIZE {}\n",
+                d.name.to_uppercase(),
+                d.size
+            )));
+            let key = if d.keyed {
+                "const uint8_t key[16]"
+            } else {
+                "void"
+            };
+            assert!(h.contains(&format!("*{}({});", d.new, key)));
+        }
+        assert!(h.contains("#define HASHES_ERR_BUFFER -2 /* output buffer too small */\n"));
+        let message = unsafe { std::ffi::CStr::from_ptr(hashes_strerror(HASHES_ERR_BUFFER)) };
+        assert_eq!("output buffer too small", message.to_str().unwrap());
+        assert_eq!(18, DIGESTS.len());
+    }
+
+    #[test]
+    fn test_4() {
+        // SipHash-2-4 of the empty message with the key 00 01 .. 0f, the first vector of the paper
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let mut out = [0u8; 8];
+        let mut detected = -1;
+        unsafe {
+            assert_eq!(
+                HASHES_OK,
+                hashes_siphash24(&key, ptr::null(), 0, out.as_mut_ptr(), 8)
+            );
+            assert_eq!(
+                HASHES_ERR_NULL,
+                hashes_siphash24(ptr::null(), ptr::null(), 0, out.as_mut_ptr(), 8)
+            );
+
+            // the answer and the error code are apart
+            let dc = hashes_sha1dc_new();
+            assert_eq!(HASHES_OK, hashes_sha1dc_update(dc, b"abc".as_ptr(), 3));
+            assert_eq!(
+                HASHES_OK,
+                hashes_sha1dc_collision_detected(dc, &mut detected)
+            );
+            assert_eq!(
+                HASHES_ERR_NULL,
+                hashes_sha1dc_collision_detected(dc, ptr::null_mut())
+            );
+            assert_eq!(
+                HASHES_ERR_NULL,
+                hashes_sha1dc_collision_detected(ptr::null(), &mut detected)
+            );
+            hashes_sha1dc_free(dc);
+        }
+        assert_eq!("310e0edd47db6f72", crate::hex::encode(&out));
+        assert_eq!(0, detected);
+    }
+}
@@ -0,0 +1,368 @@
+use core::fmt::Display;
+
+// Number of words added before the modulo is taken.
+// sum2 grows by at most WORDS * WORDS / 2 * 2^32 in this interval, which fits in u64.
+const WORDS: usize = 1024;
+
+// FletcherState hold the two running sums shared by every fletcher variant
+// Fletcher-32 and Fletcher-64 work on 16bit and 32bit little endian words,
+// so a word split between two update calls is kept in pending until it is complete.
+#[derive(Clone, Copy)]
+struct FletcherState {
+    sum1: u64,
+    sum2: u64,
+    pending: [u8; 4],
+    pending_len: usize,
+}
+
+impl FletcherState {
+    fn new() -> Self {
+        Self {
+            sum1: 0,
+            sum2: 0,
+            pending: [0; 4],
+            pending_len: 0,
+        }
+    }
+
+    fn add_words(&mut self, words: &[u8], word_size: usize, modulus: u64) {
+        for chunk in words.chunks(word_size * WORDS) {
+            for word in chunk.chunks(word_size) {
+                // each word is converted to an unsigned integer in little endian
+                let value = word
+                    .iter()
+                    .rev()
+                    .fold(0u64, |acc, &byte| (acc << 8) | byte as u64);
+                self.sum1 += value;
+                self.sum2 += self.sum1;
+            }
+            self.sum1 %= modulus;
+            self.sum2 %= modulus;
+        }
+    }
+
+    fn update(&mut self, mut input: &[u8], word_size: usize, modulus: u64) {
+        // complete the word left over from the previous update first
+        if self.pending_len > 0 {
+            let take = (word_size - self.pending_len).min(input.len());
+            self.pending[self.pending_len..self.pending_len + take].copy_from_slice(&input[..take]);
+            self.pending_len += take;
+            input = &input[take..];
+            if self.pending_len < word_size {
+                return;
+            }
+            let pending = self.pending;
+            self.add_words(&pending[..word_size], word_size, modulus);
+            self.pending_len = 0;
+        }
+
+        let full = input.len() - input.len() % word_size;
+        self.add_words(&input[..full], word_size, modulus);
+
+        let rest = &input[full..];
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+    }
+
+    fn finalize(&mut self, word_size: usize, modulus: u64) {
+        // The last incomplete word is padded with 0
+        if self.pending_len > 0 {
+            let mut pending = self.pending;
+            pending[self.pending_len..].fill(0);
+            self.add_words(&pending[..word_size], word_size, modulus);
+            self.pending_len = 0;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Fletcher16 {
+    state: FletcherState,
+}
+
+#[derive(Clone, Copy)]
+pub struct Fletcher32 {
+    state: FletcherState,
+}
+
+#[derive(Clone, Copy)]
+pub struct Fletcher64 {
+    state: FletcherState,
+}
+
+impl Fletcher16 {
+    // checksum method return the 16bit checksum, sum2 is upper 8bit and sum1 is lower 8bit
+    pub fn checksum(&self) -> u16 {
+        ((self.state.sum2 << 8) | self.state.sum1) as u16
+    }
+}
+
+impl Fletcher32 {
+    // checksum method return the 32bit checksum, sum2 is upper 16bit and sum1 is lower 16bit
+    pub fn checksum(&self) -> u32 {
+        ((self.state.sum2 << 16) | self.state.sum1) as u32
+    }
+}
+
+impl Fletcher64 {
+    // checksum method return the 64bit checksum, sum2 is upper 32bit and sum1 is lower 32bit
+    pub fn checksum(&self) -> u64 {
+        (self.state.sum2 << 32) | self.state.sum1
+    }
+}
+
+// Implement Display trait to Fletcher structs
+// The width is the checksum size in hex, so the leading 0 will not disappear
+impl Display for Fletcher16 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:04x}", self.checksum())
+    }
+}
+
+impl Display for Fletcher32 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:08x}", self.checksum())
+    }
+}
+
+impl Display for Fletcher64 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:016x}", self.checksum())
+    }
+}
+
+// FletcherDigest calculate fletcher checksum
+pub trait FletcherDigest {
+    // type related to trait
+    type Output;
+
+    // const for the word size in bytes
+    const WORD_SIZE: usize;
+    // const for the checksum size in bytes
+    const HASH_SIZE: usize = Self::WORD_SIZE * 2;
+    // const for the modulus of the sums, 2^(WORD_SIZE * 8) - 1
+    const MODULUS: u64 = (1u64 << (Self::WORD_SIZE * 8)) - 1;
+
+    fn new() -> Self;
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self) -> Self::Output;
+}
+
+// Implement FletcherDigest trait to Fletcher16 struct
+// Fletcher-16 add 8bit words modulo 255
+impl FletcherDigest for Fletcher16 {
+    type Output = Self;
+
+    const WORD_SIZE: usize = 1;
+
+    fn new() -> Self {
+        Self {
+            state: FletcherState::new(),
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input, Self::WORD_SIZE, Self::MODULUS);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.state.finalize(Self::WORD_SIZE, Self::MODULUS);
+        self
+    }
+}
+
+// Implement FletcherDigest trait to Fletcher32 struct
+// Fletcher-32 add 16bit words modulo 65535
+impl FletcherDigest for Fletcher32 {
+    type Output = Self;
+
+    const WORD_SIZE: usize = 2;
+
+    fn new() -> Self {
+        Self {
+            state: FletcherState::new(),
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input, Self::WORD_SIZE, Self::MODULUS);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.state.finalize(Self::WORD_SIZE, Self::MODULUS);
+        self
+    }
+}
+
+// Implement FletcherDigest trait to Fletcher64 struct
+// Fletcher-64 add 32bit words modulo 4294967295
+impl FletcherDigest for Fletcher64 {
+    type Output = Self;
+
+    const WORD_SIZE: usize = 4;
+
+    fn new() -> Self {
+        Self {
+            state: FletcherState::new(),
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input, Self::WORD_SIZE, Self::MODULUS);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.state.finalize(Self::WORD_SIZE, Self::MODULUS);
+        self
+    }
+}
+
+// Implement the common Digest trait to Fletcher structs
+// The checksum is output in big endian, the same as the Display output
+impl crate::Digest for Fletcher16 {
+    type Output = [u8; 2];
+
+    const OUTPUT_SIZE: usize = 2;
+
+    fn new() -> Self {
+        <Self as FletcherDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FletcherDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FletcherDigest::finalize(self).checksum().to_be_bytes()
+    }
+}
+
+impl crate::Digest for Fletcher32 {
+    type Output = [u8; 4];
+
+    const OUTPUT_SIZE: usize = 4;
+
+    fn new() -> Self {
+        <Self as FletcherDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FletcherDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FletcherDigest::finalize(self).checksum().to_be_bytes()
+    }
+}
+
+impl crate::Digest for Fletcher64 {
+    type Output = [u8; 8];
+
+    const OUTPUT_SIZE: usize = 8;
+
+    fn new() -> Self {
+        <Self as FletcherDigest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        FletcherDigest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        FletcherDigest::finalize(self).checksum().to_be_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fletcher::FletcherDigest;
+
+    #[test]
+    fn test_1() {
+        let input = b"abcde";
+        let mut hashtest = crate::fletcher::Fletcher16::new();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("c8f0", result);
+    }
+
+    #[test]
+    fn test_2() {
+        let input = b"abcdef";
+        let mut hashtest = crate::fletcher::Fletcher16::new();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("2057", result);
+    }
+
+    #[test]
+    fn test_3() {
+        let input = b"abcde";
+        let mut hashtest = crate::fletcher::Fletcher32::new();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("f04fc729", result);
+    }
+
+    #[test]
+    fn test_4() {
+        let input = b"abcdefgh";
+        let mut hashtest = crate::fletcher::Fletcher32::new();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("ebe19591", result);
+    }
+
+    #[test]
+    fn test_5() {
+        let input = b"abcde";
+        let mut hashtest = crate::fletcher::Fletcher64::new();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("c8c6c527646362c6", result);
+    }
+
+    #[test]
+    fn test_6() {
+        let input = b"abcdefgh";
+        let mut hashtest = crate::fletcher::Fletcher64::new();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("312e2b28cccac8c6", result);
+    }
+
+    #[test]
+    fn test_streaming() {
+        // words split between update calls must give the same checksum
+        let input = [0xa5u8; 10_007];
+        let mut whole = crate::fletcher::Fletcher64::new();
+        whole.update(&input);
+        let whole = whole.finalize();
+
+        let mut split = crate::fletcher::Fletcher64::new();
+        for chunk in input.chunks(3) {
+            split.update(chunk);
+        }
+        let split = split.finalize();
+
+        assert_eq!(whole.checksum(), split.checksum());
+    }
+}
@@ -0,0 +1,44 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+// encode function convert bytes to a lowercase hex string
+// Each byte is written with 02x, so the leading 0 will not disappear
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut acc, b| {
+        write!(&mut acc, "{:02x}", b).unwrap();
+        acc
+    })
+}
+
+// decode function convert a hex string to bytes
+// Both lowercase and uppercase are accepted, None is returned for odd length or a non hex character.
+pub fn decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let high = (pair[0] as char).to_digit(16)?;
+            let low = (pair[1] as char).to_digit(16)?;
+            Some((high * 16 + low) as u8)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_encode() {
+        assert_eq!("", crate::hex::encode(&[]));
+        assert_eq!("00ff0a", crate::hex::encode(&[0x00, 0xff, 0x0a]));
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Some(vec![0x00, 0xff, 0x0a]), crate::hex::decode("00FF0a"));
+        assert_eq!(None, crate::hex::decode("0"));
+        assert_eq!(None, crate::hex::decode("zz"));
+    }
+}
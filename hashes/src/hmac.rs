@@ -0,0 +1,313 @@
+use crate::Digest;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+// HMAC (RFC 2104) keyed message authentication code on top of a Digest
+// HMAC(K, m) = H((K' ^ opad) || H((K' ^ ipad) || m))
+// K' is the key padded with 0 to the block size, or the hash of the key if it is longer than a block.
+// The outer hash take the inner digest as a fresh message, so the length extension attack
+// that break H(key || message) does not work here (see attacks::length_extension).
+//
+// Hmac keep the padded key in an array, so it need no allocator.
+// The key derivation functions built on HMAC are here too, they need the alloc feature:
+//   HKDF (RFC 5869) extract a pseudorandom key from a secret with a salt and expand it to any length,
+//   PBKDF2 (RFC 8018) derive a key from a password with a salt and many iterations, to slow down guessing.
+
+// HmacDigest is a Digest with a known block size, the key is padded to this size
+pub trait HmacDigest: Digest {
+    // const for the block size in bytes
+    const BLOCK_SIZE: usize;
+}
+
+#[cfg(feature = "sha1")]
+impl HmacDigest for crate::sha1::Sha1 {
+    const BLOCK_SIZE: usize = 64;
+}
+
+#[cfg(feature = "md5")]
+impl HmacDigest for crate::md5::MD5 {
+    const BLOCK_SIZE: usize = 64;
+}
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+// MAX_BLOCK_SIZE is the size of the array of the padded key, the largest block of a HmacDigest
+const MAX_BLOCK_SIZE: usize = 128;
+
+#[derive(Clone)]
+pub struct Hmac<D: HmacDigest> {
+    inner: D,
+    // K' ^ opad, the outer hash is started only in finalize, only the first BLOCK_SIZE bytes are used
+    outer_key: [u8; MAX_BLOCK_SIZE],
+}
+
+impl<D: HmacDigest> Hmac<D> {
+    // new method start a HMAC with the key
+    pub fn new(key: &[u8]) -> Self {
+        assert!(
+            D::BLOCK_SIZE <= MAX_BLOCK_SIZE,
+            "the block is too large for Hmac"
+        );
+        let mut block_key = [0u8; MAX_BLOCK_SIZE];
+        if key.len() > D::BLOCK_SIZE {
+            let mut digest = D::new();
+            digest.update(key);
+            let hash = digest.finalize();
+            block_key[..hash.as_ref().len()].copy_from_slice(hash.as_ref());
+        } else {
+            block_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner_key = block_key;
+        let mut outer_key = block_key;
+        inner_key.iter_mut().for_each(|b| *b ^= IPAD);
+        outer_key.iter_mut().for_each(|b| *b ^= OPAD);
+        let mut inner = D::new();
+        inner.update(&inner_key[..D::BLOCK_SIZE]);
+        Self { inner, outer_key }
+    }
+
+    // update method add input message
+    pub fn update(&mut self, input: &[u8]) {
+        self.inner.update(input);
+    }
+
+    // finalize method return the MAC
+    pub fn finalize(self) -> D::Output {
+        let inner = self.inner.finalize();
+        let mut outer = D::new();
+        outer.update(&self.outer_key[..D::BLOCK_SIZE]);
+        outer.update(inner.as_ref());
+        outer.finalize()
+    }
+
+    // verify method compare the MAC with an expected one in constant time
+    // Every byte is compared even after a difference, so the time does not tell how many bytes matched.
+    pub fn verify(self, expected: &[u8]) -> bool {
+        let mac = self.finalize();
+        let mac = mac.as_ref();
+        mac.len() == expected.len()
+            && mac
+                .iter()
+                .zip(expected.iter())
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+
+    // verify_truncated method compare the first bytes of the MAC with a truncated one in constant time
+    // RFC 2104 section 5: a tag must keep at least half of the digest and not less than 80 bits,
+    // a shorter tag is refused even if it matches.
+    pub fn verify_truncated(self, expected: &[u8]) -> bool {
+        let mac = self.finalize();
+        let mac = mac.as_ref();
+        expected.len() >= (mac.len() / 2).max(10)
+            && expected.len() <= mac.len()
+            && mac
+                .iter()
+                .zip(expected.iter())
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+// hmac function calculate the MAC of a whole message at once
+pub fn hmac<D: HmacDigest>(key: &[u8], message: &[u8]) -> D::Output {
+    let mut mac = Hmac::<D>::new(key);
+    mac.update(message);
+    mac.finalize()
+}
+
+// hkdf_extract function return the pseudorandom key PRK = HMAC(salt, ikm)
+// An empty salt is the same as a salt of zeros of the digest size, as HMAC pad the key with zeros.
+pub fn hkdf_extract<D: HmacDigest>(salt: &[u8], ikm: &[u8]) -> D::Output {
+    hmac::<D>(salt, ikm)
+}
+
+// hkdf_expand function return length bytes of output keying material from the PRK
+//   T(0) = empty, T(i) = HMAC(PRK, T(i-1) || info || i), OKM = first length bytes of T(1) || T(2) || ...
+// None when length is more than 255 digests, the counter is a single byte.
+#[cfg(feature = "alloc")]
+pub fn hkdf_expand<D: HmacDigest>(prk: &[u8], info: &[u8], length: usize) -> Option<Vec<u8>> {
+    if length > 255 * D::OUTPUT_SIZE {
+        return None;
+    }
+    let mut okm = Vec::with_capacity(length);
+    let mut t = Vec::new();
+    let mut counter = 1u8;
+    while okm.len() < length {
+        let mut mac = Hmac::<D>::new(prk);
+        mac.update(&t);
+        mac.update(info);
+        mac.update(&[counter]);
+        t = mac.finalize().as_ref().to_vec();
+        let take = t.len().min(length - okm.len());
+        okm.extend_from_slice(&t[..take]);
+        counter = counter.wrapping_add(1);
+    }
+    Some(okm)
+}
+
+// hkdf function do extract and then expand
+#[cfg(feature = "alloc")]
+pub fn hkdf<D: HmacDigest>(salt: &[u8], ikm: &[u8], info: &[u8], length: usize) -> Option<Vec<u8>> {
+    hkdf_expand::<D>(hkdf_extract::<D>(salt, ikm).as_ref(), info, length)
+}
+
+// pbkdf2 function derive length bytes from a password and a salt with PBKDF2-HMAC
+//   T(i) = U1 ^ U2 ^ ... ^ Uc, U1 = HMAC(P, S || i as 4 bytes big endian), Uj = HMAC(P, U(j-1))
+// None when iterations is 0 or the key is longer than (2^32 - 1) digests.
+// The HMAC keyed with the password is cloned for every U, so the key is padded only once.
+#[cfg(feature = "alloc")]
+pub fn pbkdf2<D: HmacDigest + Clone>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    length: usize,
+) -> Option<Vec<u8>> {
+    if iterations == 0 || length as u64 > u32::MAX as u64 * D::OUTPUT_SIZE as u64 {
+        return None;
+    }
+    let keyed = Hmac::<D>::new(password);
+    let mut dk = Vec::with_capacity(length);
+    let mut block = 1u32;
+    while dk.len() < length {
+        let mut mac = keyed.clone();
+        mac.update(salt);
+        mac.update(&block.to_be_bytes());
+        let mut u = mac.finalize().as_ref().to_vec();
+        let mut t = u.clone();
+        for _ in 1..iterations {
+            let mut mac = keyed.clone();
+            mac.update(&u);
+            u = mac.finalize().as_ref().to_vec();
+            t.iter_mut().zip(u.iter()).for_each(|(t, u)| *t ^= u);
+        }
+        let take = t.len().min(length - dk.len());
+        dk.extend_from_slice(&t[..take]);
+        block += 1;
+    }
+    Some(dk)
+}
+
+#[cfg(all(test, feature = "alloc", feature = "md5", feature = "sha1"))]
+mod tests {
+    use crate::hmac::{hkdf, hkdf_expand, hkdf_extract, hmac, pbkdf2, Hmac};
+    use crate::md5::MD5;
+    use crate::sha1::Sha1;
+
+    // test vectors of RFC 2202
+    #[test]
+    fn test_1() {
+        let result = crate::hex::encode(&hmac::<Sha1>(&[0x0b; 20], b"Hi There"));
+        println!("{}", result);
+
+        assert_eq!("b617318655057264e28bc0b6fb378c8ef146be00", result);
+    }
+
+    #[test]
+    fn test_2() {
+        let result = crate::hex::encode(&hmac::<Sha1>(b"Jefe", b"what do ya want for nothing?"));
+        println!("{}", result);
+
+        assert_eq!("effcdf6ae5eb2fa2d27416d5f184df9c259a7c79", result);
+    }
+
+    #[test]
+    fn test_3() {
+        // the key is longer than a block, so the hash of the key is used
+        let result = crate::hex::encode(&hmac::<Sha1>(
+            &[0xaa; 80],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        ));
+        println!("{}", result);
+
+        assert_eq!("aa4ae5e15272d00e95705637ce8a3b55ed402112", result);
+    }
+
+    #[test]
+    fn test_4() {
+        let result = crate::hex::encode(&hmac::<MD5>(b"Jefe", b"what do ya want for nothing?"));
+        println!("{}", result);
+
+        assert_eq!("750c783e6ab0b503eaa86e310a5db738", result);
+    }
+
+    #[test]
+    fn test_5() {
+        let mut mac = Hmac::<Sha1>::new(b"Jefe");
+        mac.update(b"what do ya want ");
+        mac.update(b"for nothing?");
+        assert!(
+            mac.verify(&crate::hex::decode("effcdf6ae5eb2fa2d27416d5f184df9c259a7c79").unwrap())
+        );
+
+        let mac = Hmac::<Sha1>::new(b"Jefe");
+        assert!(!mac.verify(&[0; 20]));
+
+        // 10 bytes is the shortest tag of SHA-1, 8 bytes is refused even when they match
+        let tag = crate::hex::decode("effcdf6ae5eb2fa2d274").unwrap();
+        let mut mac = Hmac::<Sha1>::new(b"Jefe");
+        mac.update(b"what do ya want for nothing?");
+        assert!(mac.clone().verify_truncated(&tag));
+        assert!(!mac.verify_truncated(&tag[..8]));
+    }
+
+    // test vectors of RFC 5869, the SHA-1 cases 4 and 7
+    #[test]
+    fn test_6() {
+        let ikm = [0x0b; 11];
+        let salt = crate::hex::decode("000102030405060708090a0b0c").unwrap();
+        let info = crate::hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let prk = hkdf_extract::<Sha1>(&salt, &ikm);
+        assert_eq!(
+            "9b6c18c432a7bf8f0e71c8eb88f4b30baa2ba243",
+            crate::hex::encode(&prk)
+        );
+        let result = crate::hex::encode(&hkdf_expand::<Sha1>(&prk, &info, 42).unwrap());
+        println!("{}", result);
+
+        assert_eq!(
+            "085a01ea1b10f36933068b56efa5ad81a4f14b822f5b091568a9cdd4f155fda2c22e422478d305f3f896",
+            result
+        );
+
+        // no salt and no info
+        let result = crate::hex::encode(&hkdf::<Sha1>(&[], &[0x0c; 22], &[], 42).unwrap());
+        assert_eq!(
+            "2c91117204d745f3500d636a62f64f0ab3bae548aa53d423b0d1f27ebba6f5e5673a081d70cce7acfc48",
+            result
+        );
+        // the counter is one byte, 255 blocks at most
+        assert_eq!(
+            255 * 20,
+            hkdf::<Sha1>(&[], &ikm, &[], 255 * 20).unwrap().len()
+        );
+        assert_eq!(None, hkdf::<Sha1>(&[], &ikm, &[], 255 * 20 + 1));
+    }
+
+    // test vectors of RFC 6070
+    #[test]
+    fn test_7() {
+        let result = crate::hex::encode(&pbkdf2::<Sha1>(b"password", b"salt", 1, 20).unwrap());
+        assert_eq!("0c60c80f961f0e71f3a9b524af6012062fe037a6", result);
+        let result = crate::hex::encode(&pbkdf2::<Sha1>(b"password", b"salt", 4096, 20).unwrap());
+        assert_eq!("4b007901b765489abead49d926f721d065a429c1", result);
+        let result = crate::hex::encode(
+            &pbkdf2::<Sha1>(
+                b"passwordPASSWORDpassword",
+                b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+                4096,
+                25,
+            )
+            .unwrap(),
+        );
+        assert_eq!("3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038", result);
+        let result =
+            crate::hex::encode(&pbkdf2::<Sha1>(b"pass\0word", b"sa\0lt", 4096, 16).unwrap());
+        println!("{}", result);
+
+        assert_eq!("56fa6aa75548099dcc37d7f03425e0c3", result);
+        assert_eq!(None, pbkdf2::<Sha1>(b"password", b"salt", 0, 20));
+    }
+}
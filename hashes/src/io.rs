@@ -0,0 +1,395 @@
+use crate::Digest;
+use std::io::{self, Read, Write};
+
+// Implement io::Write trait to every digest struct
+// With this, io::copy(&mut file, &mut hasher) hash a file without reading it into a Vec first.
+// io::Write can not be implemented for every Digest at once by a blanket impl (it is a foreign trait),
+// so the impl is generated for each struct by this macro, the cfg of its feature go before the struct.
+// The first ident is the trait whose update is called, the SipHash structs are no Digest (they need a key).
+macro_rules! impl_write {
+    ($trait:ident: $($(#[$attr:meta])* $digest:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl Write for $digest {
+                fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                    $trait::update(self, buf);
+                    Ok(buf.len())
+                }
+
+                fn flush(&mut self) -> io::Result<()> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_write!(
+    Digest:
+    #[cfg(feature = "sha1")]
+    crate::sha1::Sha1,
+    #[cfg(feature = "md5")]
+    crate::md5::MD5,
+    #[cfg(feature = "adler32")]
+    crate::adler32::Adler32,
+    #[cfg(feature = "fletcher")]
+    crate::fletcher::Fletcher16,
+    #[cfg(feature = "fletcher")]
+    crate::fletcher::Fletcher32,
+    #[cfg(feature = "fletcher")]
+    crate::fletcher::Fletcher64,
+    #[cfg(feature = "fnv")]
+    crate::fast::fnv::Fnv1a32,
+    #[cfg(feature = "fnv")]
+    crate::fast::fnv::Fnv1a64,
+    #[cfg(feature = "murmur3")]
+    crate::fast::murmur3::Murmur3x86_32,
+    #[cfg(feature = "murmur3")]
+    crate::fast::murmur3::Murmur3x64_128,
+    #[cfg(feature = "xxhash")]
+    crate::fast::xxhash::Xxh64,
+    #[cfg(feature = "xxhash")]
+    crate::fast::xxhash::Xxh3_64,
+    #[cfg(feature = "xxhash")]
+    crate::fast::xxhash::Xxh3_128,
+);
+
+#[cfg(feature = "siphash")]
+use crate::siphash::SipHashDigest;
+
+impl_write!(
+    SipHashDigest:
+    #[cfg(feature = "siphash")]
+    crate::siphash::SipHash24,
+    #[cfg(feature = "siphash")]
+    crate::siphash::SipHash13,
+    #[cfg(feature = "siphash")]
+    crate::siphash::SipHash128_24,
+    #[cfg(feature = "siphash")]
+    crate::siphash::SipHash128_13,
+);
+
+// HashingReader hash every byte read from the inner reader
+// The data is passed through unchanged, so the caller can process it and get the digest in one pass.
+pub struct HashingReader<R, D> {
+    inner: R,
+    digest: D,
+}
+
+impl<R: Read, D: Digest> HashingReader<R, D> {
+    // new method wrap the reader with a new digest
+    pub fn new(inner: R) -> Self {
+        Self::with_digest(inner, D::new())
+    }
+
+    // with_digest method wrap the reader with an existing digest, for keyed or seeded digests
+    pub fn with_digest(inner: R, digest: D) -> Self {
+        Self { inner, digest }
+    }
+
+    // get_ref method return a reference to the inner reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    // finalize method return the inner reader and the digest of all bytes read so far
+    pub fn finalize(self) -> (R, D::Output) {
+        (self.inner, self.digest.finalize())
+    }
+}
+
+impl<R: Read, D: Digest> Read for HashingReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        // only the bytes actually read are hashed
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+// HashingWriter hash every byte written to the inner writer
+// Only the bytes accepted by the inner writer are hashed, so a short write does not break the digest.
+pub struct HashingWriter<W, D> {
+    inner: W,
+    digest: D,
+}
+
+impl<W: Write, D: Digest> HashingWriter<W, D> {
+    // new method wrap the writer with a new digest
+    pub fn new(inner: W) -> Self {
+        Self::with_digest(inner, D::new())
+    }
+
+    // with_digest method wrap the writer with an existing digest, for keyed or seeded digests
+    pub fn with_digest(inner: W, digest: D) -> Self {
+        Self { inner, digest }
+    }
+
+    // get_ref method return a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    // finalize method return the inner writer and the digest of all bytes written so far
+    pub fn finalize(self) -> (W, D::Output) {
+        (self.inner, self.digest.finalize())
+    }
+}
+
+impl<W: Write, D: Digest> Write for HashingWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// VerifyingReader hash every byte read from the inner reader and check the digest at EOF
+// If the digest does not match, the read that reach EOF return an InvalidData error instead of Ok(0).
+// The bytes are passed through before the check, so they must not be trusted until EOF is reached without error.
+// With an expected length, more data than expected fail immediately and less data fail at EOF before the digest check.
+pub struct VerifyingReader<R, D: Digest> {
+    inner: R,
+    state: VerifyState<D>,
+    expected: D::Output,
+    expected_len: Option<u64>,
+    len: u64,
+}
+
+enum VerifyState<D> {
+    Hashing(D),
+    Verified,
+    // once failed, every following read return the same error
+    Failed(io::ErrorKind, String),
+}
+
+impl<R: Read, D: Digest> VerifyingReader<R, D> {
+    // new method wrap the reader with the expected digest
+    pub fn new(inner: R, expected: D::Output) -> Self {
+        Self::with_digest(inner, D::new(), expected)
+    }
+
+    // with_digest method wrap the reader with an existing digest, for keyed or seeded digests
+    pub fn with_digest(inner: R, digest: D, expected: D::Output) -> Self {
+        Self {
+            inner,
+            state: VerifyState::Hashing(digest),
+            expected,
+            expected_len: None,
+            len: 0,
+        }
+    }
+
+    // expected_len method set the expected length in bytes
+    pub fn expected_len(mut self, len: u64) -> Self {
+        self.expected_len = Some(len);
+        self
+    }
+
+    // verified method return true after EOF is reached and the digest matched
+    pub fn verified(&self) -> bool {
+        matches!(self.state, VerifyState::Verified)
+    }
+
+    // into_inner method return the inner reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn fail(&mut self, kind: io::ErrorKind, message: String) -> io::Error {
+        self.state = VerifyState::Failed(kind, message.clone());
+        io::Error::new(kind, message)
+    }
+
+    // verify method is called at EOF, it check the length and the digest
+    fn verify(&mut self) -> io::Result<()> {
+        if let Some(expected_len) = self.expected_len {
+            if self.len < expected_len {
+                let message = format!(
+                    "truncated: expected {} bytes, got {}",
+                    expected_len, self.len
+                );
+                return Err(self.fail(io::ErrorKind::UnexpectedEof, message));
+            }
+        }
+        let VerifyState::Hashing(digest) =
+            std::mem::replace(&mut self.state, VerifyState::Verified)
+        else {
+            return Ok(());
+        };
+        let actual = digest.finalize();
+        if actual != self.expected {
+            let message = format!(
+                "digest mismatch: expected {}, got {}",
+                crate::hex::encode(self.expected.as_ref()),
+                crate::hex::encode(actual.as_ref())
+            );
+            return Err(self.fail(io::ErrorKind::InvalidData, message));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read, D: Digest> Read for VerifyingReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &self.state {
+            VerifyState::Hashing(_) => {}
+            VerifyState::Verified => return Ok(0),
+            VerifyState::Failed(kind, message) => {
+                return Err(io::Error::new(*kind, message.clone()))
+            }
+        }
+
+        let n = self.inner.read(buf)?;
+        // Ok(0) with an empty buf is not EOF
+        if n == 0 && !buf.is_empty() {
+            self.verify()?;
+            return Ok(0);
+        }
+
+        self.len += n as u64;
+        if let Some(expected_len) = self.expected_len {
+            if self.len > expected_len {
+                let message = format!("too long: expected {} bytes, got more", expected_len);
+                return Err(self.fail(io::ErrorKind::InvalidData, message));
+            }
+        }
+        if let VerifyState::Hashing(digest) = &mut self.state {
+            digest.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(all(test, feature = "all"))]
+mod tests {
+    use crate::hex::encode as hex;
+    use crate::io::{HashingReader, HashingWriter, VerifyingReader};
+    use crate::Digest;
+    use std::io::{self, Read, Write};
+
+    const MESSAGE: &[u8] = b"Aa0Aa1Aa2Aa3Aa4Aa5Aa6Aa7Aa8Aa9Ab0Ab1Ab2Ab3Ab4Ab5Ab6Ab7Ab8Ab9Ac0Ac1Ac2Ac3Ac4Ac5Ac6Ac7Ac8Ac9Ad0Ad1Ad2Ad3Ad4Ad5Ad6Ad7Ad8Ad9Ae0Ae1Ae2Ae3Ae4Ae5Ae6Ae7Ae8Ae9Af0Af1Af2Af3Af4Af5Af6Af7Af8Af9Ag0Ag1Ag2Ag3";
+
+    #[test]
+    fn test_copy() {
+        let mut hasher = crate::sha1::Sha1::new();
+        io::copy(&mut &MESSAGE[..], &mut hasher).unwrap();
+        let result = hex(&hasher.finalize());
+        println!("{}", result);
+
+        assert_eq!("3400932512e8e8fc2b51c9d8784dffbe1495b449", result);
+    }
+
+    #[test]
+    fn test_reader() {
+        let mut reader = HashingReader::<_, crate::md5::MD5>::new(b"abc".as_slice());
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        let (_, digest) = reader.finalize();
+        let result = hex(&digest);
+        println!("{}", result);
+
+        assert_eq!(b"abc", data.as_slice());
+        assert_eq!("900150983cd24fb0d6963f7d28e17f72", result);
+    }
+
+    #[test]
+    fn test_writer() {
+        let mut writer = HashingWriter::<_, crate::sha1::Sha1>::new(Vec::new());
+        for chunk in MESSAGE.chunks(7) {
+            writer.write_all(chunk).unwrap();
+        }
+        let (data, digest) = writer.finalize();
+        let result = hex(&digest);
+        println!("{}", result);
+
+        assert_eq!(MESSAGE, data.as_slice());
+        assert_eq!("3400932512e8e8fc2b51c9d8784dffbe1495b449", result);
+    }
+
+    #[test]
+    fn test_short_write() {
+        // a writer that accept at most 3 bytes per write
+        struct Short(Vec<u8>);
+        impl Write for Short {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                let n = buf.len().min(3);
+                self.0.extend_from_slice(&buf[..n]);
+                Ok(n)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = HashingWriter::<_, crate::md5::MD5>::new(Short(Vec::new()));
+        writer.write_all(b"message digest").unwrap();
+        let (inner, digest) = writer.finalize();
+
+        assert_eq!(b"message digest", inner.0.as_slice());
+        assert_eq!("f96b697d7cb7938d525a2f31aaf161d0", hex(&digest));
+    }
+
+    #[test]
+    fn test_verify_ok() {
+        let mut expected = crate::sha1::Sha1::new();
+        expected.update(MESSAGE);
+        let expected = expected.finalize();
+
+        let mut reader = VerifyingReader::<_, crate::sha1::Sha1>::new(MESSAGE, expected)
+            .expected_len(MESSAGE.len() as u64);
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+
+        assert!(reader.verified());
+        assert_eq!(MESSAGE, data.as_slice());
+    }
+
+    #[test]
+    fn test_verify_corrupt() {
+        let mut expected = crate::md5::MD5::new();
+        expected.update(b"Hello");
+        let expected = expected.finalize();
+
+        let mut reader = VerifyingReader::<_, crate::md5::MD5>::new(&b"Hellp"[..], expected);
+        let mut data = Vec::new();
+        let err = reader.read_to_end(&mut data).unwrap_err();
+        println!("{}", err);
+
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(!reader.verified());
+        // the reader keep failing after the mismatch
+        assert!(reader.read(&mut [0u8; 8]).is_err());
+    }
+
+    #[test]
+    fn test_verify_truncated() {
+        let mut expected = crate::md5::MD5::new();
+        expected.update(b"Hello");
+        let expected = expected.finalize();
+
+        let mut reader =
+            VerifyingReader::<_, crate::md5::MD5>::new(&b"Hell"[..], expected).expected_len(5);
+        let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+    }
+
+    #[test]
+    fn test_verify_too_long() {
+        let mut expected = crate::md5::MD5::new();
+        expected.update(b"Hello");
+        let expected = expected.finalize();
+
+        let mut reader =
+            VerifyingReader::<_, crate::md5::MD5>::new(&b"Hello!"[..], expected).expected_len(5);
+        let mut buf = [0u8; 6];
+        let err = reader.read(&mut buf).unwrap_err();
+
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+}
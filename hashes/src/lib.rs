@@ -1,2 +1,48 @@
+// Without the std feature the crate is no_std, the hashes then need no allocator at all
+// and only what is enabled by the features of Cargo.toml is compiled.
+// The unit tests always have std, for println! and format!.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "adler32")]
+pub mod adler32;
+#[cfg(feature = "tools")]
+pub mod algorithm;
+#[cfg(feature = "tools")]
+pub mod analysis;
+#[cfg(feature = "tools")]
+pub mod attacks;
+pub mod digest;
+#[cfg(any(feature = "fnv", feature = "murmur3", feature = "xxhash"))]
+pub mod fast;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fletcher")]
+pub mod fletcher;
+#[cfg(feature = "alloc")]
+pub mod hex;
+pub mod hmac;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "tools")]
+pub mod manifest;
+#[cfg(feature = "md5")]
 pub mod md5;
+#[cfg(feature = "tools")]
+pub mod multi;
+#[cfg(feature = "sha1")]
 pub mod sha1;
+#[cfg(feature = "sha1dc")]
+pub mod sha1dc;
+#[cfg(feature = "siphash")]
+pub mod siphash;
+#[cfg(all(feature = "alloc", any(feature = "md5", feature = "sha1")))]
+pub mod state;
+#[cfg(feature = "tools")]
+pub mod sums;
+
+#[cfg(feature = "tools")]
+pub use algorithm::Algorithm;
+pub use digest::Digest;
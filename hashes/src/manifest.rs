@@ -0,0 +1,503 @@
+use crate::multi::MultiHasher;
+use crate::Algorithm;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+// Manifest of a directory tree in the hashdeep format
+// The file start with two header lines, then one line for each file:
+//
+//   %%%% HASHDEEP-1.0
+//   %%%% size,md5,sha1,filename
+//   5,8b1a9953c4611296a827abf8c47804d7,f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0,dir/hello.txt
+//
+// Paths are relative to the root directory and use '/' on every platform,
+// so a backup restored to another place can be audited with the same manifest.
+// Lines starting with "##" are comments, hashdeep write the command line there.
+// Paths are kept as PathBuf and written as their bytes, so a name which is not UTF-8 still match
+// the file it came from when the manifest is read back on the same platform.
+// A path with a newline, carriage return, comma or backslash is escaped like in the checksum lines
+// of coreutils: the line start with '\' and those bytes become \n, \r, \, and \\.
+// hashdeep has no escaping, a newline in a name already break its own manifests.
+// errors is the list of (path, message) of the files or directories which could not be read,
+// they are not in the manifest, but one bad file does not stop the others from being hashed.
+pub struct Manifest {
+    pub algorithms: Vec<Algorithm>,
+    pub entries: Vec<ManifestEntry>,
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+// ManifestEntry is one file of the manifest, digests are in the order of Manifest::algorithms
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub size: u64,
+    pub digests: Vec<Vec<u8>>,
+    pub path: PathBuf,
+}
+
+// AuditReport is the result of comparing a directory tree with a manifest
+// moved is the pair of (path in the manifest, path in the tree),
+// errors is the (path, message) of the files of the tree which could not be read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AuditReport {
+    pub matched: Vec<PathBuf>,
+    pub moved: Vec<(PathBuf, PathBuf)>,
+    pub new: Vec<PathBuf>,
+    pub changed: Vec<PathBuf>,
+    pub missing: Vec<PathBuf>,
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl AuditReport {
+    // passed method return true if every file of the manifest is found unchanged at the same path
+    // and there is no other file in the tree, and every file could be read
+    pub fn passed(&self) -> bool {
+        self.moved.is_empty()
+            && self.errors.is_empty()
+            && self.new.is_empty()
+            && self.changed.is_empty()
+            && self.missing.is_empty()
+    }
+}
+
+// hash_file function read the file once and return its size and the digest of every algorithm
+pub fn hash_file(path: &Path, algorithms: &[Algorithm]) -> io::Result<(u64, Vec<Vec<u8>>)> {
+    let mut hasher = MultiHasher::new(algorithms);
+    let size = io::copy(&mut File::open(path)?, &mut hasher)?;
+    let result = hasher.finalize();
+    let digests = algorithms
+        .iter()
+        .map(|algorithm| result[algorithm].clone())
+        .collect();
+    Ok((size, digests))
+}
+
+// path_bytes function return a relative path as written in the manifest, the components joined by '/'
+pub fn path_bytes(path: &Path) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for (i, component) in path.iter().enumerate() {
+        if i > 0 {
+            bytes.push(b'/');
+        }
+        bytes.extend_from_slice(component_bytes(component));
+    }
+    bytes
+}
+
+// bytes_path function is the reverse of path_bytes
+fn bytes_path(bytes: &[u8]) -> PathBuf {
+    bytes.split(|&b| b == b'/').map(bytes_component).collect()
+}
+
+#[cfg(unix)]
+fn component_bytes(component: &std::ffi::OsStr) -> &[u8] {
+    std::os::unix::ffi::OsStrExt::as_bytes(component)
+}
+
+#[cfg(not(unix))]
+fn component_bytes(component: &std::ffi::OsStr) -> &[u8] {
+    component.as_encoded_bytes()
+}
+
+#[cfg(unix)]
+fn bytes_component(bytes: &[u8]) -> OsString {
+    <std::ffi::OsStr as std::os::unix::ffi::OsStrExt>::from_bytes(bytes).to_os_string()
+}
+
+#[cfg(not(unix))]
+fn bytes_component(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
+
+// needs_escape function return true if the path must be escaped in a manifest line
+fn needs_escape(path: &[u8]) -> bool {
+    path.iter()
+        .any(|b| matches!(b, b'\\' | b'\n' | b'\r' | b','))
+}
+
+// escape function escape the bytes listed in needs_escape
+fn escape(path: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(path.len());
+    for &b in path {
+        match b {
+            b'\\' => escaped.extend_from_slice(b"\\\\"),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            b',' => escaped.extend_from_slice(b"\\,"),
+            _ => escaped.push(b),
+        }
+    }
+    escaped
+}
+
+// unescape function is the reverse of escape, None is returned for an unknown escape sequence
+fn unescape(path: &[u8]) -> Option<Vec<u8>> {
+    let mut unescaped = Vec::with_capacity(path.len());
+    let mut bytes = path.iter();
+    while let Some(&b) = bytes.next() {
+        if b != b'\\' {
+            unescaped.push(b);
+            continue;
+        }
+        match bytes.next()? {
+            b'\\' => unescaped.push(b'\\'),
+            b'n' => unescaped.push(b'\n'),
+            b'r' => unescaped.push(b'\r'),
+            b',' => unescaped.push(b','),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+// walk function collect every regular file under dir, sorted by path
+// Symbolic links are not followed, so a link loop can not make the walk endless.
+// A subdirectory which can not be read is added to errors and the walk go on.
+fn walk(
+    root: &Path,
+    relative: &Path,
+    files: &mut Vec<PathBuf>,
+    errors: &mut Vec<(PathBuf, String)>,
+) -> io::Result<()> {
+    let mut names: Vec<(OsString, _)> = Vec::new();
+    for entry in std::fs::read_dir(root.join(relative))? {
+        let entry = entry?;
+        names.push((entry.file_name(), entry.file_type()?));
+    }
+    names.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (name, file_type) in names {
+        let path = relative.join(name);
+        if file_type.is_dir() {
+            if let Err(e) = walk(root, &path, files, errors) {
+                errors.push((path, e.to_string()));
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// hash_tree function hash every file under root, the files which can not be read are in the errors
+// Only an error of root itself is returned as Err.
+type Tree = (Vec<ManifestEntry>, Vec<(PathBuf, String)>);
+
+fn hash_tree(root: &Path, algorithms: &[Algorithm]) -> io::Result<Tree> {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    walk(root, Path::new(""), &mut files, &mut errors)?;
+    let mut entries = Vec::new();
+    for path in files {
+        match hash_file(&root.join(&path), algorithms) {
+            Ok((size, digests)) => entries.push(ManifestEntry {
+                size,
+                digests,
+                path,
+            }),
+            Err(e) => errors.push((path, e.to_string())),
+        }
+    }
+    errors.sort();
+    Ok((entries, errors))
+}
+
+// invalid function make the error for a broken manifest line
+fn invalid(line_number: usize, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("manifest line {}: {}", line_number, message),
+    )
+}
+
+impl Manifest {
+    // build method walk the directory tree under root and hash every file with the algorithms
+    pub fn build(root: &Path, algorithms: &[Algorithm]) -> io::Result<Manifest> {
+        let (entries, errors) = hash_tree(root, algorithms)?;
+        Ok(Manifest {
+            algorithms: algorithms.to_vec(),
+            entries,
+            errors,
+        })
+    }
+
+    // write_to method write the manifest in the hashdeep format
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "%%%% HASHDEEP-1.0")?;
+        let names: Vec<&str> = self.algorithms.iter().map(|a| a.name()).collect();
+        writeln!(out, "%%%% size,{},filename", names.join(","))?;
+        for entry in &self.entries {
+            let digests: Vec<String> = entry
+                .digests
+                .iter()
+                .map(|digest| crate::hex::encode(digest))
+                .collect();
+            let path = path_bytes(&entry.path);
+            if needs_escape(&path) {
+                write!(out, "\\{},{},", entry.size, digests.join(","))?;
+                out.write_all(&escape(&path))?;
+            } else {
+                write!(out, "{},{},", entry.size, digests.join(","))?;
+                out.write_all(&path)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    // read_from method parse a manifest written by write_to or by hashdeep
+    // The filename is the last column, so it may contain commas when the line is not escaped.
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<Manifest> {
+        let mut lines = reader.split(b'\n').enumerate();
+        // the header lines are text, only the filename column may be other bytes
+        let text = |line: Vec<u8>| String::from_utf8(line).unwrap_or_default();
+
+        let header = match lines.next() {
+            Some((_, line)) => text(line?),
+            None => String::new(),
+        };
+        if header.trim_end() != "%%%% HASHDEEP-1.0" {
+            return Err(invalid(1, "missing %%%% HASHDEEP-1.0 header"));
+        }
+        let columns = match lines.next() {
+            Some((_, line)) => text(line?),
+            None => return Err(invalid(2, "missing column header")),
+        };
+        let columns: Vec<&str> = columns
+            .trim_end()
+            .strip_prefix("%%%% ")
+            .ok_or_else(|| invalid(2, "missing column header"))?
+            .split(',')
+            .collect();
+        if columns.len() < 3 || columns[0] != "size" || columns[columns.len() - 1] != "filename" {
+            return Err(invalid(2, "the columns must be size,...,filename"));
+        }
+        let algorithms = columns[1..columns.len() - 1]
+            .iter()
+            .map(|name| name.parse::<Algorithm>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|message| invalid(2, &message))?;
+
+        let mut entries = Vec::new();
+        for (index, line) in lines {
+            let line = line?;
+            let line = line.strip_suffix(b"\r").unwrap_or(&line);
+            if line.is_empty() || line.starts_with(b"##") {
+                continue;
+            }
+            let (escaped, line) = match line.strip_prefix(b"\\") {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let mut fields = line.splitn(algorithms.len() + 2, |&b| b == b',');
+            let size = fields
+                .next()
+                .and_then(|size| std::str::from_utf8(size).ok())
+                .and_then(|size| size.parse().ok())
+                .ok_or_else(|| invalid(index + 1, "bad size"))?;
+            let mut digests = Vec::new();
+            for algorithm in &algorithms {
+                let digest = fields
+                    .next()
+                    .and_then(|digest| std::str::from_utf8(digest).ok())
+                    .and_then(crate::hex::decode)
+                    .filter(|digest| digest.len() == algorithm.output_size())
+                    .ok_or_else(|| invalid(index + 1, &format!("bad {} hash", algorithm)))?;
+                digests.push(digest);
+            }
+            let path = fields
+                .next()
+                .filter(|path| !path.is_empty())
+                .ok_or_else(|| invalid(index + 1, "missing filename"))?;
+            let path = if escaped {
+                unescape(path).ok_or_else(|| invalid(index + 1, "bad escape in filename"))?
+            } else {
+                path.to_vec()
+            };
+            entries.push(ManifestEntry {
+                size,
+                digests,
+                path: bytes_path(&path),
+            });
+        }
+
+        Ok(Manifest {
+            algorithms,
+            entries,
+            errors: Vec::new(),
+        })
+    }
+
+    // audit method hash the tree under root again and compare it with the manifest
+    // A file is
+    //   matched  when the same path has the same content,
+    //   moved    when the content is in the manifest under another path, and that path is gone,
+    //   changed  when the path is in the manifest with other content,
+    //   new      when the path is not in the manifest and the content was not moved,
+    // and a manifest entry is missing when its path is gone and its content was not found as moved.
+    // A file or directory which can not be read is only in the errors, its entries are not missing.
+    // So a copy of a file still in place is new (or changed), it is not moved.
+    pub fn audit(&self, root: &Path) -> io::Result<AuditReport> {
+        let (current, errors) = hash_tree(root, &self.algorithms)?;
+
+        let by_path: HashMap<&Path, &ManifestEntry> = self
+            .entries
+            .iter()
+            .map(|entry| (entry.path.as_path(), entry))
+            .collect();
+        let mut by_content: HashMap<(u64, &[Vec<u8>]), Vec<&Path>> = HashMap::new();
+        for entry in &self.entries {
+            by_content
+                .entry((entry.size, &entry.digests))
+                .or_default()
+                .push(&entry.path);
+        }
+
+        // the source of a moved file is an entry whose path is gone,
+        // and each source is used once, so duplicated files are paired up correctly
+        let unreadable = |path: &Path| errors.iter().any(|(error, _)| path.starts_with(error));
+        let current_paths: HashSet<&Path> =
+            current.iter().map(|entry| entry.path.as_path()).collect();
+        let mut moved_from = HashSet::new();
+        let mut report = AuditReport::default();
+        for entry in &current {
+            let known = by_path.get(entry.path.as_path());
+            let from = by_content
+                .get(&(entry.size, &entry.digests[..]))
+                .and_then(|paths| {
+                    paths.iter().find(|path| {
+                        !current_paths.contains(*path)
+                            && !unreadable(path)
+                            && !moved_from.contains(*path)
+                    })
+                });
+            if known.is_some_and(|known| known.size == entry.size && known.digests == entry.digests)
+            {
+                report.matched.push(entry.path.clone());
+            } else if let Some(from) = from {
+                moved_from.insert(*from);
+                report.moved.push((from.to_path_buf(), entry.path.clone()));
+            } else if known.is_some() {
+                report.changed.push(entry.path.clone());
+            } else {
+                report.new.push(entry.path.clone());
+            }
+        }
+
+        for entry in &self.entries {
+            // the path of an error may be a directory which could not be read
+            if !current_paths.contains(entry.path.as_path())
+                && !moved_from.contains(entry.path.as_path())
+                && !unreadable(&entry.path)
+            {
+                report.missing.push(entry.path.clone());
+            }
+        }
+
+        report.errors = errors;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifest::{Manifest, ManifestEntry};
+    use crate::Algorithm;
+
+    #[test]
+    fn test_write_read() {
+        let manifest = Manifest {
+            algorithms: vec![Algorithm::Md5, Algorithm::Sha1],
+            entries: vec![ManifestEntry {
+                size: 5,
+                digests: vec![
+                    Algorithm::Md5.digest(b"Hello"),
+                    Algorithm::Sha1.digest(b"Hello"),
+                ],
+                path: "dir/a,b.txt".into(),
+            }],
+            errors: Vec::new(),
+        };
+        let mut out = Vec::new();
+        manifest.write_to(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        println!("{}", text);
+
+        assert_eq!(
+            "%%%% HASHDEEP-1.0\n\
+             %%%% size,md5,sha1,filename\n\
+             \\5,8b1a9953c4611296a827abf8c47804d7,f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0,dir/a\\,b.txt\n",
+            text
+        );
+
+        let parsed = Manifest::read_from(text.as_bytes()).unwrap();
+        assert_eq!(manifest.algorithms, parsed.algorithms);
+        assert_eq!(manifest.entries, parsed.entries);
+
+        // a comma in a line of hashdeep, without escaping, is still part of the filename
+        let text =
+            b"%%%% HASHDEEP-1.0\n%%%% size,md5,filename\n5,8b1a9953c4611296a827abf8c47804d7,a,b\n";
+        let parsed = Manifest::read_from(&text[..]).unwrap();
+        assert_eq!(std::path::Path::new("a,b"), parsed.entries[0].path);
+    }
+
+    #[test]
+    fn test_escape() {
+        let manifest = Manifest {
+            algorithms: vec![Algorithm::Md5],
+            entries: vec![ManifestEntry {
+                size: 5,
+                digests: vec![Algorithm::Md5.digest(b"Hello")],
+                path: "new\nline\r\\back,slash".into(),
+            }],
+            errors: Vec::new(),
+        };
+        let mut out = Vec::new();
+        manifest.write_to(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        println!("{}", text);
+        assert_eq!(
+            "%%%% HASHDEEP-1.0\n\
+             %%%% size,md5,filename\n\
+             \\5,8b1a9953c4611296a827abf8c47804d7,new\\nline\\r\\\\back\\,slash\n",
+            text
+        );
+
+        let parsed = Manifest::read_from(text.as_bytes()).unwrap();
+        assert_eq!(manifest.entries, parsed.entries);
+
+        let bad = b"%%%% HASHDEEP-1.0\n%%%% size,md5,filename\n\\5,8b1a9953c4611296a827abf8c47804d7,a\\x\n";
+        assert!(Manifest::read_from(&bad[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert!(Manifest::read_from(&b"5,abc,file\n"[..]).is_err());
+        assert!(Manifest::read_from(&b"%%%% HASHDEEP-1.0\n%%%% size,sha3,filename\n"[..]).is_err());
+        let short_hash = b"%%%% HASHDEEP-1.0\n%%%% size,md5,filename\n5,8b1a,file\n";
+        assert!(Manifest::read_from(&short_hash[..]).is_err());
+
+        // comments of hashdeep are skipped
+        let text = b"%%%% HASHDEEP-1.0\n%%%% size,md5,filename\n## Invoked from: /tmp\n##\n";
+        assert!(Manifest::read_from(&text[..]).unwrap().entries.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let text = b"%%%% HASHDEEP-1.0\n%%%% size,md5,filename\n\
+                     5,8b1a9953c4611296a827abf8c47804d7,dir/bad\xff.txt\n";
+        let manifest = Manifest::read_from(&text[..]).unwrap();
+        let expected = std::path::Path::new("dir").join(OsStr::from_bytes(b"bad\xff.txt"));
+        assert_eq!(expected, manifest.entries[0].path);
+
+        let mut out = Vec::new();
+        manifest.write_to(&mut out).unwrap();
+        assert_eq!(text.as_slice(), out);
+    }
+}
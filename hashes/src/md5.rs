@@ -1,8 +1,10 @@
-use std::fmt::Display;
-use std::fmt::Write;
+use core::fmt::Display;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
 #[rustfmt::skip]
-const T: [u32; 65] = [
+pub(crate) const T: [u32; 65] = [
     // round 1
     0x00000000, 0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613,
     0xfd469501, 0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
@@ -17,8 +19,26 @@ const T: [u32; 65] = [
     0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
 ];
 
+// SHIFT is the rotation of each step, compress has them written out
+#[rustfmt::skip]
+pub(crate) const SHIFT: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+// WORD is the message word of each step
+#[rustfmt::skip]
+pub(crate) const WORD: [usize; 64] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    1, 6, 11, 0, 5, 10, 15, 4, 9, 14, 3, 8, 13, 2, 7, 12,
+    5, 8, 11, 14, 1, 4, 7, 10, 13, 0, 3, 6, 9, 12, 15, 2,
+    0, 7, 14, 5, 12, 3, 10, 1, 8, 15, 6, 13, 4, 11, 2, 9,
+];
+
 #[inline(always)]
-fn f(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
+pub(crate) const fn f(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
     // F(b, c, d) => X=b, Y=c, Z=d
     ((b & c) | (!b & d))
         .wrapping_add(a)
@@ -29,7 +49,7 @@ fn f(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
 }
 
 #[inline(always)]
-fn g(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
+pub(crate) const fn g(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
     // G(b, c, d) => X=b, Y=c, Z=d
     ((b & d) | (c & !d))
         .wrapping_add(a)
@@ -40,7 +60,7 @@ fn g(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
 }
 
 #[inline(always)]
-fn h(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
+pub(crate) const fn h(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
     // H(b, c, d) => X=b, Y=c, Z=d
     (b ^ c ^ d)
         .wrapping_add(a)
@@ -51,7 +71,7 @@ fn h(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
 }
 
 #[inline(always)]
-fn i(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
+pub(crate) const fn i(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
     // I(b, c, d) => X=b, Y=c, Z=d
     (c ^ (b | !d))
         .wrapping_add(a)
@@ -61,40 +81,46 @@ fn i(a: u32, b: u32, c: u32, d: u32, k: u32, s: u32, i: u32) -> u32 {
         .wrapping_add(b)
 }
 
+// IV is the initial chaining value
+const IV: [u32; 4] = [
+    0x6745_2301, // word A
+    0xefcd_ab89, // word B
+    0x98ba_dcfe, // word C
+    0x1032_5476, // word D
+];
+
+// MD5 keep only the chaining value and the last incomplete block,
+// so the memory does not grow with the input and the state can be exported (see state.rs).
+#[derive(Clone)]
 pub struct MD5 {
-    message: Vec<u8>,
     hash: [u32; 4],
+    // bytes of the last incomplete block
+    buffer: [u8; 64],
+    buffer_len: usize,
+    // total input length in bytes
+    length: u64,
+    // bits after the last whole byte, see update_bits, they are the highest bits of partial
+    partial: u8,
+    partial_bits: u32,
+    // variant of the compression, Params::MD5 for the real MD5
+    params: Params,
+    // every compressed block when tracing is on, tracing need the alloc feature, see MD5::traced
+    #[cfg(feature = "alloc")]
+    trace: Option<Vec<BlockTrace>>,
 }
 
 // Implement Display trait to Sha1 struct
 impl Display for MD5 {
     // fmtメソッドを定義
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         // Convert hash value to hexadecimal string
         // If not set to 08x, the leading 0 will disappear and 0x08ce5404 will not be displayed
-
-        // Original code from my sha1.rs
         // md5 case need to change from b to b.swap_bytes() for little endian
-        let hex = self.hash.iter().fold(String::new(), |mut acc, b| {
-            write!(&mut acc, "{:08x}", b.swap_bytes()).unwrap();
-            acc
-        });
-
-        /*
-        The original code is shown below, but it gives a clippy error `use of `format!` to build up a string from an iterator`.
-        Using the `format!` macro to build up a string from an iterator results in poor performance.
-        This is because using the format! macro causes memory allocation and freeing.
-        So, when you make strings from iterators, use fold function and write! macro instead of format! macro to improve performance.
-        ```rust
-        let hex = self
-            .hash
-            .iter()
-            .map(|b| format!("{:08x}", b))
-            .collect::<String>();
-        ```
-        */
-        // Write to formatter
-        write!(f, "{}", hex)
+        // Each word is written to the formatter directly, no String is built, so no allocator is needed.
+        for word in self.hash {
+            write!(f, "{:08x}", word.swap_bytes())?;
+        }
+        Ok(())
     }
 }
 
@@ -120,159 +146,616 @@ impl MD5Digest for MD5 {
     // new method return the initialized Sha1 struct
     fn new() -> Self {
         Self {
-            message: Vec::new(),
-            hash: [
-                0x6745_2301, // word A
-                0xefcd_ab89, // word B
-                0x98ba_dcfe, // word C
-                0x1032_5476, // word D
-            ],
+            hash: IV,
+            buffer: [0; 64],
+            buffer_len: 0,
+            length: 0,
+            partial: 0,
+            partial_bits: 0,
+            params: Params::MD5,
+            #[cfg(feature = "alloc")]
+            trace: None,
         }
     }
 
     // update method add input message
+    // After an update_bits which ended in the middle of a byte, the bytes are shifted by update_bits.
     fn update(&mut self, input: &[u8]) {
-        self.message.extend_from_slice(input);
+        if self.partial_bits == 0 {
+            self.update_bytes(input);
+        } else {
+            self.update_bits(input, input.len() * 8);
+        }
     }
 
     // finalize method do padding and calculate sha1 hash
     fn finalize(mut self) -> Self::Output {
-        // Padding processing
-        // 1 byte = 8 bits, Hello = 5 bytes = (5 * 8) bits = 40 bits
-        // message length (in bits)
-        // Add 8bit/1byte 0x80 to the end of the message 0x80 = 10000000
-        // add 0x80 to the end of the message 0x80 = 10000000
-        // message length comes from 8-bit units.
-        // Add 1 bit of 1 and 0 for the remaining 7 bits because we don't think there is any room in the last byte of the message.
-        // This is the reason for 0x80.
-        let len = (self.message.len() * 8) as u64;
-        // Add 1 bit of 1
-        self.message.push(0x80);
-        // +8 here will be 8 bytes = 64 bits to add the last message length.
-        // Padding is done until mod 512 becomes 0.
-        // This code itself is designed to handle more than 447 bits.
-        // 512 -> 512 - 64 -> 448 -1 -> 447bit => this 447 is the longest in the last block.
-        // In fact, even if you are not aware of this kind of thing, the following code is used to express it.
-        while (self.message.len() + 8) % Self::BLOCK_SIZE != 0 {
-            // add 0 bit
-            self.message.push(0x00);
+        // the bits of a partial byte share their byte with the 1 bit of the padding
+        let bits = self
+            .length
+            .wrapping_mul(8)
+            .wrapping_add(self.partial_bits as u64);
+        let (mut padding, len) = const_padding(bits);
+        padding[0] |= self.partial;
+        self.partial_bits = 0;
+        // update add the padding to the length, but it is not used after this
+        self.update_bytes(&padding[..len]);
+        // return Sha1 struct
+        self
+    }
+}
+
+impl MD5 {
+    // update_bytes method add whole bytes
+    // Every complete 512bit block is compressed at once, only the rest is kept in the buffer.
+    fn update_bytes(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // fill the incomplete block from the previous update first
+        if self.buffer_len > 0 {
+            let take = (Self::BLOCK_SIZE - self.buffer_len).min(input.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&input[..take]);
+            self.buffer_len += take;
+            input = &input[take..];
+            if self.buffer_len < Self::BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            self.compress(&block);
+            self.buffer_len = 0;
+        }
+
+        let mut chunks = input.chunks_exact(Self::BLOCK_SIZE);
+        for chunk in &mut chunks {
+            self.compress(chunk.try_into().unwrap());
+        }
+        let rest = chunks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    // update_bits method add the first bit_len bits of data, for messages which are not whole bytes
+    // RFC 1321 take the bits of a byte from the highest one too, the same as sha1::Sha1::update_bits,
+    // only the length at the end of the padding is little endian.
+    pub fn update_bits(&mut self, data: &[u8], bit_len: usize) {
+        assert!(bit_len <= data.len() * 8, "bit_len is longer than data");
+        let whole = &data[..bit_len / 8];
+        let rest = (bit_len % 8) as u32;
+        // the last bits, the unused low bits are cleared
+        let last = if rest > 0 {
+            data[bit_len / 8] & !(0xff >> rest)
+        } else {
+            0
+        };
+        if self.partial_bits == 0 {
+            self.update_bytes(whole);
+            self.partial = last;
+            self.partial_bits = rest;
+            return;
         }
-        // add 64bit message length
-        self.message.extend_from_slice(&len.to_le_bytes());
-
-        // calc hash
-        for chunk in self.message.chunks(Self::BLOCK_SIZE) {
-            // div chuck to 16 - 32bit word
-            let mut words = [0u32; 16];
-            // A variable reference is obtained for the first 16 elements of words with take(16).
-            for (i, word) in words.iter_mut().take(16).enumerate() {
-                // each 4byte of chunk is converted to a 32-bit unsigned integer
-                // word is a reference, so *word must be dereferenced
-                // this is often done to change references to elements in the iterator
-                *word = u32::from_le_bytes(chunk[i * 4..i * 4 + 4].try_into().unwrap());
+
+        // the shifted bytes go through a buffer of one block, so no allocator is needed
+        let shift = self.partial_bits;
+        let mut aligned = [0u8; 64];
+        for chunk in whole.chunks(64) {
+            for (out, &byte) in aligned.iter_mut().zip(chunk) {
+                *out = self.partial | byte >> shift;
+                self.partial = byte << (8 - shift);
             }
+            self.update_bytes(&aligned[..chunk.len()]);
+        }
+        self.partial |= last >> shift;
+        if shift + rest >= 8 {
+            let byte = self.partial;
+            self.update_bytes(&[byte]);
+            self.partial = last << (8 - shift);
+            self.partial_bits = shift + rest - 8;
+        } else {
+            self.partial_bits = shift + rest;
+        }
+    }
+}
+
+// padding function return the bytes that finalize add after a message of length bytes
+// It is public because the length extension attack (attacks::length_extension) need the same glue padding.
+// The length is modulo 2^64 bits like RFC 1321 says, so it is wrapping_mul and not a cast of usize.
+#[cfg(feature = "alloc")]
+pub fn padding(length: u64) -> Vec<u8> {
+    padding_bits(length.wrapping_mul(8))
+}
+
+// padding_bits function return the padding after a message of bits bits
+// When the message end in the middle of a byte, the first byte of the padding is that byte:
+// the 1 bit come right after the last bit of the message and the bits of the message must be or-ed in.
+#[cfg(feature = "alloc")]
+pub fn padding_bits(bits: u64) -> Vec<u8> {
+    let (padding, len) = const_padding(bits);
+    padding[..len].to_vec()
+}
+
+// const_padding function is padding_bits without a Vec, for const_digest
+// It return the padding in an array of the longest padding, 1 + 63 + 8 bytes, and its length.
+pub(crate) const fn const_padding(bits: u64) -> ([u8; 72], usize) {
+    // Padding processing
+    // 1 byte = 8 bits, Hello = 5 bytes = (5 * 8) bits = 40 bits
+    // message length (in bits)
+    // Add 8bit/1byte 0x80 to the end of the message 0x80 = 10000000
+    // message length comes from 8-bit units.
+    // Add 1 bit of 1 and 0 for the remaining 7 bits because we don't think there is any room in the last byte of the message.
+    // This is the reason for 0x80. After a partial byte the 1 bit is moved down to the first free bit.
+    // Add 1 bit of 1, and 0 bits until 8 bytes are left in the last block.
+    // If the last block has more than 55 bytes, the length does not fit and one more block is needed.
+    let rest = (bits / 8 % 64) as usize;
+    let zeros = if rest < 56 { 55 - rest } else { 119 - rest };
+    let mut padding = [0u8; 72];
+    padding[0] = 0x80 >> (bits % 8);
+    // add 64bit message length
+    let length = bits.to_le_bytes();
+    let mut i = 0;
+    while i < 8 {
+        padding[1 + zeros + i] = length[i];
+        i += 1;
+    }
+    (padding, 1 + zeros + 8)
+}
+
+// const_digest function calculate the MD5 of a whole message at compile time
+//   const ID: [u8; 16] = md5::const_digest(b"my-protocol v1");
+// It is the same compress and padding as MD5, it can be called at run time too.
+pub const fn const_digest(input: &[u8]) -> [u8; 16] {
+    let mut hash = IV;
+    let mut rest = input;
+    while let Some((block, tail)) = rest.split_first_chunk::<64>() {
+        compress(&mut hash, block);
+        rest = tail;
+    }
+
+    // the rest and the padding fill one or two blocks
+    let (padding, len) = const_padding((input.len() as u64).wrapping_mul(8));
+    let mut last = [0u8; 128];
+    let mut i = 0;
+    while i < rest.len() {
+        last[i] = rest[i];
+        i += 1;
+    }
+    while i < rest.len() + len {
+        last[i] = padding[i - rest.len()];
+        i += 1;
+    }
+    compress(&mut hash, last.first_chunk::<64>().unwrap());
+    if i == 128 {
+        compress(&mut hash, last.last_chunk::<64>().unwrap());
+    }
+
+    // md5 words are little endian
+    let mut digest = [0u8; 16];
+    let mut i = 0;
+    while i < 16 {
+        digest[i] = hash[i / 4].to_le_bytes()[i % 4];
+        i += 1;
+    }
+    digest
+}
+
+// compress function process one 512bit block and update the chaining value
+// It is a const fn for const_digest, so the words are read with a while loop, iterators can not be used in const.
+pub(crate) const fn compress(hash: &mut [u32; 4], chunk: &[u8; 64]) {
+    // div chuck to 16 - 32bit word
+    let mut words = [0u32; 16];
+    // n and not i, i is the function of round 4
+    let mut n = 0;
+    while n < 16 {
+        // each 4byte of chunk is converted to a 32-bit unsigned integer
+        words[n] = u32::from_le_bytes([
+            chunk[n * 4],
+            chunk[n * 4 + 1],
+            chunk[n * 4 + 2],
+            chunk[n * 4 + 3],
+        ]);
+        n += 1;
+    }
+
+    let mut a = hash[0]; // A
+    let mut b = hash[1]; // B
+    let mut c = hash[2]; // C
+    let mut d = hash[3]; // D
+    let aa = hash[0]; // A
+    let bb = hash[1]; // B
+    let cc = hash[2]; // C
+    let dd = hash[3]; // D
 
-            let mut a = self.hash[0]; // A
-            let mut b = self.hash[1]; // B
-            let mut c = self.hash[2]; // C
-            let mut d = self.hash[3]; // D
-            let aa = self.hash[0]; // A
-            let bb = self.hash[1]; // B
-            let cc = self.hash[2]; // C
-            let dd = self.hash[3]; // D
-
-            // round 1
-            a = f(a, b, c, d, words[0], 7, T[1]);
-            d = f(d, a, b, c, words[1], 12, T[2]);
-            c = f(c, d, a, b, words[2], 17, T[3]);
-            b = f(b, c, d, a, words[3], 22, T[4]);
-
-            a = f(a, b, c, d, words[4], 7, T[5]);
-            d = f(d, a, b, c, words[5], 12, T[6]);
-            c = f(c, d, a, b, words[6], 17, T[7]);
-            b = f(b, c, d, a, words[7], 22, T[8]);
-
-            a = f(a, b, c, d, words[8], 7, T[9]);
-            d = f(d, a, b, c, words[9], 12, T[10]);
-            c = f(c, d, a, b, words[10], 17, T[11]);
-            b = f(b, c, d, a, words[11], 22, T[12]);
-
-            a = f(a, b, c, d, words[12], 7, T[13]);
-            d = f(d, a, b, c, words[13], 12, T[14]);
-            c = f(c, d, a, b, words[14], 17, T[15]);
-            b = f(b, c, d, a, words[15], 22, T[16]);
-
-            // round 2
-            a = g(a, b, c, d, words[1], 5, T[17]);
-            d = g(d, a, b, c, words[6], 9, T[18]);
-            c = g(c, d, a, b, words[11], 14, T[19]);
-            b = g(b, c, d, a, words[0], 20, T[20]);
-
-            a = g(a, b, c, d, words[5], 5, T[21]);
-            d = g(d, a, b, c, words[10], 9, T[22]);
-            c = g(c, d, a, b, words[15], 14, T[23]);
-            b = g(b, c, d, a, words[4], 20, T[24]);
-
-            a = g(a, b, c, d, words[9], 5, T[25]);
-            d = g(d, a, b, c, words[14], 9, T[26]);
-            c = g(c, d, a, b, words[3], 14, T[27]);
-            b = g(b, c, d, a, words[8], 20, T[28]);
-
-            a = g(a, b, c, d, words[13], 5, T[29]);
-            d = g(d, a, b, c, words[2], 9, T[30]);
-            c = g(c, d, a, b, words[7], 14, T[31]);
-            b = g(b, c, d, a, words[12], 20, T[32]);
-
-            // round 3
-            a = h(a, b, c, d, words[5], 4, T[33]);
-            d = h(d, a, b, c, words[8], 11, T[34]);
-            c = h(c, d, a, b, words[11], 16, T[35]);
-            b = h(b, c, d, a, words[14], 23, T[36]);
-
-            a = h(a, b, c, d, words[1], 4, T[37]);
-            d = h(d, a, b, c, words[4], 11, T[38]);
-            c = h(c, d, a, b, words[7], 16, T[39]);
-            b = h(b, c, d, a, words[10], 23, T[40]);
-
-            a = h(a, b, c, d, words[13], 4, T[41]);
-            d = h(d, a, b, c, words[0], 11, T[42]);
-            c = h(c, d, a, b, words[3], 16, T[43]);
-            b = h(b, c, d, a, words[6], 23, T[44]);
-
-            a = h(a, b, c, d, words[9], 4, T[45]);
-            d = h(d, a, b, c, words[12], 11, T[46]);
-            c = h(c, d, a, b, words[15], 16, T[47]);
-            b = h(b, c, d, a, words[2], 23, T[48]);
-
-            // round 4
-            a = i(a, b, c, d, words[0], 6, T[49]);
-            d = i(d, a, b, c, words[7], 10, T[50]);
-            c = i(c, d, a, b, words[14], 15, T[51]);
-            b = i(b, c, d, a, words[5], 21, T[52]);
-
-            a = i(a, b, c, d, words[12], 6, T[53]);
-            d = i(d, a, b, c, words[3], 10, T[54]);
-            c = i(c, d, a, b, words[10], 15, T[55]);
-            b = i(b, c, d, a, words[1], 21, T[56]);
-
-            a = i(a, b, c, d, words[8], 6, T[57]);
-            d = i(d, a, b, c, words[15], 10, T[58]);
-            c = i(c, d, a, b, words[6], 15, T[59]);
-            b = i(b, c, d, a, words[13], 21, T[60]);
-
-            a = i(a, b, c, d, words[4], 6, T[61]);
-            d = i(d, a, b, c, words[11], 10, T[62]);
-            c = i(c, d, a, b, words[2], 15, T[63]);
-            b = i(b, c, d, a, words[9], 21, T[64]);
-
-            // refresh hash
-            self.hash[0] = a.wrapping_add(aa);
-            self.hash[1] = b.wrapping_add(bb);
-            self.hash[2] = c.wrapping_add(cc);
-            self.hash[3] = d.wrapping_add(dd);
+    // round 1
+    a = f(a, b, c, d, words[0], 7, T[1]);
+    d = f(d, a, b, c, words[1], 12, T[2]);
+    c = f(c, d, a, b, words[2], 17, T[3]);
+    b = f(b, c, d, a, words[3], 22, T[4]);
+
+    a = f(a, b, c, d, words[4], 7, T[5]);
+    d = f(d, a, b, c, words[5], 12, T[6]);
+    c = f(c, d, a, b, words[6], 17, T[7]);
+    b = f(b, c, d, a, words[7], 22, T[8]);
+
+    a = f(a, b, c, d, words[8], 7, T[9]);
+    d = f(d, a, b, c, words[9], 12, T[10]);
+    c = f(c, d, a, b, words[10], 17, T[11]);
+    b = f(b, c, d, a, words[11], 22, T[12]);
+
+    a = f(a, b, c, d, words[12], 7, T[13]);
+    d = f(d, a, b, c, words[13], 12, T[14]);
+    c = f(c, d, a, b, words[14], 17, T[15]);
+    b = f(b, c, d, a, words[15], 22, T[16]);
+
+    // round 2
+    a = g(a, b, c, d, words[1], 5, T[17]);
+    d = g(d, a, b, c, words[6], 9, T[18]);
+    c = g(c, d, a, b, words[11], 14, T[19]);
+    b = g(b, c, d, a, words[0], 20, T[20]);
+
+    a = g(a, b, c, d, words[5], 5, T[21]);
+    d = g(d, a, b, c, words[10], 9, T[22]);
+    c = g(c, d, a, b, words[15], 14, T[23]);
+    b = g(b, c, d, a, words[4], 20, T[24]);
+
+    a = g(a, b, c, d, words[9], 5, T[25]);
+    d = g(d, a, b, c, words[14], 9, T[26]);
+    c = g(c, d, a, b, words[3], 14, T[27]);
+    b = g(b, c, d, a, words[8], 20, T[28]);
+
+    a = g(a, b, c, d, words[13], 5, T[29]);
+    d = g(d, a, b, c, words[2], 9, T[30]);
+    c = g(c, d, a, b, words[7], 14, T[31]);
+    b = g(b, c, d, a, words[12], 20, T[32]);
+
+    // round 3
+    a = h(a, b, c, d, words[5], 4, T[33]);
+    d = h(d, a, b, c, words[8], 11, T[34]);
+    c = h(c, d, a, b, words[11], 16, T[35]);
+    b = h(b, c, d, a, words[14], 23, T[36]);
+
+    a = h(a, b, c, d, words[1], 4, T[37]);
+    d = h(d, a, b, c, words[4], 11, T[38]);
+    c = h(c, d, a, b, words[7], 16, T[39]);
+    b = h(b, c, d, a, words[10], 23, T[40]);
+
+    a = h(a, b, c, d, words[13], 4, T[41]);
+    d = h(d, a, b, c, words[0], 11, T[42]);
+    c = h(c, d, a, b, words[3], 16, T[43]);
+    b = h(b, c, d, a, words[6], 23, T[44]);
+
+    a = h(a, b, c, d, words[9], 4, T[45]);
+    d = h(d, a, b, c, words[12], 11, T[46]);
+    c = h(c, d, a, b, words[15], 16, T[47]);
+    b = h(b, c, d, a, words[2], 23, T[48]);
+
+    // round 4
+    a = i(a, b, c, d, words[0], 6, T[49]);
+    d = i(d, a, b, c, words[7], 10, T[50]);
+    c = i(c, d, a, b, words[14], 15, T[51]);
+    b = i(b, c, d, a, words[5], 21, T[52]);
+
+    a = i(a, b, c, d, words[12], 6, T[53]);
+    d = i(d, a, b, c, words[3], 10, T[54]);
+    c = i(c, d, a, b, words[10], 15, T[55]);
+    b = i(b, c, d, a, words[1], 21, T[56]);
+
+    a = i(a, b, c, d, words[8], 6, T[57]);
+    d = i(d, a, b, c, words[15], 10, T[58]);
+    c = i(c, d, a, b, words[6], 15, T[59]);
+    b = i(b, c, d, a, words[13], 21, T[60]);
+
+    a = i(a, b, c, d, words[4], 6, T[61]);
+    d = i(d, a, b, c, words[11], 10, T[62]);
+    c = i(c, d, a, b, words[2], 15, T[63]);
+    b = i(b, c, d, a, words[9], 21, T[64]);
+
+    // refresh hash
+    hash[0] = a.wrapping_add(aa);
+    hash[1] = b.wrapping_add(bb);
+    hash[2] = c.wrapping_add(cc);
+    hash[3] = d.wrapping_add(dd);
+}
+
+// Step is one step of the compression, as the tracing mode record it
+// a, b, c and d are the arguments of the call f(a, b, c, d, word, shift, T), result is the new register.
+// The registers turn each step, the result is b of the next step and a, d, c are d, c, b of this one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    // step 0..63, round is step / 16
+    pub step: usize,
+    // 'F', 'G', 'H' or 'I'
+    pub function: char,
+    // index of the message word and its value
+    pub word: usize,
+    pub value: u32,
+    // T[step + 1] and the rotation
+    pub t: u32,
+    pub shift: u32,
+    pub a: u32,
+    pub b: u32,
+    pub c: u32,
+    pub d: u32,
+    pub result: u32,
+}
+
+// BlockTrace is the record of the compression of one block
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockTrace {
+    // the 16 message words of the block
+    pub words: [u32; 16],
+    // chaining value before and after the block, A B C D
+    pub input: [u32; 4],
+    pub steps: Vec<Step>,
+    pub output: [u32; 4],
+}
+
+// compress_steps function do the same as compress, step by step, and stop after the given number of steps
+// record is called with every step, the tracing mode keep them. The block is returned as words.
+// compress stay written out, so the variants and the tracing do not slow down the normal hashing.
+fn compress_steps(
+    hash: &mut [u32; 4],
+    chunk: &[u8; 64],
+    steps: usize,
+    mut record: impl FnMut(Step),
+) -> [u32; 16] {
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(chunk.chunks(4)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    let [mut a, mut b, mut c, mut d] = *hash;
+    for step in 0..steps {
+        let word = WORD[step];
+        let (value, t, shift) = (words[word], T[step + 1], SHIFT[step]);
+        let (function, result) = match step / 16 {
+            0 => ('F', f(a, b, c, d, value, shift, t)),
+            1 => ('G', g(a, b, c, d, value, shift, t)),
+            2 => ('H', h(a, b, c, d, value, shift, t)),
+            _ => ('I', i(a, b, c, d, value, shift, t)),
+        };
+        record(Step {
+            step,
+            function,
+            word,
+            value,
+            t,
+            shift,
+            a,
+            b,
+            c,
+            d,
+            result,
+        });
+        (a, b, c, d) = (d, result, b, c);
+    }
+
+    hash[0] = hash[0].wrapping_add(a);
+    hash[1] = hash[1].wrapping_add(b);
+    hash[2] = hash[2].wrapping_add(c);
+    hash[3] = hash[3].wrapping_add(d);
+    words
+}
+
+// trace_compress function compress one block with the given number of steps and record every step
+#[cfg(feature = "alloc")]
+fn trace_compress(hash: &mut [u32; 4], chunk: &[u8; 64], steps: usize) -> BlockTrace {
+    let input = *hash;
+    let mut trace = Vec::with_capacity(steps);
+    let words = compress_steps(hash, chunk, steps, |step| trace.push(step));
+    BlockTrace {
+        words,
+        input,
+        steps: trace,
+        output: *hash,
+    }
+}
+
+// Params choose a variant of MD5 for the cryptanalysis exercises, fewer steps or another IV
+// The padding and the feed forward of the chaining value stay the same.
+// After a number of steps which is not a multiple of 4, the registers are added to A B C D as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    // number of steps, 0..=64, each round is 16 steps
+    pub steps: usize,
+    // initial chaining value A B C D
+    pub iv: [u32; 4],
+}
+
+impl Params {
+    // MD5 is the real MD5
+    pub const MD5: Params = Params { steps: 64, iv: IV };
+
+    // compress method is the compression function of the variant, hash is the chaining value
+    pub fn compress(&self, hash: &mut [u32; 4], block: &[u8; 64]) {
+        assert!(self.steps <= 64, "MD5 has at most 64 steps");
+        if self.steps == 64 {
+            compress(hash, block);
+        } else {
+            compress_steps(hash, block, self.steps, |_| {});
         }
-        // return Sha1 struct
-        self
+    }
+}
+
+// Display print the trace as a table for the terminal, one line per step
+#[cfg(feature = "alloc")]
+impl Display for BlockTrace {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        writeln!(
+            f,
+            "input     A {:08x}  B {:08x}  C {:08x}  D {:08x}",
+            self.input[0], self.input[1], self.input[2], self.input[3]
+        )?;
+        writeln!(
+            f,
+            "step fn word value    T        shift a        b        c        d        result"
+        )?;
+        for step in &self.steps {
+            writeln!(
+                f,
+                "{:>4} {}  m{:<3} {:08x} {:08x} {:>5} {:08x} {:08x} {:08x} {:08x} {:08x}",
+                step.step,
+                step.function,
+                step.word,
+                step.value,
+                step.t,
+                step.shift,
+                step.a,
+                step.b,
+                step.c,
+                step.d,
+                step.result
+            )?;
+        }
+        writeln!(
+            f,
+            "output    A {:08x}  B {:08x}  C {:08x}  D {:08x}",
+            self.output[0], self.output[1], self.output[2], self.output[3]
+        )
+    }
+}
+
+impl MD5 {
+    // with_params method return a hash of a variant of MD5, see Params
+    // The state of a variant can not be exported, export_state return StateError::Variant.
+    pub fn with_params(params: Params) -> Self {
+        assert!(params.steps <= 64, "MD5 has at most 64 steps");
+        Self {
+            hash: params.iv,
+            params,
+            ..<Self as MD5Digest>::new()
+        }
+    }
+
+    // traced method return a hash which record every block it compress, for teaching
+    // The padding blocks of finalize are recorded too, trace return them all after finalize.
+    #[cfg(feature = "alloc")]
+    pub fn traced() -> Self {
+        Self::traced_with_params(Params::MD5)
+    }
+
+    // traced_with_params method return a traced hash of a variant of MD5
+    #[cfg(feature = "alloc")]
+    pub fn traced_with_params(params: Params) -> Self {
+        Self {
+            trace: Some(Vec::new()),
+            ..Self::with_params(params)
+        }
+    }
+
+    // trace method return the record of every block compressed so far, empty when tracing is off
+    #[cfg(feature = "alloc")]
+    pub fn trace(&self) -> &[BlockTrace] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
+    // compress method compress one block with the steps of the variant, with its trace when tracing is on
+    fn compress(&mut self, block: &[u8; 64]) {
+        #[cfg(feature = "alloc")]
+        if let Some(trace) = &mut self.trace {
+            trace.push(trace_compress(&mut self.hash, block, self.params.steps));
+            return;
+        }
+        self.params.compress(&mut self.hash, block);
+    }
+
+    // digest method return the hash value as 16 bytes
+    // md5 words are little endian, so each word is converted with to_le_bytes
+    pub fn digest(&self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        for (chunk, word) in bytes.chunks_mut(4).zip(self.hash.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    // from_digest method rebuild a hash from its output, as if length bytes had been hashed
+    // The output is the whole chaining value, this is what make the length extension attack possible.
+    // length must be a multiple of the block size, that is the message together with its padding.
+    pub fn from_digest(digest: &[u8; 16], length: u64) -> Self {
+        assert!(
+            length.is_multiple_of(Self::BLOCK_SIZE as u64),
+            "length must be a multiple of the block size"
+        );
+        let mut hash = [0u32; 4];
+        for (word, bytes) in hash.iter_mut().zip(digest.chunks(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        Self {
+            hash,
+            buffer: [0; 64],
+            buffer_len: 0,
+            length,
+            partial: 0,
+            partial_bits: 0,
+            params: Params::MD5,
+            #[cfg(feature = "alloc")]
+            trace: None,
+        }
+    }
+
+    // export_state method save the state of a partially fed hash, see state.rs for the format
+    // The hash can be restored with import_state, even in another process, and fed with the rest of the input.
+    // The bits after the last whole byte of update_bits are saved too.
+    // Only the real MD5 can be exported, a variant of with_params is refused.
+    #[cfg(feature = "alloc")]
+    pub fn export_state(&self) -> Result<Vec<u8>, crate::state::StateError> {
+        if self.params != Params::MD5 {
+            return Err(crate::state::StateError::Variant);
+        }
+        Ok(crate::state::encode(
+            crate::state::ALGORITHM_MD5,
+            self.length,
+            self.partial_bits,
+            self.partial,
+            &self.digest(),
+            &self.buffer[..self.buffer_len],
+        ))
+    }
+
+    // import_state method restore a hash saved by export_state
+    #[cfg(feature = "alloc")]
+    pub fn import_state(state: &[u8]) -> Result<Self, crate::state::StateError> {
+        let state = crate::state::decode(
+            state,
+            crate::state::ALGORITHM_MD5,
+            Self::HASH_SIZE,
+            Self::BLOCK_SIZE,
+        )?;
+        let mut hash = [0u32; 4];
+        for (word, bytes) in hash.iter_mut().zip(state.chaining.chunks(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        let mut buffer = [0u8; 64];
+        buffer[..state.partial.len()].copy_from_slice(state.partial);
+        Ok(Self {
+            hash,
+            buffer,
+            buffer_len: state.partial.len(),
+            length: state.length,
+            partial: state.byte,
+            partial_bits: state.bits,
+            params: Params::MD5,
+            #[cfg(feature = "alloc")]
+            trace: None,
+        })
+    }
+}
+
+// Implement the common Digest trait to MD5 struct
+impl crate::Digest for MD5 {
+    type Output = [u8; 16];
+
+    const OUTPUT_SIZE: usize = 16;
+
+    fn new() -> Self {
+        <Self as MD5Digest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        MD5Digest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        MD5Digest::finalize(self).digest()
     }
 }
 
@@ -377,4 +860,201 @@ mod tests {
 
         assert_eq!("57edf4a22be3c955ac49da2e2107b67a", result);
     }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_9() {
+        // a state exported in the middle of a block is restored and fed with the rest
+        let input =
+            b"12345678901234567890123456789012345678901234567890123456789012345678901234567890";
+        let mut hashtest = crate::md5::MD5::new();
+        hashtest.update(&input[..33]);
+        let state = hashtest.export_state().unwrap();
+
+        let mut hashtest = crate::md5::MD5::import_state(&state).unwrap();
+        hashtest.update(&input[33..]);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("57edf4a22be3c955ac49da2e2107b67a", result);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_10() {
+        // the traced hash give the same digest and record each step of each block
+        let input = b"abc";
+        let mut hashtest = crate::md5::MD5::traced();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        let trace = hashtest.trace();
+        println!("{}", trace[0]);
+
+        assert_eq!("900150983cd24fb0d6963f7d28e17f72", result);
+        assert_eq!(1, trace.len());
+        assert_eq!(64, trace[0].steps.len());
+        assert_eq!(
+            [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            trace[0].input
+        );
+        let mut output = [0u8; 16];
+        for (bytes, word) in output.chunks_mut(4).zip(trace[0].output.iter()) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+        assert_eq!(hashtest.digest(), output);
+        let step = &trace[0].steps[17];
+        assert_eq!(
+            ('G', 6, 0xc040b340, 9),
+            (step.function, step.word, step.t, step.shift)
+        );
+        assert_eq!(trace[0].steps[16].result, step.b);
+        assert_eq!(67, format!("{}", trace[0]).lines().count());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_11() {
+        // the variants: the real parameters, another IV and fewer steps
+        let input = b"abc";
+        let mut hashtest = crate::md5::MD5::with_params(crate::md5::Params::MD5);
+        hashtest.update(input);
+        let result = format!("{}", hashtest.finalize());
+        assert_eq!("900150983cd24fb0d6963f7d28e17f72", result);
+
+        let params = crate::md5::Params {
+            steps: 64,
+            iv: [0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210],
+        };
+        let mut hashtest = crate::md5::MD5::with_params(params);
+        hashtest.update(input);
+        // the state of a variant would be resumed as the real MD5, so it is refused
+        assert_eq!(
+            Some(crate::state::StateError::Variant),
+            hashtest.export_state().err()
+        );
+        let result = format!("{}", hashtest.finalize());
+        println!("{}", result);
+        assert_ne!("900150983cd24fb0d6963f7d28e17f72", result);
+
+        // with 0 steps only the feed forward is left, the chaining value is doubled
+        let mut hashtest = crate::md5::MD5::with_params(crate::md5::Params {
+            steps: 0,
+            ..crate::md5::Params::MD5
+        });
+        hashtest.update(input);
+        let result = format!("{}", hashtest.finalize());
+        assert_eq!("02468ace12579bdffcb97531eca86420", result);
+
+        // a traced variant record only its steps
+        let params = crate::md5::Params {
+            steps: 16,
+            ..crate::md5::Params::MD5
+        };
+        let mut hashtest = crate::md5::MD5::traced_with_params(params);
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let trace = hashtest.trace();
+        assert_eq!(16, trace[0].steps.len());
+        let mut hash = params.iv;
+        let mut block = input.to_vec();
+        block.extend_from_slice(&crate::md5::padding(3));
+        params.compress(&mut hash, block[..].try_into().unwrap());
+        assert_eq!(hash, trace[0].output);
+    }
+
+    #[test]
+    fn test_12() {
+        // a message of 5 bits, 0b10011
+        let mut hashtest = crate::md5::MD5::new();
+        hashtest.update_bits(&[0x98], 5);
+        let result = format!("{}", hashtest.finalize());
+        println!("{}", result);
+        assert_eq!("e0ce190aabc2e4aa602238ca5b81dd6d", result);
+
+        // the unused low bits of the last byte are ignored
+        let mut hashtest = crate::md5::MD5::new();
+        hashtest.update_bits(&[0xbf], 3);
+        assert_eq!(
+            "0d3b29adf592b5d31afe94d88cc85fe9",
+            format!("{}", hashtest.finalize())
+        );
+
+        // whole bytes after a partial byte are shifted, 0b101 || "bc" is the 19 bits ac 4c 60
+        let mut hashtest = crate::md5::MD5::new();
+        hashtest.update_bits(&[0xa0], 3);
+        hashtest.update(b"bc");
+        let mut same = crate::md5::MD5::new();
+        same.update_bits(&[0xac, 0x4c, 0x60], 19);
+        let result = format!("{}", hashtest.finalize());
+        assert_eq!("8e525be2e2f89cbe26fd46e471e020ef", result);
+        assert_eq!(result, format!("{}", same.finalize()));
+
+        // a long input after a partial byte is shifted one block at a time
+        // 0b101 || 0x5a * 200 is ab, 4b * 199 and the 3 bits 010
+        let mut hashtest = crate::md5::MD5::new();
+        hashtest.update_bits(&[0xa0], 3);
+        hashtest.update(&[0x5a; 200]);
+        let mut packed = vec![0x4b; 201];
+        packed[0] = 0xab;
+        packed[200] = 0x40;
+        let mut same = crate::md5::MD5::new();
+        same.update_bits(&packed, 1603);
+        assert_eq!(
+            format!("{}", hashtest.finalize()),
+            format!("{}", same.finalize())
+        );
+
+        // bits which end on a byte boundary are the same as update
+        let mut hashtest = crate::md5::MD5::new();
+        hashtest.update_bits(&[0x61, 0x62, 0x60], 21);
+        hashtest.update_bits(&[0x60], 3);
+        assert_eq!(
+            "900150983cd24fb0d6963f7d28e17f72",
+            format!("{}", hashtest.finalize())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_13() {
+        // computed by the compiler
+        const ABC: [u8; 16] = crate::md5::const_digest(b"abc");
+        let result = crate::hex::encode(&ABC);
+        println!("{}", result);
+        assert_eq!("900150983cd24fb0d6963f7d28e17f72", result);
+
+        // the same as MD5 for every length around the one and two block paddings
+        let message: Vec<u8> = (0..200u32).map(|i| (i * 37 + 5) as u8).collect();
+        for len in 0..message.len() {
+            let mut hashtest = crate::md5::MD5::new();
+            hashtest.update(&message[..len]);
+            assert_eq!(
+                hashtest.finalize().digest(),
+                crate::md5::const_digest(&message[..len])
+            );
+            assert_eq!(
+                crate::md5::padding(len as u64),
+                crate::md5::padding_bits(len as u64 * 8)
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_14() {
+        // a state exported after a partial byte keep its bits
+        let mut hashtest = crate::md5::MD5::new();
+        hashtest.update(b"a");
+        hashtest.update_bits(&[0b1010_0000], 3);
+        let state = hashtest.export_state().unwrap();
+
+        let mut hashtest = crate::md5::MD5::import_state(&state).unwrap();
+        hashtest.update(b"bc");
+        let mut whole = crate::md5::MD5::new();
+        whole.update(b"a");
+        whole.update_bits(&[0xac, 0x4c, 0x60], 19);
+        assert_eq!(whole.finalize().digest(), hashtest.finalize().digest());
+    }
 }
@@ -0,0 +1,174 @@
+use crate::algorithm::DynDigest;
+use crate::Algorithm;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+// MultiHasher feed the same input to several digests, so a large file is read only once
+// In the threaded mode every digest run on its own thread. A chunk is copied once into an Arc
+// and sent to all threads through bounded channels, so a slow digest make update wait
+// instead of buffering the whole input in memory.
+pub struct MultiHasher {
+    inner: Inner,
+}
+
+enum Inner {
+    Sequential(Vec<(Algorithm, Box<dyn DynDigest + Send>)>),
+    Threaded(Vec<Worker>),
+}
+
+// Worker is one digest thread of the threaded mode
+struct Worker {
+    algorithm: Algorithm,
+    sender: SyncSender<Arc<[u8]>>,
+    handle: JoinHandle<Vec<u8>>,
+}
+
+// dedup function sort the algorithms and remove duplicates, it is the order of the result map anyway
+fn dedup(algorithms: &[Algorithm]) -> Vec<Algorithm> {
+    let mut algorithms = algorithms.to_vec();
+    algorithms.sort();
+    algorithms.dedup();
+    algorithms
+}
+
+impl MultiHasher {
+    // new method make a hasher that update the digests one after another on the calling thread
+    pub fn new(algorithms: &[Algorithm]) -> Self {
+        let digests = dedup(algorithms)
+            .into_iter()
+            .map(|algorithm| (algorithm, algorithm.new_digest()))
+            .collect();
+        Self {
+            inner: Inner::Sequential(digests),
+        }
+    }
+
+    // threaded method make a hasher with one thread for each digest
+    // capacity is the number of chunks that may wait in each channel, at least 1 is used.
+    pub fn threaded(algorithms: &[Algorithm], capacity: usize) -> Self {
+        let workers = dedup(algorithms)
+            .into_iter()
+            .map(|algorithm| {
+                let (sender, receiver) = sync_channel::<Arc<[u8]>>(capacity.max(1));
+                let mut digest = algorithm.new_digest();
+                let handle = std::thread::spawn(move || {
+                    // the loop end when the sender is dropped by finalize
+                    for chunk in receiver {
+                        digest.update(&chunk);
+                    }
+                    digest.finalize_boxed()
+                });
+                Worker {
+                    algorithm,
+                    sender,
+                    handle,
+                }
+            })
+            .collect();
+        Self {
+            inner: Inner::Threaded(workers),
+        }
+    }
+
+    // algorithms method return the algorithms of this hasher in the order of the result map
+    pub fn algorithms(&self) -> Vec<Algorithm> {
+        match &self.inner {
+            Inner::Sequential(digests) => digests.iter().map(|(algorithm, _)| *algorithm).collect(),
+            Inner::Threaded(workers) => workers.iter().map(|worker| worker.algorithm).collect(),
+        }
+    }
+
+    // update method feed the input to every digest
+    pub fn update(&mut self, input: &[u8]) {
+        if input.is_empty() {
+            return;
+        }
+        match &mut self.inner {
+            Inner::Sequential(digests) => {
+                for (_, digest) in digests.iter_mut() {
+                    digest.update(input);
+                }
+            }
+            Inner::Threaded(workers) => {
+                let chunk: Arc<[u8]> = Arc::from(input);
+                for worker in workers.iter() {
+                    // send fail only if the thread is gone, that is a panic which finalize report
+                    let _ = worker.sender.send(Arc::clone(&chunk));
+                }
+            }
+        }
+    }
+
+    // finalize method return the digest of every algorithm
+    // In the threaded mode this wait for every thread to hash the remaining chunks.
+    pub fn finalize(self) -> BTreeMap<Algorithm, Vec<u8>> {
+        match self.inner {
+            Inner::Sequential(digests) => digests
+                .into_iter()
+                .map(|(algorithm, digest)| (algorithm, digest.finalize_boxed()))
+                .collect(),
+            Inner::Threaded(workers) => workers
+                .into_iter()
+                .map(|worker| {
+                    drop(worker.sender);
+                    let digest = worker.handle.join().expect("digest thread panicked");
+                    (worker.algorithm, digest)
+                })
+                .collect(),
+        }
+    }
+}
+
+// Implement io::Write trait to MultiHasher, io::copy(&mut file, &mut hasher) hash a file in one pass
+impl Write for MultiHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::multi::MultiHasher;
+    use crate::Algorithm;
+
+    #[test]
+    fn test_sequential() {
+        let mut hasher = MultiHasher::new(&[Algorithm::Sha1, Algorithm::Md5, Algorithm::Sha1]);
+        assert_eq!(vec![Algorithm::Md5, Algorithm::Sha1], hasher.algorithms());
+        hasher.update(b"Hel");
+        hasher.update(b"lo");
+        let result = hasher.finalize();
+
+        assert_eq!(2, result.len());
+        assert_eq!(
+            "8b1a9953c4611296a827abf8c47804d7",
+            crate::hex::encode(&result[&Algorithm::Md5])
+        );
+        assert_eq!(
+            "f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0",
+            crate::hex::encode(&result[&Algorithm::Sha1])
+        );
+    }
+
+    #[test]
+    fn test_threaded() {
+        // every algorithm with a capacity of 1, so update often wait for a slow thread
+        let input: Vec<u8> = (0..100_000u32).map(|i| (i * 7 + i / 251) as u8).collect();
+        let mut hasher = MultiHasher::threaded(&Algorithm::ALL, 1);
+        std::io::copy(&mut &input[..], &mut hasher).unwrap();
+        let result = hasher.finalize();
+
+        assert_eq!(Algorithm::ALL.len(), result.len());
+        for algorithm in Algorithm::ALL {
+            assert_eq!(algorithm.digest(&input), result[&algorithm]);
+        }
+    }
+}
@@ -1,37 +1,48 @@
-use std::fmt::Display;
-use std::fmt::Write;
+use core::fmt::Display;
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+// IV is the initial chaining value
+const IV: [u32; 5] = [
+    0x67452301, // h0
+    0xefcdab89, // h1
+    0x98badcfe, // h2
+    0x10325476, // h3
+    0xc3d2e1f0, // h4
+];
+
+// Sha1 keep only the chaining value and the last incomplete block,
+// so the memory does not grow with the input and the state can be exported (see state.rs).
+#[derive(Clone)]
 pub struct Sha1 {
-    message: Vec<u8>,
     hash: [u32; 5],
+    // bytes of the last incomplete block
+    buffer: [u8; 64],
+    buffer_len: usize,
+    // total input length in bytes
+    length: u64,
+    // bits after the last whole byte, see update_bits, they are the highest bits of partial
+    partial: u8,
+    partial_bits: u32,
+    // variant of the compression, Params::SHA1 for the real SHA-1
+    params: Params,
+    // every compressed block when tracing is on, tracing need the alloc feature, see Sha1::traced
+    #[cfg(feature = "alloc")]
+    trace: Option<Vec<BlockTrace>>,
 }
 
 // Implement Display trait to Sha1 struct
 impl Display for Sha1 {
     // fmtメソッドを定義
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         // Convert hash value to hexadecimal string
         // If not set to 08x, the leading 0 will disappear and 0x08ce5404 will not be displayed
-        let hex = self.hash.iter().fold(String::new(), |mut acc, b| {
-            write!(&mut acc, "{:08x}", b).unwrap();
-            acc
-        });
-
-        /*
-        The original code is shown below, but it gives a clippy error `use of `format!` to build up a string from an iterator`.
-        Using the `format!` macro to build up a string from an iterator results in poor performance.
-        This is because using the format! macro causes memory allocation and freeing.
-        So, when you make strings from iterators, use fold function and write! macro instead of format! macro to improve performance.
-        ```rust
-        let hex = self
-            .hash
-            .iter()
-            .map(|b| format!("{:08x}", b))
-            .collect::<String>();
-        ```
-        */
-        // Write to formatter
-        write!(f, "{}", hex)
+        // Each word is written to the formatter directly, no String is built, so no allocator is needed.
+        for word in self.hash {
+            write!(f, "{:08x}", word)?;
+        }
+        Ok(())
     }
 }
 
@@ -57,101 +68,589 @@ impl Sha1Digest for Sha1 {
     // new method return the initialized Sha1 struct
     fn new() -> Self {
         Self {
-            message: Vec::new(),
-            hash: [
-                0x67452301, // h0
-                0xefcdab89, // h1
-                0x98badcfe, // h2
-                0x10325476, // h3
-                0xc3d2e1f0, // h4
-            ],
+            hash: IV,
+            buffer: [0; 64],
+            buffer_len: 0,
+            length: 0,
+            partial: 0,
+            partial_bits: 0,
+            params: Params::SHA1,
+            #[cfg(feature = "alloc")]
+            trace: None,
         }
     }
 
     // update method add input message
+    // After an update_bits which ended in the middle of a byte, the bytes are shifted by update_bits.
     fn update(&mut self, input: &[u8]) {
-        self.message.extend_from_slice(input);
+        if self.partial_bits == 0 {
+            self.update_bytes(input);
+        } else {
+            self.update_bits(input, input.len() * 8);
+        }
     }
+
     // finalize method do padding and calculate sha1 hash
     fn finalize(mut self) -> Self::Output {
-        // Padding processing
-        // 1 byte = 8 bits, Hello = 5 bytes = (5 * 8) bits = 40 bits
-        // message length (in bits)
-        // Add 8bit/1byte 0x80 to the end of the message 0x80 = 10000000
-        // add 0x80 to the end of the message 0x80 = 10000000
-        // message length comes from 8-bit units.
-        // Add 1 bit of 1 and 0 for the remaining 7 bits because we don't think there is any room in the last byte of the message.
-        // This is the reason for 0x80.
-        let len = (self.message.len() * 8) as u64;
-        // Add 1 bit of 1
-        self.message.push(0x80);
-        // +8 here will be 8 bytes = 64 bits to add the last message length.
-        // Padding is done until mod 512 becomes 0.
-        // This code itself is designed to handle more than 447 bits.
-        // 512 -> 512 - 64 -> 448 -1 -> 447bit => this 447 is the longest in the last block.
-        // In fact, even if you are not aware of this kind of thing, the following code is used to express it.
-        while (self.message.len() + 8) % Self::BLOCK_SIZE != 0 {
-            // add 0 bit
-            self.message.push(0x00);
-        }
-        // add 64bit message length
-        self.message.extend_from_slice(&len.to_be_bytes());
-
-        // calc hash
-        for chunk in self.message.chunks(Self::BLOCK_SIZE) {
-            // div chuck to 16 - 31bit word
-            let mut words = [0u32; 80];
-            // A variable reference is obtained for the first 16 elements of words with take(16).
-            for (i, word) in words.iter_mut().take(16).enumerate() {
-                // each 4byte of chunk is converted to a 32-bit unsigned integer
-                // word is a reference, so *word must be dereferenced
-                // this is often done to change references to elements in the iterator
-                *word = u32::from_be_bytes(chunk[i * 4..i * 4 + 4].try_into().unwrap());
+        // the bits of a partial byte share their byte with the 1 bit of the padding
+        let bits = self
+            .length
+            .wrapping_mul(8)
+            .wrapping_add(self.partial_bits as u64);
+        let (mut padding, len) = const_padding(bits);
+        padding[0] |= self.partial;
+        self.partial_bits = 0;
+        // update add the padding to the length, but it is not used after this
+        self.update_bytes(&padding[..len]);
+        // return Sha1 struct
+        self
+    }
+}
+
+impl Sha1 {
+    // update_bytes method add whole bytes
+    // Every complete 512bit block is compressed at once, only the rest is kept in the buffer.
+    fn update_bytes(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // fill the incomplete block from the previous update first
+        if self.buffer_len > 0 {
+            let take = (Self::BLOCK_SIZE - self.buffer_len).min(input.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&input[..take]);
+            self.buffer_len += take;
+            input = &input[take..];
+            if self.buffer_len < Self::BLOCK_SIZE {
+                return;
             }
+            let block = self.buffer;
+            self.compress(&block);
+            self.buffer_len = 0;
+        }
+
+        let mut chunks = input.chunks_exact(Self::BLOCK_SIZE);
+        for chunk in &mut chunks {
+            self.compress(chunk.try_into().unwrap());
+        }
+        let rest = chunks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    // update_bits method add the first bit_len bits of data, for messages which are not whole bytes
+    // The bits of a byte are taken from the highest one, like FIPS 180-4 and the bit oriented CAVP vectors,
+    // so update_bits(&[0b1010_0000], 3) add the bits 1, 0, 1. The input can go on after a partial byte,
+    // every next byte is then shifted into the bits kept in partial.
+    pub fn update_bits(&mut self, data: &[u8], bit_len: usize) {
+        assert!(bit_len <= data.len() * 8, "bit_len is longer than data");
+        let whole = &data[..bit_len / 8];
+        let rest = (bit_len % 8) as u32;
+        // the last bits, the unused low bits are cleared
+        let last = if rest > 0 {
+            data[bit_len / 8] & !(0xff >> rest)
+        } else {
+            0
+        };
+        if self.partial_bits == 0 {
+            self.update_bytes(whole);
+            self.partial = last;
+            self.partial_bits = rest;
+            return;
+        }
 
-            // Expand to 80 32-bit words
-            for i in 16..80 {
-                let temp = words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16];
-                words[i] = temp.rotate_left(1);
+        // the shifted bytes go through a buffer of one block, so no allocator is needed
+        let shift = self.partial_bits;
+        let mut aligned = [0u8; 64];
+        for chunk in whole.chunks(64) {
+            for (out, &byte) in aligned.iter_mut().zip(chunk) {
+                *out = self.partial | byte >> shift;
+                self.partial = byte << (8 - shift);
             }
+            self.update_bytes(&aligned[..chunk.len()]);
+        }
+        self.partial |= last >> shift;
+        if shift + rest >= 8 {
+            let byte = self.partial;
+            self.update_bytes(&[byte]);
+            self.partial = last << (8 - shift);
+            self.partial_bits = shift + rest - 8;
+        } else {
+            self.partial_bits = shift + rest;
+        }
+    }
+}
+
+// padding function return the bytes that finalize add after a message of length bytes
+// It is public because the length extension attack (attacks::length_extension) need the same glue padding.
+// The length is modulo 2^64 bits like the standard says, so it is wrapping_mul and not a cast of usize.
+#[cfg(feature = "alloc")]
+pub fn padding(length: u64) -> Vec<u8> {
+    padding_bits(length.wrapping_mul(8))
+}
+
+// padding_bits function return the padding after a message of bits bits
+// When the message end in the middle of a byte, the first byte of the padding is that byte:
+// the 1 bit come right after the last bit of the message and the bits of the message must be or-ed in.
+#[cfg(feature = "alloc")]
+pub fn padding_bits(bits: u64) -> Vec<u8> {
+    let (padding, len) = const_padding(bits);
+    padding[..len].to_vec()
+}
+
+// const_padding function is padding_bits without a Vec, for const_digest
+// It return the padding in an array of the longest padding, 1 + 63 + 8 bytes, and its length.
+pub(crate) const fn const_padding(bits: u64) -> ([u8; 72], usize) {
+    // Padding processing
+    // 1 byte = 8 bits, Hello = 5 bytes = (5 * 8) bits = 40 bits
+    // message length (in bits)
+    // Add 8bit/1byte 0x80 to the end of the message 0x80 = 10000000
+    // message length comes from 8-bit units.
+    // Add 1 bit of 1 and 0 for the remaining 7 bits because we don't think there is any room in the last byte of the message.
+    // This is the reason for 0x80. After a partial byte the 1 bit is moved down to the first free bit.
+    // Add 1 bit of 1, and 0 bits until 8 bytes are left in the last block.
+    // If the last block has more than 55 bytes, the length does not fit and one more block is needed.
+    let rest = (bits / 8 % 64) as usize;
+    let zeros = if rest < 56 { 55 - rest } else { 119 - rest };
+    let mut padding = [0u8; 72];
+    padding[0] = 0x80 >> (bits % 8);
+    // add 64bit message length
+    let length = bits.to_be_bytes();
+    let mut i = 0;
+    while i < 8 {
+        padding[1 + zeros + i] = length[i];
+        i += 1;
+    }
+    (padding, 1 + zeros + 8)
+}
+
+// const_digest function calculate the SHA-1 of a whole message at compile time
+//   const ID: [u8; 20] = sha1::const_digest(b"my-protocol v1");
+// It is the same compress and padding as Sha1, it can be called at run time too.
+pub const fn const_digest(input: &[u8]) -> [u8; 20] {
+    let mut hash = IV;
+    let mut rest = input;
+    while let Some((block, tail)) = rest.split_first_chunk::<64>() {
+        compress(&mut hash, block);
+        rest = tail;
+    }
+
+    // the rest and the padding fill one or two blocks
+    let (padding, len) = const_padding((input.len() as u64).wrapping_mul(8));
+    let mut last = [0u8; 128];
+    let mut i = 0;
+    while i < rest.len() {
+        last[i] = rest[i];
+        i += 1;
+    }
+    while i < rest.len() + len {
+        last[i] = padding[i - rest.len()];
+        i += 1;
+    }
+    compress(&mut hash, last.first_chunk::<64>().unwrap());
+    if i == 128 {
+        compress(&mut hash, last.last_chunk::<64>().unwrap());
+    }
+
+    let mut digest = [0u8; 20];
+    let mut i = 0;
+    while i < 20 {
+        digest[i] = hash[i / 4].to_be_bytes()[i % 4];
+        i += 1;
+    }
+    digest
+}
+
+// compress function process one 512bit block and update the chaining value
+// It is a const fn for const_digest, so the loops are while loops, iterators can not be used in const.
+const fn compress(hash: &mut [u32; 5], chunk: &[u8; 64]) {
+    // div chuck to 16 - 31bit word
+    let mut words = [0u32; 80];
+    let mut i = 0;
+    while i < 16 {
+        // each 4byte of chunk is converted to a 32-bit unsigned integer
+        words[i] = u32::from_be_bytes([
+            chunk[i * 4],
+            chunk[i * 4 + 1],
+            chunk[i * 4 + 2],
+            chunk[i * 4 + 3],
+        ]);
+        i += 1;
+    }
+
+    // Expand to 80 32-bit words
+    while i < 80 {
+        let temp = words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16];
+        words[i] = temp.rotate_left(1);
+        i += 1;
+    }
+
+    let mut a = hash[0];
+    let mut b = hash[1];
+    let mut c = hash[2];
+    let mut d = hash[3];
+    let mut e = hash[4];
+
+    // main loop
+    let mut i = 0;
+    while i < 80 {
+        let (f, k) = match i {
+            0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+            20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+            40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+            60..=79 => (b ^ c ^ d, 0xca62_c1d6),
+            _ => unreachable!(),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(words[i]);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+        i += 1;
+    }
+
+    // refresh hash
+    hash[0] = hash[0].wrapping_add(a);
+    hash[1] = hash[1].wrapping_add(b);
+    hash[2] = hash[2].wrapping_add(c);
+    hash[3] = hash[3].wrapping_add(d);
+    hash[4] = hash[4].wrapping_add(e);
+}
+
+// Round is one round of the compression, as the tracing mode record it
+// a..e are the registers after the round, so a is the new value and the others are shifted from the round before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Round {
+    // round 0..79
+    pub round: usize,
+    // "Ch" (0..19), "Parity" (20..39 and 60..79) or "Maj" (40..59)
+    pub function: &'static str,
+    pub k: u32,
+    // the word of the schedule used in this round
+    pub w: u32,
+    pub a: u32,
+    pub b: u32,
+    pub c: u32,
+    pub d: u32,
+    pub e: u32,
+}
+
+// BlockTrace is the record of the compression of one block
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockTrace {
+    // the 80 words of the message schedule, the first 16 are the block
+    pub schedule: [u32; 80],
+    // chaining value before and after the block, h0..h4
+    pub input: [u32; 5],
+    pub rounds: Vec<Round>,
+    pub output: [u32; 5],
+}
+
+// compress_rounds function do the same as compress and stop after the given number of rounds
+// sha0 leave out the rotation of the schedule, record is called with every round. The schedule is returned.
+fn compress_rounds(
+    hash: &mut [u32; 5],
+    chunk: &[u8; 64],
+    rounds: usize,
+    sha0: bool,
+    mut record: impl FnMut(Round),
+) -> [u32; 80] {
+    let mut schedule = [0u32; 80];
+    for (word, bytes) in schedule.iter_mut().zip(chunk.chunks(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().unwrap());
+    }
+    // SHA-1 is SHA-0 with this rotate_left(1), added in 1995 for a weakness which was not published
+    let rotation = if sha0 { 0 } else { 1 };
+    for i in 16..80 {
+        schedule[i] = (schedule[i - 3] ^ schedule[i - 8] ^ schedule[i - 14] ^ schedule[i - 16])
+            .rotate_left(rotation);
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e] = *hash;
+    for (i, w) in schedule.iter().take(rounds).enumerate() {
+        let (function, f, k) = match i {
+            0..=19 => ("Ch", (b & c) | (!b & d), 0x5a82_7999),
+            20..=39 => ("Parity", b ^ c ^ d, 0x6ed9_eba1),
+            40..=59 => ("Maj", (b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+            _ => ("Parity", b ^ c ^ d, 0xca62_c1d6),
+        };
+        let temp = a
+            .rotate_left(5)
+            .wrapping_add(f)
+            .wrapping_add(e)
+            .wrapping_add(k)
+            .wrapping_add(*w);
+        e = d;
+        d = c;
+        c = b.rotate_left(30);
+        b = a;
+        a = temp;
+        record(Round {
+            round: i,
+            function,
+            k,
+            w: *w,
+            a,
+            b,
+            c,
+            d,
+            e,
+        });
+    }
+
+    hash[0] = hash[0].wrapping_add(a);
+    hash[1] = hash[1].wrapping_add(b);
+    hash[2] = hash[2].wrapping_add(c);
+    hash[3] = hash[3].wrapping_add(d);
+    hash[4] = hash[4].wrapping_add(e);
+    schedule
+}
+
+// trace_compress function compress one block like the variant and record the schedule and every round
+#[cfg(feature = "alloc")]
+fn trace_compress(hash: &mut [u32; 5], chunk: &[u8; 64], params: &Params) -> BlockTrace {
+    let input = *hash;
+    let mut rounds = Vec::with_capacity(params.rounds);
+    let schedule = compress_rounds(hash, chunk, params.rounds, params.sha0, |round| {
+        rounds.push(round)
+    });
+    BlockTrace {
+        schedule,
+        input,
+        rounds,
+        output: *hash,
+    }
+}
+
+// Params choose a variant of SHA-1 for the cryptanalysis exercises, fewer rounds, another IV or SHA-0
+// The padding and the feed forward of the chaining value stay the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Params {
+    // number of rounds, 0..=80
+    pub rounds: usize,
+    // initial chaining value h0..h4
+    pub iv: [u32; 5],
+    // SHA-0, the message schedule without the rotation
+    pub sha0: bool,
+}
 
-            let mut a = self.hash[0];
-            let mut b = self.hash[1];
-            let mut c = self.hash[2];
-            let mut d = self.hash[3];
-            let mut e = self.hash[4];
-
-            // main loop
-            for (i, _item) in words.iter().enumerate() {
-                let (f, k) = match i {
-                    0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
-                    20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
-                    40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
-                    60..=79 => (b ^ c ^ d, 0xca62_c1d6),
-                    _ => unreachable!(),
-                };
-                let temp = a
-                    .rotate_left(5)
-                    .wrapping_add(f)
-                    .wrapping_add(e)
-                    .wrapping_add(k)
-                    .wrapping_add(words[i]);
-                e = d;
-                d = c;
-                c = b.rotate_left(30);
-                b = a;
-                a = temp;
+impl Params {
+    // SHA1 is the real SHA-1
+    pub const SHA1: Params = Params {
+        rounds: 80,
+        iv: IV,
+        sha0: false,
+    };
+    // SHA0 is SHA-0, FIPS 180 before it was withdrawn
+    pub const SHA0: Params = Params {
+        rounds: 80,
+        iv: IV,
+        sha0: true,
+    };
+
+    // compress method is the compression function of the variant, hash is the chaining value
+    pub fn compress(&self, hash: &mut [u32; 5], block: &[u8; 64]) {
+        assert!(self.rounds <= 80, "SHA-1 has at most 80 rounds");
+        if self.rounds == 80 && !self.sha0 {
+            compress(hash, block);
+        } else {
+            compress_rounds(hash, block, self.rounds, self.sha0, |_| {});
+        }
+    }
+}
+
+// Display print the trace as a table for the terminal, the schedule 8 words a line and then one line per round
+#[cfg(feature = "alloc")]
+impl Display for BlockTrace {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        writeln!(f, "schedule")?;
+        for (i, words) in self.schedule.chunks(8).enumerate() {
+            write!(f, "w{:<3}", i * 8)?;
+            for word in words {
+                write!(f, " {:08x}", word)?;
             }
+            writeln!(f)?;
+        }
+        writeln!(
+            f,
+            "input  {:08x} {:08x} {:08x} {:08x} {:08x}",
+            self.input[0], self.input[1], self.input[2], self.input[3], self.input[4]
+        )?;
+        writeln!(
+            f,
+            "round f      K        w        a        b        c        d        e"
+        )?;
+        for round in &self.rounds {
+            writeln!(
+                f,
+                "{:>5} {:<6} {:08x} {:08x} {:08x} {:08x} {:08x} {:08x} {:08x}",
+                round.round,
+                round.function,
+                round.k,
+                round.w,
+                round.a,
+                round.b,
+                round.c,
+                round.d,
+                round.e
+            )?;
+        }
+        writeln!(
+            f,
+            "output {:08x} {:08x} {:08x} {:08x} {:08x}",
+            self.output[0], self.output[1], self.output[2], self.output[3], self.output[4]
+        )
+    }
+}
 
-            // refresh hash
-            self.hash[0] = self.hash[0].wrapping_add(a);
-            self.hash[1] = self.hash[1].wrapping_add(b);
-            self.hash[2] = self.hash[2].wrapping_add(c);
-            self.hash[3] = self.hash[3].wrapping_add(d);
-            self.hash[4] = self.hash[4].wrapping_add(e);
+impl Sha1 {
+    // with_params method return a hash of a variant of SHA-1, see Params
+    // The state of a variant can not be exported, export_state return StateError::Variant.
+    pub fn with_params(params: Params) -> Self {
+        assert!(params.rounds <= 80, "SHA-1 has at most 80 rounds");
+        Self {
+            hash: params.iv,
+            params,
+            ..<Self as Sha1Digest>::new()
         }
-        // return Sha1 struct
-        self
+    }
+
+    // traced method return a hash which record every block it compress, for teaching
+    // The padding blocks of finalize are recorded too, trace return them all after finalize.
+    #[cfg(feature = "alloc")]
+    pub fn traced() -> Self {
+        Self::traced_with_params(Params::SHA1)
+    }
+
+    // traced_with_params method return a traced hash of a variant of SHA-1
+    #[cfg(feature = "alloc")]
+    pub fn traced_with_params(params: Params) -> Self {
+        Self {
+            trace: Some(Vec::new()),
+            ..Self::with_params(params)
+        }
+    }
+
+    // trace method return the record of every block compressed so far, empty when tracing is off
+    #[cfg(feature = "alloc")]
+    pub fn trace(&self) -> &[BlockTrace] {
+        self.trace.as_deref().unwrap_or(&[])
+    }
+
+    // compress method compress one block like the variant, with its trace when tracing is on
+    fn compress(&mut self, block: &[u8; 64]) {
+        #[cfg(feature = "alloc")]
+        if let Some(trace) = &mut self.trace {
+            trace.push(trace_compress(&mut self.hash, block, &self.params));
+            return;
+        }
+        self.params.compress(&mut self.hash, block);
+    }
+
+    // digest method return the hash value as 20 bytes
+    // sha1 words are big endian, so the bytes are in the same order as the Display output
+    pub fn digest(&self) -> [u8; 20] {
+        let mut bytes = [0u8; 20];
+        for (chunk, word) in bytes.chunks_mut(4).zip(self.hash.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    // from_digest method rebuild a hash from its output, as if length bytes had been hashed
+    // The output is the whole chaining value, this is what make the length extension attack possible.
+    // length must be a multiple of the block size, that is the message together with its padding.
+    pub fn from_digest(digest: &[u8; 20], length: u64) -> Self {
+        assert!(
+            length.is_multiple_of(Self::BLOCK_SIZE as u64),
+            "length must be a multiple of the block size"
+        );
+        let mut hash = [0u32; 5];
+        for (word, bytes) in hash.iter_mut().zip(digest.chunks(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        Self {
+            hash,
+            buffer: [0; 64],
+            buffer_len: 0,
+            length,
+            partial: 0,
+            partial_bits: 0,
+            params: Params::SHA1,
+            #[cfg(feature = "alloc")]
+            trace: None,
+        }
+    }
+
+    // export_state method save the state of a partially fed hash, see state.rs for the format
+    // The hash can be restored with import_state, even in another process, and fed with the rest of the input.
+    // The bits after the last whole byte of update_bits are saved too.
+    // Only the real SHA-1 can be exported, a variant of with_params is refused.
+    #[cfg(feature = "alloc")]
+    pub fn export_state(&self) -> Result<Vec<u8>, crate::state::StateError> {
+        if self.params != Params::SHA1 {
+            return Err(crate::state::StateError::Variant);
+        }
+        Ok(crate::state::encode(
+            crate::state::ALGORITHM_SHA1,
+            self.length,
+            self.partial_bits,
+            self.partial,
+            &self.digest(),
+            &self.buffer[..self.buffer_len],
+        ))
+    }
+
+    // import_state method restore a hash saved by export_state
+    #[cfg(feature = "alloc")]
+    pub fn import_state(state: &[u8]) -> Result<Self, crate::state::StateError> {
+        let state = crate::state::decode(
+            state,
+            crate::state::ALGORITHM_SHA1,
+            Self::HASH_SIZE,
+            Self::BLOCK_SIZE,
+        )?;
+        let mut hash = [0u32; 5];
+        for (word, bytes) in hash.iter_mut().zip(state.chaining.chunks(4)) {
+            *word = u32::from_be_bytes(bytes.try_into().unwrap());
+        }
+        let mut buffer = [0u8; 64];
+        buffer[..state.partial.len()].copy_from_slice(state.partial);
+        Ok(Self {
+            hash,
+            buffer,
+            buffer_len: state.partial.len(),
+            length: state.length,
+            partial: state.byte,
+            partial_bits: state.bits,
+            params: Params::SHA1,
+            #[cfg(feature = "alloc")]
+            trace: None,
+        })
+    }
+}
+
+// Implement the common Digest trait to Sha1 struct
+impl crate::Digest for Sha1 {
+    type Output = [u8; 20];
+
+    const OUTPUT_SIZE: usize = 20;
+
+    fn new() -> Self {
+        <Self as Sha1Digest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Sha1Digest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha1Digest::finalize(self).digest()
     }
 }
 
@@ -195,4 +694,293 @@ mod tests {
 
         assert_eq!("da39a3ee5e6b4b0d3255bfef95601890afd80709", result);
     }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_4() {
+        // a state exported in the middle of a block is restored and fed with the rest
+        let input = b"Aa0Aa1Aa2Aa3Aa4Aa5Aa6Aa7Aa8Aa9Ab0Ab1Ab2Ab3Ab4Ab5Ab6Ab7Ab8Ab9Ac0Ac1Ac2Ac3Ac4Ac5Ac6Ac7Ac8Ac9";
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update(&input[..70]);
+        let state = hashtest.export_state().unwrap();
+        // header 15 bytes + chaining value 20 bytes + 6 bytes of the second block
+        assert_eq!(15 + 20 + 6, state.len());
+
+        let mut hashtest = crate::sha1::Sha1::import_state(&state).unwrap();
+        hashtest.update(&input[70..]);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        let mut whole = crate::sha1::Sha1::new();
+        whole.update(input);
+        assert_eq!(format!("{}", whole.finalize()), result);
+    }
+
+    #[test]
+    #[cfg(all(feature = "alloc", feature = "md5"))]
+    fn test_5() {
+        use crate::state::StateError;
+
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update(b"Hello");
+        let state = hashtest.export_state().unwrap();
+
+        let mut bad = state.clone();
+        bad[0] = b'X';
+        assert_eq!(
+            Some(StateError::BadMagic),
+            crate::sha1::Sha1::import_state(&bad).err()
+        );
+
+        let mut bad = state.clone();
+        bad[3] = 3;
+        assert_eq!(
+            Some(StateError::UnsupportedVersion(3)),
+            crate::sha1::Sha1::import_state(&bad).err()
+        );
+
+        assert_eq!(
+            Some(StateError::BadLength {
+                expected: state.len(),
+                found: state.len() - 1
+            }),
+            crate::sha1::Sha1::import_state(&state[..state.len() - 1]).err()
+        );
+
+        // a MD5 state can not be imported into Sha1
+        let md5_state = <crate::md5::MD5 as crate::md5::MD5Digest>::new()
+            .export_state()
+            .unwrap();
+        assert_eq!(
+            Some(StateError::WrongAlgorithm {
+                expected: crate::state::ALGORITHM_SHA1,
+                found: crate::state::ALGORITHM_MD5
+            }),
+            crate::sha1::Sha1::import_state(&md5_state).err()
+        );
+
+        // the state of a variant would be resumed as the real SHA-1, so it is refused
+        let sha0 = crate::sha1::Sha1::with_params(crate::sha1::Params::SHA0);
+        assert_eq!(Some(StateError::Variant), sha0.export_state().err());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_6() {
+        // the traced hash give the same digest and record the schedule and each round
+        let input = b"abc";
+        let mut hashtest = crate::sha1::Sha1::traced();
+        hashtest.update(input);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        let trace = hashtest.trace();
+        println!("{}", trace[0]);
+
+        assert_eq!("a9993e364706816aba3e25717850c26c9cd0d89d", result);
+        assert_eq!(1, trace.len());
+        assert_eq!(0x61626380, trace[0].schedule[0]);
+        assert_eq!(0x00000018, trace[0].schedule[15]);
+        assert_eq!(80, trace[0].rounds.len());
+        // FIPS 180 example, the registers after round 0 and round 79
+        let round = &trace[0].rounds[0];
+        assert_eq!(
+            [0x0116fc33, 0x67452301, 0x7bf36ae2, 0x98badcfe, 0x10325476],
+            [round.a, round.b, round.c, round.d, round.e]
+        );
+        let round = &trace[0].rounds[79];
+        assert_eq!(
+            [0x42541b35, 0x5738d5e1, 0x21834873, 0x681e6df6, 0xd8fdf6ad],
+            [round.a, round.b, round.c, round.d, round.e]
+        );
+        assert_eq!(
+            ("Maj", 0x8f1b_bcdc),
+            (trace[0].rounds[40].function, trace[0].rounds[40].k)
+        );
+        assert_eq!(94, format!("{}", trace[0]).lines().count());
+    }
+
+    #[test]
+    fn test_7() {
+        // SHA-0, the vectors of FIPS 180
+        let input = b"abc";
+        let mut hashtest = crate::sha1::Sha1::with_params(crate::sha1::Params::SHA0);
+        hashtest.update(input);
+        let result = format!("{}", hashtest.finalize());
+        println!("{}", result);
+        assert_eq!("0164b8a914cd2a5e74c4f7ff082c4d97f1edf880", result);
+
+        let input = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        let mut hashtest = crate::sha1::Sha1::with_params(crate::sha1::Params::SHA0);
+        hashtest.update(input);
+        let result = format!("{}", hashtest.finalize());
+        println!("{}", result);
+        assert_eq!("d2516ee1acfa5baf33dfc1c471e438449ef134c8", result);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_8() {
+        // the variants: the real parameters, another IV and fewer rounds
+        let input = b"abc";
+        let mut hashtest = crate::sha1::Sha1::with_params(crate::sha1::Params::SHA1);
+        hashtest.update(input);
+        let result = format!("{}", hashtest.finalize());
+        assert_eq!("a9993e364706816aba3e25717850c26c9cd0d89d", result);
+
+        let params = crate::sha1::Params {
+            iv: [0; 5],
+            ..crate::sha1::Params::SHA1
+        };
+        let mut hashtest = crate::sha1::Sha1::with_params(params);
+        hashtest.update(input);
+        let result = format!("{}", hashtest.finalize());
+        println!("{}", result);
+        assert_ne!("a9993e364706816aba3e25717850c26c9cd0d89d", result);
+
+        // with 0 rounds only the feed forward is left, the chaining value is doubled
+        let mut hashtest = crate::sha1::Sha1::with_params(crate::sha1::Params {
+            rounds: 0,
+            ..crate::sha1::Params::SHA1
+        });
+        hashtest.update(input);
+        let result = format!("{}", hashtest.finalize());
+        assert_eq!("ce8a4602df9b57123175b9fc2064a8ec87a5c3e0", result);
+
+        // a traced variant record only its rounds, the registers are the ones of the full SHA-1 so far
+        let params = crate::sha1::Params {
+            rounds: 20,
+            ..crate::sha1::Params::SHA1
+        };
+        let mut hashtest = crate::sha1::Sha1::traced_with_params(params);
+        hashtest.update(input);
+        let reduced = hashtest.finalize();
+        let mut hashtest = crate::sha1::Sha1::traced();
+        hashtest.update(input);
+        let full = hashtest.finalize();
+        assert_eq!(20, reduced.trace()[0].rounds.len());
+        assert_eq!(full.trace()[0].rounds[..20], reduced.trace()[0].rounds[..]);
+    }
+
+    #[test]
+    fn test_9() {
+        // a message of 5 bits, 0b10011 (the bit oriented example of NIST)
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update_bits(&[0x98], 5);
+        let result = format!("{}", hashtest.finalize());
+        println!("{}", result);
+        assert_eq!("29826b003b906e660eff4027ce98af3531ac75ba", result);
+
+        // the unused low bits of the last byte are ignored
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update_bits(&[0xbf], 3);
+        assert_eq!(
+            "4b34059899d74dafee6335cafdc44a9eefb154be",
+            format!("{}", hashtest.finalize())
+        );
+
+        // whole bytes after a partial byte are shifted, 0b101 || "bc" is the 19 bits ac 4c 60
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update_bits(&[0xa0], 3);
+        hashtest.update(b"bc");
+        let mut same = crate::sha1::Sha1::new();
+        same.update_bits(&[0xac, 0x4c, 0x60], 19);
+        let result = format!("{}", hashtest.finalize());
+        assert_eq!("3c0e7b862695dd7f0f7e307783c1f844877a1965", result);
+        assert_eq!(result, format!("{}", same.finalize()));
+
+        // a long input after a partial byte is shifted one block at a time
+        // 0b101 || 0x5a * 200 is ab, 4b * 199 and the 3 bits 010
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update_bits(&[0xa0], 3);
+        hashtest.update(&[0x5a; 200]);
+        let mut packed = vec![0x4b; 201];
+        packed[0] = 0xab;
+        packed[200] = 0x40;
+        let mut same = crate::sha1::Sha1::new();
+        same.update_bits(&packed, 1603);
+        assert_eq!(
+            format!("{}", hashtest.finalize()),
+            format!("{}", same.finalize())
+        );
+
+        // bits which end on a byte boundary are the same as update
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update_bits(&[0x61, 0x62, 0x60], 21);
+        hashtest.update_bits(&[0x60], 3);
+        assert_eq!(
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+            format!("{}", hashtest.finalize())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_10() {
+        // computed by the compiler
+        const ABC: [u8; 20] = crate::sha1::const_digest(b"abc");
+        let result = crate::hex::encode(&ABC);
+        println!("{}", result);
+        assert_eq!("a9993e364706816aba3e25717850c26c9cd0d89d", result);
+
+        // the same as Sha1 for every length around the one and two block paddings
+        let message: Vec<u8> = (0..200u32).map(|i| (i * 37 + 5) as u8).collect();
+        for len in 0..message.len() {
+            let mut hashtest = crate::sha1::Sha1::new();
+            hashtest.update(&message[..len]);
+            assert_eq!(
+                hashtest.finalize().digest(),
+                crate::sha1::const_digest(&message[..len])
+            );
+            assert_eq!(
+                crate::sha1::padding(len as u64),
+                crate::sha1::padding_bits(len as u64 * 8)
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_11() {
+        use crate::state::StateError;
+
+        // a state exported after a partial byte keep its bits, 0b101 || "bc" is the 19 bits ac 4c 60
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update(b"a");
+        hashtest.update_bits(&[0b1010_0000], 3);
+        let state = hashtest.export_state().unwrap();
+        assert_eq!((3, 0b1010_0000), (state[13], state[14]));
+
+        let mut hashtest = crate::sha1::Sha1::import_state(&state).unwrap();
+        hashtest.update(b"bc");
+        let mut whole = crate::sha1::Sha1::new();
+        whole.update(b"a");
+        whole.update_bits(&[0xac, 0x4c, 0x60], 19);
+        assert_eq!(whole.finalize().digest(), hashtest.finalize().digest());
+
+        let mut bad = state.clone();
+        bad[13] = 8;
+        assert_eq!(
+            Some(StateError::BadBits {
+                bits: 8,
+                byte: 0b1010_0000
+            }),
+            crate::sha1::Sha1::import_state(&bad).err()
+        );
+        let mut bad = state.clone();
+        bad[14] |= 1;
+        assert!(crate::sha1::Sha1::import_state(&bad).is_err());
+
+        // a state of version 1 has no bits fields and is still imported
+        let mut hashtest = crate::sha1::Sha1::new();
+        hashtest.update(b"Hello");
+        let mut state = hashtest.export_state().unwrap();
+        state.drain(13..15);
+        state[3] = 1;
+        let mut hashtest = crate::sha1::Sha1::import_state(&state).unwrap();
+        hashtest.update(b" world");
+        let mut whole = crate::sha1::Sha1::new();
+        whole.update(b"Hello world");
+        assert_eq!(whole.finalize().digest(), hashtest.finalize().digest());
+    }
 }
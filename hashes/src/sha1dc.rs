@@ -0,0 +1,387 @@
+use crate::sha1::Sha1Digest;
+use core::fmt::Display;
+
+// SHA-1 with collision detection (counter-cryptanalysis, Marc Stevens and Dan Shumow, "sha1dc")
+// The practical SHA-1 collisions (SHAttered 2017, SHAmbles 2020) are made of near-collision blocks
+// built from a small number of disturbance vectors (DV). For each DV the message difference is known,
+// so for every compressed block we can ask: "if this block was one half of a collision with this DV,
+// what would the other half give?". The other half is recompressed from an intermediate state,
+// and if it reach the same chaining value, the block is one half of a collision.
+// Normal input never trigger this (the chance is about 2^-160 for each check).
+//
+// When a collision is detected and safe_hash is on (the default, like Git), the block is compressed
+// 3 times instead of once. The digest is then different from SHA-1, but the two colliding files
+// get different digests, so a forged signature or object id does not match.
+//
+// sha1dc also check "unavoidable bit conditions" first to skip most DVs quickly.
+// That is only a speedup, here every DV is recompressed for every block to keep the code short,
+// so Sha1DC is a lot slower than Sha1. Use it where the input may come from an attacker.
+
+// DvType is the type of the disturbance vector, I(K,B) or II(K,B) in the paper
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DvType {
+    I,
+    II,
+}
+
+// Dv is one disturbance vector to check
+// The message difference of I(K,B) is the one of I(52,0) shifted by 52 - K steps and rotated left by B,
+// and II(K,B) is the same with II(56,0), so only the two base differences are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Dv {
+    pub kind: DvType,
+    pub k: usize,
+    pub b: u32,
+}
+
+// The 32 disturbance vectors of sha1dc
+#[rustfmt::skip]
+pub const DVS: [Dv; 32] = [
+    Dv { kind: DvType::I, k: 43, b: 0 }, Dv { kind: DvType::I, k: 44, b: 0 },
+    Dv { kind: DvType::I, k: 45, b: 0 }, Dv { kind: DvType::I, k: 46, b: 0 },
+    Dv { kind: DvType::I, k: 46, b: 2 }, Dv { kind: DvType::I, k: 47, b: 0 },
+    Dv { kind: DvType::I, k: 47, b: 2 }, Dv { kind: DvType::I, k: 48, b: 0 },
+    Dv { kind: DvType::I, k: 48, b: 2 }, Dv { kind: DvType::I, k: 49, b: 0 },
+    Dv { kind: DvType::I, k: 49, b: 2 }, Dv { kind: DvType::I, k: 50, b: 0 },
+    Dv { kind: DvType::I, k: 50, b: 2 }, Dv { kind: DvType::I, k: 51, b: 0 },
+    Dv { kind: DvType::I, k: 51, b: 2 }, Dv { kind: DvType::I, k: 52, b: 0 },
+    Dv { kind: DvType::II, k: 45, b: 0 }, Dv { kind: DvType::II, k: 46, b: 0 },
+    Dv { kind: DvType::II, k: 46, b: 2 }, Dv { kind: DvType::II, k: 47, b: 0 },
+    Dv { kind: DvType::II, k: 48, b: 0 }, Dv { kind: DvType::II, k: 49, b: 0 },
+    Dv { kind: DvType::II, k: 49, b: 2 }, Dv { kind: DvType::II, k: 50, b: 0 },
+    Dv { kind: DvType::II, k: 50, b: 2 }, Dv { kind: DvType::II, k: 51, b: 0 },
+    Dv { kind: DvType::II, k: 51, b: 2 }, Dv { kind: DvType::II, k: 52, b: 0 },
+    Dv { kind: DvType::II, k: 53, b: 0 }, Dv { kind: DvType::II, k: 54, b: 0 },
+    Dv { kind: DvType::II, k: 55, b: 0 }, Dv { kind: DvType::II, k: 56, b: 0 },
+];
+
+// First 16 words of the message difference of I(52,0) and II(56,0)
+// The rest follow from the SHA-1 message expansion, because the expansion is linear for xor.
+#[rustfmt::skip]
+const DM_I_52: [u32; 16] = [
+    0x04000010, 0xe8000000, 0x0800000c, 0x18000000, 0xb800000a, 0xc8000010, 0x2c000010, 0xf4000014,
+    0xb4000008, 0x08000000, 0x9800000c, 0xd8000010, 0x08000010, 0xb8000010, 0x98000000, 0x60000000,
+];
+#[rustfmt::skip]
+const DM_II_56: [u32; 16] = [
+    0x2600001a, 0x00000010, 0x0400001c, 0xcc000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x3c000004,
+    0xbc00001a, 0x20000010, 0x2400001c, 0xec000014, 0x0c000002, 0xc0000010, 0xb400001c, 0x2c000004,
+];
+
+impl Dv {
+    // test_step method return the step the recompression start from
+    // sha1dc use step 58 for K <= 49 and step 65 for K >= 50, where the difference of the state is 0.
+    pub fn test_step(&self) -> usize {
+        if self.k <= 49 {
+            58
+        } else {
+            65
+        }
+    }
+
+    // message_difference method return the xor difference of the 80 expanded message words
+    pub fn message_difference(&self) -> [u32; 80] {
+        let (base, top) = match self.kind {
+            DvType::I => (&DM_I_52, 52),
+            DvType::II => (&DM_II_56, 56),
+        };
+        // expand the base difference far enough for the shift
+        let mut long = [0u32; 96];
+        long[..16].copy_from_slice(base);
+        for i in 16..96 {
+            long[i] = (long[i - 3] ^ long[i - 8] ^ long[i - 14] ^ long[i - 16]).rotate_left(1);
+        }
+        let shift = top - self.k;
+        let mut dm = [0u32; 80];
+        for (i, word) in dm.iter_mut().enumerate() {
+            *word = long[i + shift].rotate_left(self.b);
+        }
+        dm
+    }
+}
+
+// f_k function return the boolean function and the constant of step i
+fn f_k(i: usize, b: u32, c: u32, d: u32) -> (u32, u32) {
+    match i {
+        0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+        20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+        40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+        _ => (b ^ c ^ d, 0xca62_c1d6),
+    }
+}
+
+// step function do step i forward, state is (a, b, c, d, e) before the step
+fn step(state: &mut [u32; 5], w: u32, i: usize) {
+    let [a, b, c, d, e] = *state;
+    let (f, k) = f_k(i, b, c, d);
+    let temp = a
+        .rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(e)
+        .wrapping_add(k)
+        .wrapping_add(w);
+    *state = [temp, a, b.rotate_left(30), c, d];
+}
+
+// step_back function undo step i, state is (a, b, c, d, e) after the step
+// Every value except the old e is just moved, and e follow from the new a.
+fn step_back(state: &mut [u32; 5], w: u32, i: usize) {
+    let [temp, a, c30, c, d] = *state;
+    let b = c30.rotate_right(30);
+    let (f, k) = f_k(i, b, c, d);
+    let e = temp
+        .wrapping_sub(a.rotate_left(5))
+        .wrapping_sub(f)
+        .wrapping_sub(k)
+        .wrapping_sub(w);
+    *state = [a, b, c, d, e];
+}
+
+// expand function make the 80 message words of a block
+fn expand(block: &[u8; 64]) -> [u32; 80] {
+    let mut words = [0u32; 80];
+    for (i, word) in words.iter_mut().take(16).enumerate() {
+        *word = u32::from_be_bytes(block[i * 4..i * 4 + 4].try_into().unwrap());
+    }
+    for i in 16..80 {
+        words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+    }
+    words
+}
+
+// compress_words function is the SHA-1 compression of expanded words, it also return the states before step 58 and 65
+fn compress_words(hash: &mut [u32; 5], words: &[u32; 80]) -> ([u32; 5], [u32; 5]) {
+    let mut state = *hash;
+    let mut state_58 = [0; 5];
+    let mut state_65 = [0; 5];
+    for (i, &w) in words.iter().enumerate() {
+        if i == 58 {
+            state_58 = state;
+        } else if i == 65 {
+            state_65 = state;
+        }
+        step(&mut state, w, i);
+    }
+    for (h, s) in hash.iter_mut().zip(state.iter()) {
+        *h = h.wrapping_add(*s);
+    }
+    (state_58, state_65)
+}
+
+// recompress function recompress the other half of a possible collision from the state before step test_step
+// It go backward to get the input chaining value and forward to get the output one.
+fn recompress(words: &[u32; 80], state: &[u32; 5], test_step: usize) -> [u32; 5] {
+    let mut input = *state;
+    for i in (0..test_step).rev() {
+        step_back(&mut input, words[i], i);
+    }
+    let mut output = *state;
+    for (i, &w) in words.iter().enumerate().skip(test_step) {
+        step(&mut output, w, i);
+    }
+    let mut hash = input;
+    for (h, s) in hash.iter_mut().zip(output.iter()) {
+        *h = h.wrapping_add(*s);
+    }
+    hash
+}
+
+#[derive(Clone)]
+pub struct Sha1DC {
+    hash: [u32; 5],
+    // bytes of the last incomplete block
+    buffer: [u8; 64],
+    buffer_len: usize,
+    // total input length in bytes
+    length: u64,
+    safe_hash: bool,
+    collision: bool,
+}
+
+impl Sha1DC {
+    // with_safe_hash method choose what happen when a collision is detected
+    // true (what new does) is the safe hash: the block is compressed 3 times instead of once,
+    // so the digest is not the SHA-1 of the input anymore. Only false return the plain SHA-1.
+    pub fn with_safe_hash(safe_hash: bool) -> Self {
+        let mut hash = <Self as Sha1Digest>::new();
+        hash.safe_hash = safe_hash;
+        hash
+    }
+
+    // collision_detected method return true if a block looked like one half of a collision
+    pub fn collision_detected(&self) -> bool {
+        self.collision
+    }
+
+    // digest method return the hash value as 20 bytes
+    pub fn digest(&self) -> [u8; 20] {
+        let mut bytes = [0u8; 20];
+        for (chunk, word) in bytes.chunks_mut(4).zip(self.hash.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+
+    // process method compress one block and check it for every disturbance vector
+    fn process(&mut self, block: &[u8; 64]) {
+        let words = expand(block);
+        let mut hash = self.hash;
+        let (state_58, state_65) = compress_words(&mut hash, &words);
+
+        for dv in DVS.iter() {
+            let dm = dv.message_difference();
+            let mut other = [0u32; 80];
+            for (i, word) in other.iter_mut().enumerate() {
+                *word = words[i] ^ dm[i];
+            }
+            let state = if dv.test_step() == 58 {
+                &state_58
+            } else {
+                &state_65
+            };
+            if recompress(&other, state, dv.test_step()) == hash {
+                self.collision = true;
+                if self.safe_hash {
+                    // compress the block 2 more times, 240 steps in total
+                    compress_words(&mut hash, &words);
+                    compress_words(&mut hash, &words);
+                }
+                break;
+            }
+        }
+        self.hash = hash;
+    }
+}
+
+// Implement Display trait to Sha1DC struct, the same format as Sha1
+impl Display for Sha1DC {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for word in self.hash {
+            write!(f, "{:08x}", word)?;
+        }
+        Ok(())
+    }
+}
+
+// Implement Sha1Digest trait to Sha1DC struct
+// The digest is the same as Sha1 unless a collision is detected.
+impl Sha1Digest for Sha1DC {
+    type Output = Self;
+
+    fn new() -> Self {
+        Self {
+            hash: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            buffer: [0; 64],
+            buffer_len: 0,
+            length: 0,
+            safe_hash: true,
+            collision: false,
+        }
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // fill the incomplete block from the previous update first
+        if self.buffer_len > 0 {
+            let take = (Self::BLOCK_SIZE - self.buffer_len).min(input.len());
+            self.buffer[self.buffer_len..self.buffer_len + take].copy_from_slice(&input[..take]);
+            self.buffer_len += take;
+            input = &input[take..];
+            if self.buffer_len < Self::BLOCK_SIZE {
+                return;
+            }
+            let block = self.buffer;
+            self.process(&block);
+            self.buffer_len = 0;
+        }
+
+        let mut chunks = input.chunks_exact(Self::BLOCK_SIZE);
+        for chunk in &mut chunks {
+            self.process(chunk.try_into().unwrap());
+        }
+        let rest = chunks.remainder();
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_len = rest.len();
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        // the padding is the same as Sha1
+        let (padding, len) = crate::sha1::const_padding(self.length.wrapping_mul(8));
+        Sha1Digest::update(&mut self, &padding[..len]);
+        self
+    }
+}
+
+// Implement the common Digest trait to Sha1DC struct
+impl crate::Digest for Sha1DC {
+    type Output = [u8; 20];
+
+    const OUTPUT_SIZE: usize = 20;
+
+    fn new() -> Self {
+        <Self as Sha1Digest>::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Sha1Digest::update(self, input);
+    }
+
+    fn finalize(self) -> Self::Output {
+        Sha1Digest::finalize(self).digest()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sha1::Sha1Digest;
+
+    #[test]
+    fn test_1() {
+        let mut hashtest = crate::sha1dc::Sha1DC::new();
+        hashtest.update(b"Hello");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0", result);
+        assert!(!hashtest.collision_detected());
+    }
+
+    #[test]
+    fn test_2() {
+        // normal input of many blocks give the plain SHA-1
+        let input: Vec<u8> = (0..10_000u32).map(|i| (i * 13 + i / 7) as u8).collect();
+        let mut hashtest = crate::sha1dc::Sha1DC::new();
+        hashtest.update(&input);
+        let hashtest = hashtest.finalize();
+
+        let mut plain = crate::sha1::Sha1::new();
+        plain.update(&input);
+        assert_eq!(format!("{}", plain.finalize()), format!("{}", hashtest));
+        assert!(!hashtest.collision_detected());
+    }
+
+    #[test]
+    fn test_3() {
+        // the generated differences are the ones of the sha1dc table
+        let dm = crate::sha1dc::DVS[0].message_difference();
+        assert_eq!([0x08000000, 0x9800000c, 0xd8000010, 0x08000010], dm[..4]);
+        let dm = crate::sha1dc::DVS[4].message_difference();
+        assert_eq!([0xb0000040, 0xd0000053, 0xd0000022, 0x20000000], dm[..4]);
+        let dm = crate::sha1dc::DVS[16].message_difference();
+        assert_eq!([0xec000014, 0x0c000002, 0xc0000010, 0xb400001c], dm[..4]);
+    }
+
+    #[test]
+    fn test_4() {
+        // step_back undo step, so the recompression of the same message give the same hash
+        let block = [0x5au8; 64];
+        let words = crate::sha1dc::expand(&block);
+        let mut hash = [1, 2, 3, 4, 5];
+        let (state_58, state_65) = crate::sha1dc::compress_words(&mut hash, &words);
+
+        assert_eq!(hash, crate::sha1dc::recompress(&words, &state_58, 58));
+        assert_eq!(hash, crate::sha1dc::recompress(&words, &state_65, 65));
+    }
+}
@@ -0,0 +1,465 @@
+use core::fmt::Display;
+use core::hash::{BuildHasher, Hasher};
+
+// SipState hold the four 64bit words v0..v3 and the bytes that do not yet make a 64bit word
+// C_ROUNDS is the number of SipRounds per message word and D_ROUNDS is the number of finalization rounds.
+// SipHash-2-4 is C_ROUNDS = 2, D_ROUNDS = 4 and SipHash-1-3 is C_ROUNDS = 1, D_ROUNDS = 3.
+#[derive(Clone, Copy)]
+struct SipState<const C_ROUNDS: usize, const D_ROUNDS: usize> {
+    v: [u64; 4],
+    tail: [u8; 8],
+    tail_len: usize,
+    // only the lowest 8bit of the length is used, but it is counted in full
+    length: u64,
+}
+
+impl<const C_ROUNDS: usize, const D_ROUNDS: usize> SipState<C_ROUNDS, D_ROUNDS> {
+    fn new(key: &[u8; 16], wide: bool) -> Self {
+        let k0 = u64::from_le_bytes(key[0..8].try_into().unwrap());
+        let k1 = u64::from_le_bytes(key[8..16].try_into().unwrap());
+        let mut v = [
+            k0 ^ 0x736f_6d65_7073_6575, // "somepseu"
+            k1 ^ 0x646f_7261_6e64_6f6d, // "dorandom"
+            k0 ^ 0x6c79_6765_6e65_7261, // "lygenera"
+            k1 ^ 0x7465_6462_7974_6573, // "tedbytes"
+        ];
+        // 128bit output variant change v1 so that its first half differ from the 64bit output
+        if wide {
+            v[1] ^= 0xee;
+        }
+        Self {
+            v,
+            tail: [0; 8],
+            tail_len: 0,
+            length: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn sip_round(&mut self) {
+        let [mut v0, mut v1, mut v2, mut v3] = self.v;
+        v0 = v0.wrapping_add(v1);
+        v1 = v1.rotate_left(13);
+        v1 ^= v0;
+        v0 = v0.rotate_left(32);
+        v2 = v2.wrapping_add(v3);
+        v3 = v3.rotate_left(16);
+        v3 ^= v2;
+        v0 = v0.wrapping_add(v3);
+        v3 = v3.rotate_left(21);
+        v3 ^= v0;
+        v2 = v2.wrapping_add(v1);
+        v1 = v1.rotate_left(17);
+        v1 ^= v2;
+        v2 = v2.rotate_left(32);
+        self.v = [v0, v1, v2, v3];
+    }
+
+    fn compress(&mut self, m: u64) {
+        self.v[3] ^= m;
+        for _ in 0..C_ROUNDS {
+            self.sip_round();
+        }
+        self.v[0] ^= m;
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        self.length = self.length.wrapping_add(input.len() as u64);
+
+        // complete the word left over from the previous update first
+        if self.tail_len > 0 {
+            let take = (8 - self.tail_len).min(input.len());
+            self.tail[self.tail_len..self.tail_len + take].copy_from_slice(&input[..take]);
+            self.tail_len += take;
+            input = &input[take..];
+            if self.tail_len < 8 {
+                return;
+            }
+            self.compress(u64::from_le_bytes(self.tail));
+            self.tail_len = 0;
+        }
+
+        let mut chunks = input.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.compress(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+
+        let rest = chunks.remainder();
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+    }
+
+    // finish method process the last word and run the finalization rounds
+    // The last word is the remaining bytes with the message length in its highest byte.
+    fn finish(mut self, wide: bool) -> (u64, u64) {
+        let mut last = [0u8; 8];
+        last[..self.tail_len].copy_from_slice(&self.tail[..self.tail_len]);
+        last[7] = self.length as u8;
+        self.compress(u64::from_le_bytes(last));
+
+        self.v[2] ^= if wide { 0xee } else { 0xff };
+        for _ in 0..D_ROUNDS {
+            self.sip_round();
+        }
+        let first = self.v[0] ^ self.v[1] ^ self.v[2] ^ self.v[3];
+        if !wide {
+            return (first, 0);
+        }
+
+        self.v[1] ^= 0xdd;
+        for _ in 0..D_ROUNDS {
+            self.sip_round();
+        }
+        let second = self.v[0] ^ self.v[1] ^ self.v[2] ^ self.v[3];
+        (first, second)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SipHash24 {
+    state: SipState<2, 4>,
+    hash: u64,
+}
+
+#[derive(Clone, Copy)]
+pub struct SipHash13 {
+    state: SipState<1, 3>,
+    hash: u64,
+}
+
+#[derive(Clone, Copy)]
+pub struct SipHash128_24 {
+    state: SipState<2, 4>,
+    hash: u128,
+}
+
+#[derive(Clone, Copy)]
+pub struct SipHash128_13 {
+    state: SipState<1, 3>,
+    hash: u128,
+}
+
+// Implement Display trait to SipHash structs
+// SipHash output is defined as little endian bytes, so the words are swapped like md5.
+impl Display for SipHash24 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:016x}", self.hash.swap_bytes())
+    }
+}
+
+impl Display for SipHash13 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:016x}", self.hash.swap_bytes())
+    }
+}
+
+impl Display for SipHash128_24 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:032x}", self.hash.swap_bytes())
+    }
+}
+
+impl Display for SipHash128_13 {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{:032x}", self.hash.swap_bytes())
+    }
+}
+
+// SipHashDigest calculate siphash keyed hash
+pub trait SipHashDigest {
+    // type related to trait
+    type Output;
+
+    // const for 128bit key
+    const KEY_SIZE: usize = 16;
+    // const for 64bit output
+    const HASH_SIZE: usize = 8;
+
+    // there is no new without a key, a siphash with a known key is no protection for the table keys
+    fn new_with_key(key: &[u8; 16]) -> Self;
+    fn update(&mut self, input: &[u8]);
+    fn finalize(self) -> Self::Output;
+}
+
+// Implement SipHashDigest trait to SipHash24 struct
+impl SipHashDigest for SipHash24 {
+    type Output = Self;
+
+    fn new_with_key(key: &[u8; 16]) -> Self {
+        Self {
+            state: SipState::new(key, false),
+            hash: 0,
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.hash = self.state.finish(false).0;
+        self
+    }
+}
+
+// Implement SipHashDigest trait to SipHash13 struct
+impl SipHashDigest for SipHash13 {
+    type Output = Self;
+
+    fn new_with_key(key: &[u8; 16]) -> Self {
+        Self {
+            state: SipState::new(key, false),
+            hash: 0,
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        self.hash = self.state.finish(false).0;
+        self
+    }
+}
+
+// Implement SipHashDigest trait to SipHash128_24 struct
+impl SipHashDigest for SipHash128_24 {
+    type Output = Self;
+
+    // const for 128bit output
+    const HASH_SIZE: usize = 16;
+
+    fn new_with_key(key: &[u8; 16]) -> Self {
+        Self {
+            state: SipState::new(key, true),
+            hash: 0,
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        let (first, second) = self.state.finish(true);
+        self.hash = first as u128 | (second as u128) << 64;
+        self
+    }
+}
+
+// Implement SipHashDigest trait to SipHash128_13 struct
+impl SipHashDigest for SipHash128_13 {
+    type Output = Self;
+
+    // const for 128bit output
+    const HASH_SIZE: usize = 16;
+
+    fn new_with_key(key: &[u8; 16]) -> Self {
+        Self {
+            state: SipState::new(key, true),
+            hash: 0,
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    fn finalize(mut self) -> Self::Output {
+        let (first, second) = self.state.finish(true);
+        self.hash = first as u128 | (second as u128) << 64;
+        self
+    }
+}
+
+impl SipHash24 {
+    // hash method return the 64bit hash value
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl SipHash13 {
+    // hash method return the 64bit hash value
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl SipHash128_24 {
+    // hash method return the 128bit hash value, the first output word is the lower 64bit
+    pub fn hash(&self) -> u128 {
+        self.hash
+    }
+}
+
+impl SipHash128_13 {
+    // hash method return the 128bit hash value, the first output word is the lower 64bit
+    pub fn hash(&self) -> u128 {
+        self.hash
+    }
+}
+
+// Implement Hasher trait so that SipHash can be used with HashMap and HashSet
+// finish does not consume the hasher, so the state is copied before finalization.
+impl Hasher for SipHash24 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.state.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state.finish(false).0
+    }
+}
+
+impl Hasher for SipHash13 {
+    fn write(&mut self, bytes: &[u8]) {
+        self.state.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.state.finish(false).0
+    }
+}
+
+// SipBuildHasher create SipHash24 hashers with the same 128bit key
+// Use a secret random key for tables whose keys are controlled by an attacker.
+#[derive(Clone)]
+pub struct SipBuildHasher {
+    key: [u8; 16],
+}
+
+impl SipBuildHasher {
+    pub fn new(key: [u8; 16]) -> Self {
+        Self { key }
+    }
+}
+
+impl BuildHasher for SipBuildHasher {
+    type Hasher = SipHash24;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        SipHash24::new_with_key(&self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::siphash::SipHashDigest;
+    use std::hash::{BuildHasher, Hasher};
+
+    // key and message of the test vectors in the SipHash paper
+    // key = 00 01 02 ... 0f, message = 00 01 02 ... 0e
+    const KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f,
+    ];
+    const MESSAGE: [u8; 15] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+    ];
+
+    #[test]
+    fn test_1() {
+        let mut hashtest = crate::siphash::SipHash24::new_with_key(&KEY);
+        hashtest.update(&MESSAGE);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("e545be4961ca29a1", result);
+        assert_eq!(0xa129ca6149be45e5, hashtest.hash());
+    }
+
+    #[test]
+    fn test_2() {
+        let mut hashtest = crate::siphash::SipHash24::new_with_key(&KEY);
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("310e0edd47db6f72", result);
+    }
+
+    #[test]
+    fn test_3() {
+        let mut hashtest = crate::siphash::SipHash13::new_with_key(&KEY);
+        hashtest.update(&MESSAGE);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("5699512a6dd820d3", result);
+    }
+
+    #[test]
+    fn test_4() {
+        let mut hashtest = crate::siphash::SipHash128_24::new_with_key(&KEY);
+        hashtest.update(b"");
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("a3817f04ba25a8e66df67214c7550293", result);
+    }
+
+    #[test]
+    fn test_5() {
+        let mut hashtest = crate::siphash::SipHash128_24::new_with_key(&KEY);
+        hashtest.update(&MESSAGE);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("5493e99933b0a8117e08ec0f97cfc3d9", result);
+    }
+
+    #[test]
+    fn test_6() {
+        let mut hashtest = crate::siphash::SipHash128_13::new_with_key(&KEY);
+        hashtest.update(&MESSAGE);
+        let hashtest = hashtest.finalize();
+        let result = format!("{}", hashtest);
+        println!("{}", result);
+
+        assert_eq!("c17e5505b2bd526c2921cdec1e7e0109", result);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let input: Vec<u8> = (0..=255).collect();
+        let mut whole = crate::siphash::SipHash24::new_with_key(&KEY);
+        whole.update(&input);
+        let whole = whole.finalize();
+
+        let mut split = crate::siphash::SipHash24::new_with_key(&KEY);
+        for chunk in input.chunks(5) {
+            split.update(chunk);
+        }
+        let split = split.finalize();
+
+        assert_eq!(whole.hash(), split.hash());
+    }
+
+    #[test]
+    fn test_hasher() {
+        let build = crate::siphash::SipBuildHasher::new(KEY);
+        let mut hasher = build.build_hasher();
+        hasher.write(&MESSAGE);
+        assert_eq!(0xa129ca6149be45e5, hasher.finish());
+
+        // the same key and value always give the same hash
+        let a = build.hash_one("attacker controlled");
+        let b = build.hash_one("attacker controlled");
+        assert_eq!(a, b);
+
+        // a different key give a different hash
+        let c = crate::siphash::SipBuildHasher::new([0xff; 16]).hash_one("attacker controlled");
+        assert_ne!(a, c);
+
+        let mut map = std::collections::HashMap::with_hasher(build);
+        map.insert("key", 1);
+        assert_eq!(Some(&1), map.get("key"));
+    }
+}
@@ -0,0 +1,154 @@
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+// Exported hash state of Sha1 and MD5, to resume hashing later in another process
+// The format is small and versioned, all integers are big endian:
+//
+//   offset  size  field
+//   0       3     magic "HST"
+//   3       1     format version, VERSION
+//   4       1     algorithm id, ALGORITHM_SHA1 or ALGORITHM_MD5
+//   5       8     number of whole bytes hashed so far
+//   13      1     number of bits after the last whole byte, 0 to 7 (see update_bits)
+//   14      1     these bits, in the highest bits of the byte, the other bits are 0
+//   15      n     chaining value, the hash words in the byte order of the algorithm output
+//   15+n    m     partial block, m = byte count % 64
+//
+// The size of the partial block is not stored, it always follow from the byte count,
+// so a truncated or padded state is found by the length check.
+// Version 1 had no bits fields (the chaining value start at 13), it is still imported.
+// The chaining value is the secret of a keyed construction such as HMAC, keep the state private like the key.
+
+const MAGIC: &[u8; 3] = b"HST";
+pub const VERSION: u8 = 2;
+pub const ALGORITHM_SHA1: u8 = 1;
+pub const ALGORITHM_MD5: u8 = 2;
+
+// size of the fields before the chaining value, in version 1 and now
+const HEADER_SIZE_V1: usize = 13;
+const HEADER_SIZE: usize = 15;
+
+// StateError is the reason why a state could not be exported or imported
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateError {
+    // the hash is a variant made by with_params, the format has no room for its parameters
+    // and the state would be resumed as the real algorithm
+    Variant,
+    // the state does not start with the magic bytes
+    BadMagic,
+    // the state was written by a newer format version
+    UnsupportedVersion(u8),
+    // the state is of another algorithm, e.g. a MD5 state imported into Sha1
+    WrongAlgorithm { expected: u8, found: u8 },
+    // the state is shorter or longer than the byte count says
+    BadLength { expected: usize, found: usize },
+    // the bits after the last whole byte are more than 7, or the unused bits of their byte are not 0
+    BadBits { bits: u8, byte: u8 },
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            StateError::Variant => write!(f, "the state of a hash variant can not be exported"),
+            StateError::BadMagic => write!(f, "not an exported hash state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported hash state version {}", version)
+            }
+            StateError::WrongAlgorithm { expected, found } => write!(
+                f,
+                "hash state is for algorithm {}, expected {}",
+                found, expected
+            ),
+            StateError::BadLength { expected, found } => write!(
+                f,
+                "hash state is {} bytes long, expected {}",
+                found, expected
+            ),
+            StateError::BadBits { bits, byte } => write!(
+                f,
+                "hash state has bad partial byte {:02x} of {} bits",
+                byte, bits
+            ),
+        }
+    }
+}
+
+impl core::error::Error for StateError {}
+
+// Decoded is an imported state, the fields of the format above
+pub(crate) struct Decoded<'a> {
+    pub length: u64,
+    pub bits: u32,
+    pub byte: u8,
+    pub chaining: &'a [u8],
+    pub partial: &'a [u8],
+}
+
+// encode function write the state in the format above
+// byte has the bits bits after the last whole byte, like the partial and partial_bits fields of the hashes.
+pub(crate) fn encode(
+    algorithm: u8,
+    length: u64,
+    bits: u32,
+    byte: u8,
+    chaining: &[u8],
+    partial: &[u8],
+) -> Vec<u8> {
+    let mut state = Vec::with_capacity(HEADER_SIZE + chaining.len() + partial.len());
+    state.extend_from_slice(MAGIC);
+    state.push(VERSION);
+    state.push(algorithm);
+    state.extend_from_slice(&length.to_be_bytes());
+    state.push(bits as u8);
+    state.push(byte);
+    state.extend_from_slice(chaining);
+    state.extend_from_slice(partial);
+    state
+}
+
+// decode function check a state of version 1 or 2 and return its fields
+pub(crate) fn decode(
+    state: &[u8],
+    algorithm: u8,
+    chaining_size: usize,
+    block_size: usize,
+) -> Result<Decoded<'_>, StateError> {
+    if state.len() < HEADER_SIZE_V1 || &state[..3] != MAGIC {
+        return Err(StateError::BadMagic);
+    }
+    let header_size = match state[3] {
+        1 => HEADER_SIZE_V1,
+        VERSION => HEADER_SIZE,
+        version => return Err(StateError::UnsupportedVersion(version)),
+    };
+    if state[4] != algorithm {
+        return Err(StateError::WrongAlgorithm {
+            expected: algorithm,
+            found: state[4],
+        });
+    }
+    let length = u64::from_be_bytes(state[5..HEADER_SIZE_V1].try_into().unwrap());
+    let partial_size = (length % block_size as u64) as usize;
+    let expected = header_size + chaining_size + partial_size;
+    if state.len() != expected {
+        return Err(StateError::BadLength {
+            expected,
+            found: state.len(),
+        });
+    }
+    let (bits, byte) = if header_size == HEADER_SIZE {
+        (state[13], state[14])
+    } else {
+        (0, 0)
+    };
+    if bits > 7 || byte & (0xff >> bits) != 0 {
+        return Err(StateError::BadBits { bits, byte });
+    }
+    Ok(Decoded {
+        length,
+        bits: bits as u32,
+        byte,
+        chaining: &state[header_size..header_size + chaining_size],
+        partial: &state[header_size + chaining_size..],
+    })
+}
@@ -0,0 +1,329 @@
+use crate::Algorithm;
+
+// Checksum file format compatible with GNU coreutils md5sum and sha1sum
+// Each line is "HASH  filename" for text mode or "HASH *filename" for binary mode.
+// The BSD style (md5sum --tag, macOS and FreeBSD md5/sha1) is "MD5 (filename) = HASH",
+// it name the algorithm on every line, so one file can mix several algorithms.
+// A filename that contains a backslash, a newline or a carriage return is escaped,
+// and the line then start with a backslash so that old readers can tell it apart.
+// The lines are bytes, not strings: a filename is any bytes on unix and must come back unchanged.
+
+// SumLine is one parsed line of a checksum file
+// algorithm is Some only for the BSD style, a GNU line does not say which algorithm made it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SumLine {
+    pub algorithm: Option<Algorithm>,
+    pub digest: Vec<u8>,
+    pub name: Vec<u8>,
+    pub binary: bool,
+}
+
+// needs_escape function return true if the filename has to be escaped
+pub fn needs_escape(name: &[u8]) -> bool {
+    name.iter().any(|b| matches!(b, b'\\' | b'\n' | b'\r'))
+}
+
+// escape function escape backslash, newline and carriage return like coreutils
+pub fn escape(name: &[u8]) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(name.len());
+    for &b in name {
+        match b {
+            b'\\' => escaped.extend_from_slice(b"\\\\"),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            _ => escaped.push(b),
+        }
+    }
+    escaped
+}
+
+// unescape function is the reverse of escape, None is returned for an unknown escape sequence
+pub fn unescape(name: &[u8]) -> Option<Vec<u8>> {
+    let mut unescaped = Vec::with_capacity(name.len());
+    let mut bytes = name.iter();
+    while let Some(&b) = bytes.next() {
+        if b != b'\\' {
+            unescaped.push(b);
+            continue;
+        }
+        match bytes.next()? {
+            b'\\' => unescaped.push(b'\\'),
+            b'n' => unescaped.push(b'\n'),
+            b'r' => unescaped.push(b'\r'),
+            _ => return None,
+        }
+    }
+    Some(unescaped)
+}
+
+// find and rfind function return the position of the first or the last needle in a line
+fn find(line: &[u8], needle: &[u8]) -> Option<usize> {
+    line.windows(needle.len()).position(|w| w == needle)
+}
+
+fn rfind(line: &[u8], needle: &[u8]) -> Option<usize> {
+    line.windows(needle.len()).rposition(|w| w == needle)
+}
+
+// hex function decode the hex digits of a line, None if they are not all hex digits
+fn hex(digits: &[u8]) -> Option<Vec<u8>> {
+    crate::hex::decode(std::str::from_utf8(digits).ok()?)
+}
+
+// format_gnu function make a GNU checksum line without the trailing newline
+pub fn format_gnu(digest: &[u8], name: &[u8], binary: bool) -> Vec<u8> {
+    let mut line = Vec::new();
+    if needs_escape(name) {
+        line.push(b'\\');
+    }
+    line.extend_from_slice(crate::hex::encode(digest).as_bytes());
+    line.extend_from_slice(if binary { b" *" } else { b"  " });
+    if needs_escape(name) {
+        line.extend_from_slice(&escape(name));
+    } else {
+        line.extend_from_slice(name);
+    }
+    line
+}
+
+// parse_gnu function parse a GNU checksum line, None is returned for an improperly formatted line
+// The trailing newline must be removed before calling this function.
+pub fn parse_gnu(line: &[u8]) -> Option<SumLine> {
+    let (escaped, line) = match line.strip_prefix(b"\\") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    // The hash is the hex digits up to the first space
+    let hex_len = line.iter().position(|b| !b.is_ascii_hexdigit())?;
+    let digest = hex(&line[..hex_len])?;
+    if digest.is_empty() {
+        return None;
+    }
+    let rest = line[hex_len..].strip_prefix(b" ")?;
+
+    // The mode character is ' ' for text and '*' for binary
+    let (binary, name) = match rest.first()? {
+        b'*' => (true, &rest[1..]),
+        b' ' => (false, &rest[1..]),
+        _ => (false, rest),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    let name = if escaped {
+        unescape(name)?
+    } else {
+        name.to_vec()
+    };
+
+    Some(SumLine {
+        algorithm: None,
+        digest,
+        name,
+        binary,
+    })
+}
+
+// format_bsd function make a BSD style checksum line without the trailing newline
+pub fn format_bsd(algorithm: Algorithm, digest: &[u8], name: &[u8]) -> Vec<u8> {
+    let mut line = Vec::new();
+    if needs_escape(name) {
+        line.push(b'\\');
+    }
+    line.extend_from_slice(algorithm.tag().as_bytes());
+    line.extend_from_slice(b" (");
+    if needs_escape(name) {
+        line.extend_from_slice(&escape(name));
+    } else {
+        line.extend_from_slice(name);
+    }
+    line.extend_from_slice(b") = ");
+    line.extend_from_slice(crate::hex::encode(digest).as_bytes());
+    line
+}
+
+// parse_bsd function parse a BSD style checksum line, None is returned for an improperly formatted line
+// An unknown algorithm or a hash of the wrong length for the algorithm is also None.
+pub fn parse_bsd(line: &[u8]) -> Option<SumLine> {
+    let (escaped, line) = match line.strip_prefix(b"\\") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+
+    // The algorithm is before the first " (" and the hash is after the last ") = ",
+    // so a filename with parentheses in it is still found correctly.
+    let open = find(line, b" (")?;
+    let (tag, rest) = (&line[..open], &line[open + 2..]);
+    let close = rfind(rest, b") = ")?;
+    let (name, digits) = (&rest[..close], &rest[close + 4..]);
+    let algorithm: Algorithm = std::str::from_utf8(tag).ok()?.parse().ok()?;
+    let digest = hex(digits)?;
+    if digest.len() != algorithm.output_size() || name.is_empty() {
+        return None;
+    }
+    let name = if escaped {
+        unescape(name)?
+    } else {
+        name.to_vec()
+    };
+
+    // The BSD style is always made in binary mode
+    Some(SumLine {
+        algorithm: Some(algorithm),
+        digest,
+        name,
+        binary: true,
+    })
+}
+
+// parse_line function parse a line of either style
+// A BSD line never start with hex digits and a space, so the order does not matter.
+pub fn parse_line(line: &[u8]) -> Option<SumLine> {
+    parse_bsd(line).or_else(|| parse_gnu(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sums::{format_bsd, format_gnu, parse_bsd, parse_gnu, parse_line, SumLine};
+    use crate::Algorithm;
+
+    #[test]
+    fn test_format() {
+        let digest = crate::hex::decode("8b1a9953c4611296a827abf8c47804d7").unwrap();
+        assert_eq!(
+            b"8b1a9953c4611296a827abf8c47804d7  hello.txt".as_slice(),
+            format_gnu(&digest, b"hello.txt", false)
+        );
+        assert_eq!(
+            b"8b1a9953c4611296a827abf8c47804d7 *hello.txt".as_slice(),
+            format_gnu(&digest, b"hello.txt", true)
+        );
+        // the same output as md5sum for a filename with a backslash and a newline
+        assert_eq!(
+            b"\\8b1a9953c4611296a827abf8c47804d7  we\\\\ird\\nname".as_slice(),
+            format_gnu(&digest, b"we\\ird\nname", false)
+        );
+        // a name which is not UTF-8 is written as it is
+        assert_eq!(
+            b"8b1a9953c4611296a827abf8c47804d7  bad\xff".as_slice(),
+            format_gnu(&digest, b"bad\xff", false)
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let digest = crate::hex::decode("8b1a9953c4611296a827abf8c47804d7").unwrap();
+        assert_eq!(
+            Some(SumLine {
+                algorithm: None,
+                digest: digest.clone(),
+                name: b"hello.txt".to_vec(),
+                binary: false,
+            }),
+            parse_gnu(b"8b1a9953c4611296a827abf8c47804d7  hello.txt")
+        );
+        assert_eq!(
+            Some(SumLine {
+                algorithm: None,
+                digest: digest.clone(),
+                name: b"we\\ird\nname".to_vec(),
+                binary: true,
+            }),
+            parse_gnu(b"\\8B1A9953C4611296A827ABF8C47804D7 *we\\\\ird\\nname")
+        );
+        // a name with spaces is kept as it is
+        assert_eq!(
+            b"a  b".as_slice(),
+            parse_gnu(b"8b1a9953c4611296a827abf8c47804d7  a  b")
+                .unwrap()
+                .name
+        );
+        // and so is a name which is not UTF-8
+        assert_eq!(
+            b"bad\xff".as_slice(),
+            parse_gnu(b"8b1a9953c4611296a827abf8c47804d7  bad\xff")
+                .unwrap()
+                .name
+        );
+
+        assert_eq!(None, parse_gnu(b""));
+        assert_eq!(None, parse_gnu(b"8b1a9953c4611296a827abf8c47804d7"));
+        assert_eq!(None, parse_gnu(b"8b1a9953c4611296a827abf8c47804d  odd.txt"));
+        assert_eq!(
+            None,
+            parse_gnu(b"\\8b1a9953c4611296a827abf8c47804d7  bad\\escape")
+        );
+    }
+
+    #[test]
+    fn test_format_bsd() {
+        let digest = crate::hex::decode("8b1a9953c4611296a827abf8c47804d7").unwrap();
+        assert_eq!(
+            b"MD5 (hello.txt) = 8b1a9953c4611296a827abf8c47804d7".as_slice(),
+            format_bsd(Algorithm::Md5, &digest, b"hello.txt")
+        );
+        // the same output as md5sum --tag for a filename with a backslash and a newline
+        assert_eq!(
+            b"\\MD5 (we\\\\ird\\nname) = 8b1a9953c4611296a827abf8c47804d7".as_slice(),
+            format_bsd(Algorithm::Md5, &digest, b"we\\ird\nname")
+        );
+
+        // the hash is the same string as the Display output of Sha1
+        let mut hashtest = <crate::sha1::Sha1 as crate::sha1::Sha1Digest>::new();
+        crate::sha1::Sha1Digest::update(&mut hashtest, b"Hello");
+        let hashtest = crate::sha1::Sha1Digest::finalize(hashtest);
+        assert_eq!(
+            format!("SHA1 (hello.txt) = {}", hashtest).into_bytes(),
+            format_bsd(Algorithm::Sha1, &hashtest.digest(), b"hello.txt")
+        );
+    }
+
+    #[test]
+    fn test_parse_bsd() {
+        let digest = crate::hex::decode("f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0").unwrap();
+        assert_eq!(
+            Some(SumLine {
+                algorithm: Some(Algorithm::Sha1),
+                digest: digest.clone(),
+                name: b"file (1).txt".to_vec(),
+                binary: true,
+            }),
+            parse_bsd(b"SHA1 (file (1).txt) = f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0")
+        );
+        assert_eq!(
+            b"we\\ird\nname".as_slice(),
+            parse_bsd(b"\\MD5 (we\\\\ird\\nname) = 8b1a9953c4611296a827abf8c47804d7")
+                .unwrap()
+                .name
+        );
+
+        // the length of the hash must match the algorithm
+        assert_eq!(
+            None,
+            parse_bsd(b"MD5 (hello.txt) = f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0")
+        );
+        assert_eq!(
+            None,
+            parse_bsd(b"SHA3 (hello.txt) = 8b1a9953c4611296a827abf8c47804d7")
+        );
+        assert_eq!(
+            None,
+            parse_bsd(b"MD5 hello.txt = 8b1a9953c4611296a827abf8c47804d7")
+        );
+    }
+
+    #[test]
+    fn test_parse_mixed() {
+        let lines: [&[u8]; 3] = [
+            b"MD5 (a.txt) = 8b1a9953c4611296a827abf8c47804d7",
+            b"SHA1 (b.txt) = f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0",
+            b"f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0  c.txt",
+        ];
+        let parsed: Vec<SumLine> = lines.iter().map(|line| parse_line(line).unwrap()).collect();
+        assert_eq!(Some(Algorithm::Md5), parsed[0].algorithm);
+        assert_eq!(Some(Algorithm::Sha1), parsed[1].algorithm);
+        assert_eq!(None, parsed[2].algorithm);
+        assert_eq!(b"c.txt".as_slice(), parsed[2].name);
+    }
+}
@@ -0,0 +1,134 @@
+extern crate hashes;
+use hashes::sha1;
+use hashes::sha1::Sha1Digest;
+use hashes::sha1dc::Sha1DC;
+use hashes::Algorithm;
+
+mod common;
+use common::cavp;
+
+// tests/data/cavp has records of the NIST SHAVS response files of SHA-1, not the complete files:
+// 45 of ShortMsg (0 to 45 bytes), the first 2 of LongMsg and the first 3 of BitShortMsg.
+// tests/data/generated has more vectors in the same layout, computed with Python and not from NIST:
+// every length of 0 to 64 bytes (ShortMsg), 64 long messages up to 6.4 KB (LongMsg),
+// every length of 0 to 512 bits (BitShortMsg), for update_bits, and the Monte Carlo test from the NIST seed,
+// whose messages are the previous digests. tests/data/README.md tell how each file was made.
+
+fn sha1(message: &[u8]) -> Vec<u8> {
+    let mut hashtest = sha1::Sha1::new();
+    hashtest.update(message);
+    hashtest.finalize().digest().to_vec()
+}
+
+// sha1_chunks function feed the message in pieces of 1, 2, 3, ... bytes, so the block buffer is hit everywhere
+fn sha1_chunks(message: &[u8]) -> Vec<u8> {
+    let mut hashtest = sha1::Sha1::new();
+    let mut rest = message;
+    let mut size = 1;
+    while !rest.is_empty() {
+        let (chunk, tail) = rest.split_at(size.min(rest.len()));
+        hashtest.update(chunk);
+        rest = tail;
+        size = size % 67 + 1;
+    }
+    hashtest.finalize().digest().to_vec()
+}
+
+fn sha1dc(message: &[u8]) -> Vec<u8> {
+    let mut hashtest = Sha1DC::new();
+    hashtest.update(message);
+    let result = hashtest.finalize();
+    assert!(!result.collision_detected());
+    result.digest().to_vec()
+}
+
+// bits function return the bits start..start + len of a message, moved to the highest bits of the first byte
+fn bits(message: &[u8], start: usize, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len.div_ceil(8)];
+    for i in 0..len {
+        let bit = message[(start + i) / 8] >> (7 - (start + i) % 8) & 1;
+        bytes[i / 8] |= bit << (7 - i % 8);
+    }
+    bytes
+}
+
+#[test]
+fn cavp_parse() {
+    let text = "#  CAVS 11.0\n\n[L = 20]\n\nLen = 0\nMsg = 00\nMD = da39a3ee5e6b4b0d3255bfef95601890afd80709\n\nLen = 8\nMsg = 36\nMD = c1dfd96eea8cc2b62785275bca38ac261256e278\n";
+    let vectors = cavp::vectors(text);
+    assert_eq!(2, vectors.len());
+    assert!(vectors[0].msg.is_empty());
+    assert_eq!(vec![0x36], vectors[1].msg);
+    assert_eq!(vectors[1].md, sha1(&vectors[1].msg));
+}
+
+#[test]
+fn cavp_sha1_short_msg() {
+    assert_eq!(45, cavp::check("cavp/SHA1ShortMsg.rsp", sha1));
+    assert_eq!(45, cavp::check("cavp/SHA1ShortMsg.rsp", sha1_chunks));
+    assert_eq!(65, cavp::check("generated/SHA1ShortMsg.rsp", sha1));
+    assert_eq!(65, cavp::check("generated/SHA1ShortMsg.rsp", sha1_chunks));
+}
+
+#[test]
+fn cavp_sha1_long_msg() {
+    assert_eq!(2, cavp::check("cavp/SHA1LongMsg.rsp", sha1));
+    assert_eq!(2, cavp::check("cavp/SHA1LongMsg.rsp", sha1_chunks));
+    assert_eq!(64, cavp::check("generated/SHA1LongMsg.rsp", sha1));
+    assert_eq!(64, cavp::check("generated/SHA1LongMsg.rsp", sha1_chunks));
+}
+
+#[test]
+fn cavp_sha1_monte() {
+    assert_eq!(100, cavp::check_monte("generated/SHA1Monte.rsp", sha1));
+}
+
+// update_bits function hash len bits of msg with update_bits
+fn update_bits(msg: &[u8], len: usize) -> Vec<u8> {
+    let mut hashtest = sha1::Sha1::new();
+    hashtest.update_bits(msg, len);
+    hashtest.finalize().digest().to_vec()
+}
+
+#[test]
+fn cavp_sha1_bit_short_msg() {
+    assert_eq!(3, cavp::check_bits("cavp/SHA1BitShortMsg.rsp", update_bits));
+    assert_eq!(
+        513,
+        cavp::check_bits("generated/SHA1BitShortMsg.rsp", update_bits)
+    );
+    // the same bits fed in pieces of 1 to 11 bits, so they are shifted through every offset
+    cavp::check_bits("generated/SHA1BitShortMsg.rsp", |msg, len| {
+        let mut hashtest = sha1::Sha1::new();
+        let (mut start, mut size) = (0, 1);
+        while start < len {
+            let take = size.min(len - start);
+            let piece = bits(msg, start, take);
+            hashtest.update_bits(&piece, take);
+            start += take;
+            size = size % 11 + 1;
+        }
+        hashtest.finalize().digest().to_vec()
+    });
+}
+
+#[test]
+fn cavp_sha1dc() {
+    // the collision detection must not change the digest of a normal message
+    assert_eq!(45, cavp::check("cavp/SHA1ShortMsg.rsp", sha1dc));
+    assert_eq!(2, cavp::check("cavp/SHA1LongMsg.rsp", sha1dc));
+    assert_eq!(65, cavp::check("generated/SHA1ShortMsg.rsp", sha1dc));
+    assert_eq!(64, cavp::check("generated/SHA1LongMsg.rsp", sha1dc));
+}
+
+#[test]
+fn cavp_algorithm() {
+    // the same vectors through the runtime selection of the algorithm
+    let hash = |message: &[u8]| {
+        let mut hashtest = Algorithm::Sha1.new_digest();
+        hashtest.update(message);
+        hashtest.finalize_boxed()
+    };
+    assert_eq!(45, cavp::check("cavp/SHA1ShortMsg.rsp", hash));
+    assert_eq!(100, cavp::check_monte("generated/SHA1Monte.rsp", hash));
+}
@@ -0,0 +1,162 @@
+// tests/common/cavp.rs
+#![allow(dead_code)]
+use std::path::Path;
+
+// Parser of the NIST CAVP response files of SHAVS (SHA1ShortMsg.rsp, SHA1LongMsg.rsp, SHA1Monte.rsp)
+// A file is "# comment" lines, "[L = 20]" sections and "Name = value" fields, a blank line ends a record.
+// ShortMsg and LongMsg records are Len (in bits), Msg and MD, Monte has one Seed and then COUNT and MD.
+// The bit oriented files have every Len, the last byte of Msg then has the bits from the highest one.
+
+// Vector is one ShortMsg or LongMsg record
+pub struct Vector {
+    pub len: usize,
+    pub msg: Vec<u8>,
+    pub md: Vec<u8>,
+}
+
+// Monte is the seed and the 100 checkpoints of a Monte Carlo file
+pub struct Monte {
+    pub seed: Vec<u8>,
+    pub checkpoints: Vec<(usize, Vec<u8>)>,
+}
+
+pub fn hex(value: &str) -> Vec<u8> {
+    hashes::hex::decode(value).unwrap_or_else(|| panic!("bad hex {}", value))
+}
+
+// read function return the contents of a file of tests/data, name is like "cavp/SHA1ShortMsg.rsp"
+pub fn read(name: &str) -> String {
+    std::fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(name),
+    )
+    .unwrap()
+}
+
+// fields function split a file into records of (name, value), the comments and sections are skipped
+pub fn fields(text: &str) -> Vec<Vec<(String, String)>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !record.is_empty() {
+                records.push(std::mem::take(&mut record));
+            }
+        } else if !line.starts_with('#') && !line.starts_with('[') {
+            let (name, value) = line
+                .split_once('=')
+                .unwrap_or_else(|| panic!("bad line {}", line));
+            record.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    if !record.is_empty() {
+        records.push(record);
+    }
+    records
+}
+
+fn field<'a>(record: &'a [(String, String)], name: &str) -> &'a str {
+    record
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+        .unwrap_or_else(|| panic!("no {} in record", name))
+}
+
+// vectors function parse a ShortMsg or LongMsg file
+// The message of Len = 0 is written "00", only the bytes which have one of the Len bits are kept.
+pub fn vectors(text: &str) -> Vec<Vector> {
+    fields(text)
+        .iter()
+        .map(|record| {
+            let len: usize = field(record, "Len").parse().unwrap();
+            let mut msg = hex(field(record, "Msg"));
+            msg.truncate(len.div_ceil(8));
+            Vector {
+                len,
+                msg,
+                md: hex(field(record, "MD")),
+            }
+        })
+        .collect()
+}
+
+// monte function parse a Monte Carlo file
+pub fn monte(text: &str) -> Monte {
+    let records = fields(text);
+    let seed = hex(field(&records[0], "Seed"));
+    let checkpoints = records[1..]
+        .iter()
+        .map(|record| {
+            (
+                field(record, "COUNT").parse().unwrap(),
+                hex(field(record, "MD")),
+            )
+        })
+        .collect();
+    Monte { seed, checkpoints }
+}
+
+// monte_carlo function run the SHAVS Monte Carlo procedure of SHA-1 and return the 100 checkpoints
+//   MD0 = MD1 = MD2 = Seed
+//   for i in 3..1003: Mi = MD(i-3) || MD(i-2) || MD(i-1), MDi = SHA(Mi)
+//   the checkpoint is MD1002, and it is the seed of the next one
+pub fn monte_carlo<F: Fn(&[u8]) -> Vec<u8>>(hash: F, seed: &[u8], count: usize) -> Vec<Vec<u8>> {
+    let mut seed = seed.to_vec();
+    let mut checkpoints = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut md = [seed.clone(), seed.clone(), seed.clone()];
+        for _ in 3..1003 {
+            let message = [md[0].as_slice(), &md[1], &md[2]].concat();
+            md = [md[1].clone(), md[2].clone(), hash(&message)];
+        }
+        seed = md[2].clone();
+        checkpoints.push(seed.clone());
+    }
+    checkpoints
+}
+
+// check function run the vectors of a file against a hash and return the number of vectors
+pub fn check<F: Fn(&[u8]) -> Vec<u8>>(name: &str, hash: F) -> usize {
+    check_bits(name, |msg, len| {
+        assert_eq!(
+            0,
+            len % 8,
+            "{} has bit oriented vectors, see check_bits",
+            name
+        );
+        hash(msg)
+    })
+}
+
+// check_bits function run the vectors of a file against a hash of the first len bits of a message
+pub fn check_bits<F: Fn(&[u8], usize) -> Vec<u8>>(name: &str, hash: F) -> usize {
+    let vectors = vectors(&read(name));
+    for vector in &vectors {
+        assert_eq!(
+            hashes::hex::encode(&vector.md),
+            hashes::hex::encode(&hash(&vector.msg, vector.len)),
+            "{} Len = {}",
+            name,
+            vector.len
+        );
+    }
+    vectors.len()
+}
+
+// check_monte function run the Monte Carlo file against a hash
+pub fn check_monte<F: Fn(&[u8]) -> Vec<u8>>(name: &str, hash: F) -> usize {
+    let monte = monte(&read(name));
+    let checkpoints = monte_carlo(hash, &monte.seed, monte.checkpoints.len());
+    for ((count, expected), md) in monte.checkpoints.iter().zip(checkpoints.iter()) {
+        assert_eq!(
+            hashes::hex::encode(expected),
+            hashes::hex::encode(md),
+            "{} COUNT = {}",
+            name,
+            count
+        );
+    }
+    checkpoints.len()
+}
@@ -3,3 +3,7 @@
 pub fn setup() {
     // Write common code in here
 }
+
+pub mod cavp;
+pub mod json;
+pub mod wycheproof;
@@ -0,0 +1,213 @@
+// tests/common/wycheproof.rs
+#![allow(dead_code)]
+use super::json::{self, Value};
+use hashes::hmac::{hkdf, pbkdf2, Hmac};
+use hashes::sha1::Sha1;
+use std::path::{Path, PathBuf};
+
+// Harness of the Wycheproof test vector files (github.com/C2SP/wycheproof)
+// A file has a schema ("mac_test_schema.json", "hkdf_test_schema.json", ...), the notes which explain
+// every flag, and test groups. The members of a group (keySize, tagSize, ...) apply to all its tests,
+// and every test has a tcId, a comment, flags and a result:
+//   valid: the operation must succeed and give the expected output
+//   invalid: the operation must fail (a modified tag, a size out of range, ...)
+//   acceptable: both are right, it depends on the policy of the library, which is told by the flags
+// The schema tell the fields of the tests, so run check it before the tests are given to the check.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Valid,
+    Invalid,
+    Acceptable,
+}
+
+// Case is one test with its group
+pub struct Case<'a> {
+    pub tc_id: u64,
+    pub comment: &'a str,
+    pub result: Expected,
+    pub flags: Vec<&'a str>,
+    pub group: &'a Value,
+    pub test: &'a Value,
+}
+
+impl Case<'_> {
+    // hex method return a hex field of the test as bytes
+    pub fn hex(&self, name: &str) -> Vec<u8> {
+        let value = self.field(name).as_str().unwrap();
+        hashes::hex::decode(value)
+            .unwrap_or_else(|| panic!("tcId {}: bad hex {}", self.tc_id, name))
+    }
+
+    // number method return a number field of the test, or of the group when the test has none
+    pub fn number(&self, name: &str) -> u64 {
+        self.field(name)
+            .as_u64()
+            .unwrap_or_else(|| panic!("tcId {}: {} is not a number", self.tc_id, name))
+    }
+
+    fn field(&self, name: &str) -> &Value {
+        self.test
+            .get(name)
+            .or_else(|| self.group.get(name))
+            .unwrap_or_else(|| panic!("tcId {}: no field {}", self.tc_id, name))
+    }
+}
+
+// Summary count the tests of a file by result
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub valid: usize,
+    pub invalid: usize,
+    pub acceptable_accepted: usize,
+    pub acceptable_rejected: usize,
+}
+
+impl Summary {
+    pub fn total(&self) -> usize {
+        self.valid + self.invalid + self.acceptable_accepted + self.acceptable_rejected
+    }
+}
+
+fn path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(name)
+}
+
+// present function tell if a file of tests/data is there, name is like "wycheproof/hmac_sha1_test.json"
+pub fn present(name: &str) -> bool {
+    path(name).is_file()
+}
+
+// load function read a file of tests/data
+pub fn load(name: &str) -> Value {
+    let text = std::fs::read_to_string(path(name)).unwrap();
+    json::parse(&text).unwrap_or_else(|e| panic!("{}: {}", name, e))
+}
+
+// run function give every test of a file to check, which return true when the crate accepted it
+// An acceptable test whose flags are all in accept must be accepted, other acceptable tests may go
+// either way and are only counted. A flag which is not explained in the notes is an error of the file.
+// All the failures are collected, the panic list them together.
+pub fn run<F: Fn(&Case) -> bool>(name: &str, schema: &str, accept: &[&str], check: F) -> Summary {
+    let file = load(name);
+    assert_eq!(
+        Some(schema),
+        file.get("schema").and_then(Value::as_str),
+        "{}: schema",
+        name
+    );
+    let notes = file.get("notes").and_then(Value::as_object).unwrap_or(&[]);
+
+    let mut summary = Summary::default();
+    let mut failures = Vec::new();
+    for group in file.get("testGroups").and_then(Value::as_array).unwrap() {
+        for test in group.get("tests").and_then(Value::as_array).unwrap() {
+            let case = Case {
+                tc_id: test.get("tcId").and_then(Value::as_u64).unwrap(),
+                comment: test.get("comment").and_then(Value::as_str).unwrap_or(""),
+                result: match test.get("result").and_then(Value::as_str) {
+                    Some("valid") => Expected::Valid,
+                    Some("invalid") => Expected::Invalid,
+                    Some("acceptable") => Expected::Acceptable,
+                    other => panic!("{}: unknown result {:?}", name, other),
+                },
+                flags: test
+                    .get("flags")
+                    .and_then(Value::as_array)
+                    .unwrap_or(&[])
+                    .iter()
+                    .map(|flag| flag.as_str().unwrap())
+                    .collect(),
+                group,
+                test,
+            };
+            for flag in &case.flags {
+                if !notes.iter().any(|(note, _)| note == flag) {
+                    failures.push(format!("tcId {}: flag {} has no note", case.tc_id, flag));
+                }
+            }
+
+            let accepted = check(&case);
+            let right = match case.result {
+                Expected::Valid => {
+                    summary.valid += 1;
+                    accepted
+                }
+                Expected::Invalid => {
+                    summary.invalid += 1;
+                    !accepted
+                }
+                Expected::Acceptable => {
+                    if accepted {
+                        summary.acceptable_accepted += 1;
+                    } else {
+                        summary.acceptable_rejected += 1;
+                    }
+                    accepted || !case.flags.iter().all(|flag| accept.contains(flag))
+                }
+            };
+            if !right {
+                failures.push(format!(
+                    "tcId {} ({:?} {:?}) {}: {}",
+                    case.tc_id,
+                    case.result,
+                    case.flags,
+                    case.comment,
+                    if accepted { "accepted" } else { "rejected" }
+                ));
+            }
+        }
+    }
+    if let Some(count) = file.get("numberOfTests").and_then(Value::as_u64) {
+        assert_eq!(count as usize, summary.total(), "{}: numberOfTests", name);
+    }
+    assert!(
+        failures.is_empty(),
+        "{}: {} failures\n{}",
+        name,
+        failures.len(),
+        failures.join("\n")
+    );
+    summary
+}
+
+// The checks of the crate, for the files of Wycheproof and the generated ones of the same format
+
+// hmac_sha1 function verify the tag of a MacTest, a full tag with verify and a truncated one with verify_truncated
+pub fn hmac_sha1(case: &Case) -> bool {
+    let tag = case.hex("tag");
+    if tag.len() * 8 != case.number("tagSize") as usize {
+        return false;
+    }
+    let mut mac = Hmac::<Sha1>::new(&case.hex("key"));
+    mac.update(&case.hex("msg"));
+    if tag.len() == 20 {
+        mac.verify(&tag)
+    } else {
+        mac.verify_truncated(&tag)
+    }
+}
+
+// hkdf_sha1 function derive the okm of a HkdfTest
+pub fn hkdf_sha1(case: &Case) -> bool {
+    let okm = hkdf::<Sha1>(
+        &case.hex("salt"),
+        &case.hex("ikm"),
+        &case.hex("info"),
+        case.number("size") as usize,
+    );
+    okm == Some(case.hex("okm"))
+}
+
+// pbkdf2_hmacsha1 function derive the dk of a PbkdfTest
+pub fn pbkdf2_hmacsha1(case: &Case) -> bool {
+    let dk = pbkdf2::<Sha1>(
+        &case.hex("password"),
+        &case.hex("salt"),
+        case.number("iterationCount") as u32,
+        case.number("dkLen") as usize,
+    );
+    dk == Some(case.hex("dk"))
+}
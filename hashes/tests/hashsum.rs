@@ -0,0 +1,409 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+mod common;
+
+// temp_dir function make an empty directory for one test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hashsum-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn hashsum(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_hashsum"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn hashsum_print() {
+    let dir = temp_dir("print");
+    fs::write(dir.join("hello.txt"), b"Hello").unwrap();
+
+    let output = hashsum(&dir, &["-a", "md5", "hello.txt"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "8b1a9953c4611296a827abf8c47804d7  hello.txt\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    let output = hashsum(&dir, &["--binary", "hello.txt"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0 *hello.txt\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    // a missing file is reported, the other files are still printed
+    let output = hashsum(&dir, &["-a", "md5", "missing.txt", "hello.txt"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "8b1a9953c4611296a827abf8c47804d7  hello.txt\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(
+        "hashsum: missing.txt: No such file or directory\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hashsum_escape() {
+    let dir = temp_dir("escape");
+    fs::write(dir.join("we\\ird\nname"), b"Hello").unwrap();
+
+    let output = hashsum(&dir, &["-a", "md5", "we\\ird\nname"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_eq!(
+        "\\8b1a9953c4611296a827abf8c47804d7  we\\\\ird\\nname\n",
+        stdout
+    );
+
+    // the escaped line can be checked again
+    fs::write(dir.join("MD5SUMS"), stdout).unwrap();
+    let output = hashsum(&dir, &["-a", "md5", "-c", "MD5SUMS"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "\\we\\\\ird\\nname: OK\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// A name which is not UTF-8 is printed and checked as its bytes, like coreutils
+#[cfg(unix)]
+#[test]
+fn hashsum_non_utf8_name() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = temp_dir("non-utf8");
+    let name = OsStr::from_bytes(b"bad\xff.txt");
+    fs::write(dir.join(name), b"Hello").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_hashsum"))
+        .current_dir(&dir)
+        .args([OsStr::new("-a"), OsStr::new("md5"), name])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        b"8b1a9953c4611296a827abf8c47804d7  bad\xff.txt\n".as_slice(),
+        output.stdout
+    );
+
+    fs::write(dir.join("sums.md5"), &output.stdout).unwrap();
+    let output = hashsum(&dir, &["-a", "md5", "-c", "sums.md5"]);
+    assert!(output.status.success());
+    assert_eq!(b"bad\xff.txt: OK\n".as_slice(), output.stdout);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hashsum_check() {
+    let dir = temp_dir("check");
+    fs::write(dir.join("a.txt"), b"Hello").unwrap();
+    fs::write(dir.join("b.txt"), b"World").unwrap();
+    fs::write(
+        dir.join("SHA1SUMS"),
+        "# comment\n\
+         f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0  a.txt\n\
+         0000000000000000000000000000000000000000 *b.txt\n\
+         f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0  c.txt\n\
+         not a checksum line\n",
+    )
+    .unwrap();
+
+    let output = hashsum(&dir, &["-c", "SHA1SUMS"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "a.txt: OK\nb.txt: FAILED\nc.txt: FAILED open or read\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(
+        "hashsum: c.txt: No such file or directory\n\
+         hashsum: WARNING: 1 line is improperly formatted\n\
+         hashsum: WARNING: 1 listed file could not be read\n\
+         hashsum: WARNING: 1 computed checksum did NOT match\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // --status print nothing, the exit code tell the result
+    let output = hashsum(&dir, &["-c", "--status", "SHA1SUMS"]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hashsum_check_strict() {
+    let dir = temp_dir("strict");
+    fs::write(dir.join("a.txt"), b"Hello").unwrap();
+    fs::write(
+        dir.join("SHA1SUMS"),
+        "f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0  a.txt\n\
+         f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0  missing.txt\n\
+         8b1a9953c4611296a827abf8c47804d7  a.txt\n",
+    )
+    .unwrap();
+
+    // the md5 line has the wrong length for sha1, so it is only a warning without --strict
+    let output = hashsum(&dir, &["-c", "--quiet", "--ignore-missing", "SHA1SUMS"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let output = hashsum(
+        &dir,
+        &["-c", "--quiet", "--ignore-missing", "--strict", "SHA1SUMS"],
+    );
+    assert_eq!(Some(1), output.status.code());
+
+    let output = hashsum(&dir, &["-c", "-w", "--ignore-missing", "SHA1SUMS"]);
+    assert_eq!(
+        "hashsum: SHA1SUMS: 3: improperly formatted SHA1 checksum line\n\
+         hashsum: WARNING: 1 line is improperly formatted\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // no properly formatted line is always an error
+    fs::write(dir.join("EMPTY"), "\n").unwrap();
+    let output = hashsum(&dir, &["-c", "EMPTY"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "hashsum: EMPTY: no properly formatted checksum lines found\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hashsum_tag() {
+    let dir = temp_dir("tag");
+    fs::write(dir.join("a.txt"), b"Hello").unwrap();
+    fs::write(dir.join("b.txt"), b"World").unwrap();
+
+    let output = hashsum(&dir, &["-a", "md5", "--tag", "a.txt"]);
+    assert!(output.status.success());
+    let md5_line = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_eq!("MD5 (a.txt) = 8b1a9953c4611296a827abf8c47804d7\n", md5_line);
+
+    let output = hashsum(&dir, &["--tag", "b.txt"]);
+    assert!(output.status.success());
+    let sha1_line = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_eq!(
+        "SHA1 (b.txt) = 70c07ec18ef89c5309bbb0937f3a6342411e1fdd\n",
+        sha1_line
+    );
+
+    // a mixed file is checked with the algorithm of each line, whatever -a says
+    fs::write(
+        dir.join("CHECKSUMS"),
+        format!(
+            "{}{}SHA1 (a.txt) = 70c07ec18ef89c5309bbb0937f3a6342411e1fdd\n",
+            md5_line, sha1_line
+        ),
+    )
+    .unwrap();
+    let output = hashsum(&dir, &["-a", "xxh64", "-c", "CHECKSUMS"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "a.txt: OK\nb.txt: OK\na.txt: FAILED\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hashsum_manifest_audit() {
+    let dir = temp_dir("manifest");
+    let tree = dir.join("tree");
+    fs::create_dir_all(tree.join("sub")).unwrap();
+    fs::write(tree.join("a.txt"), b"Hello").unwrap();
+    fs::write(tree.join("sub/b.txt"), b"World").unwrap();
+    fs::write(tree.join("c.txt"), b"same").unwrap();
+    fs::write(tree.join("d.txt"), b"gone").unwrap();
+
+    let output = hashsum(&dir, &["manifest", "tree"]);
+    assert!(output.status.success());
+    let manifest = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_eq!(
+        "%%%% HASHDEEP-1.0\n\
+         %%%% size,md5,sha1,filename\n\
+         5,8b1a9953c4611296a827abf8c47804d7,f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0,a.txt\n\
+         4,51037a4a37730f52c8732586d3aaa316,ff3390557335ba88d37755e41514beb03bc499ec,c.txt\n\
+         4,50c1f58be7f5e47e0f53d64c094783c2,a6dfdeaa3a44a4c52d44284847d7160892b4017e,d.txt\n\
+         5,f5a7924e621e84c9280a9a27e1bcb7f6,70c07ec18ef89c5309bbb0937f3a6342411e1fdd,sub/b.txt\n",
+        manifest
+    );
+    fs::write(dir.join("MANIFEST"), manifest).unwrap();
+
+    // the untouched tree pass the audit
+    let output = hashsum(&dir, &["audit", "-k", "MANIFEST", "tree"]);
+    assert!(output.status.success());
+
+    // move, add, change and remove one file each, and copy one, a copy is not moved
+    fs::rename(tree.join("sub/b.txt"), tree.join("b.txt")).unwrap();
+    fs::write(tree.join("e.txt"), b"new file").unwrap();
+    fs::copy(tree.join("a.txt"), tree.join("f.txt")).unwrap();
+    fs::write(tree.join("c.txt"), b"changed").unwrap();
+    fs::remove_file(tree.join("d.txt")).unwrap();
+
+    let output = hashsum(&dir, &["audit", "-k", "MANIFEST", "tree"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "moved: sub/b.txt -> b.txt\n\
+         new: e.txt\n\
+         new: f.txt\n\
+         changed: c.txt\n\
+         missing: d.txt\n\
+         Files matched: 1\n\
+         Files moved: 1\n\
+         New files found: 2\n\
+         Files changed: 1\n\
+         Known files not found: 1\n\
+         Audit failed\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// A file which can not be read is reported and the other files are still in the manifest
+#[cfg(unix)]
+#[test]
+fn hashsum_manifest_unreadable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = temp_dir("unreadable");
+    let tree = dir.join("tree");
+    fs::create_dir_all(&tree).unwrap();
+    fs::write(tree.join("a.txt"), b"Hello").unwrap();
+    fs::write(tree.join("b.txt"), b"secret").unwrap();
+    fs::write(tree.join("c.txt"), b"World").unwrap();
+    let output = hashsum(&dir, &["manifest", "tree"]);
+    fs::write(dir.join("MANIFEST"), &output.stdout).unwrap();
+
+    fs::set_permissions(tree.join("b.txt"), fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::File::open(tree.join("b.txt")).is_ok() {
+        // root can read it anyway
+        println!("skipped: the file is still readable");
+        fs::remove_dir_all(&dir).unwrap();
+        return;
+    }
+
+    let output = hashsum(&dir, &["manifest", "tree"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "%%%% HASHDEEP-1.0\n\
+         %%%% size,md5,sha1,filename\n\
+         5,8b1a9953c4611296a827abf8c47804d7,f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0,a.txt\n\
+         5,f5a7924e621e84c9280a9a27e1bcb7f6,70c07ec18ef89c5309bbb0937f3a6342411e1fdd,c.txt\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert_eq!(
+        "hashsum: tree/b.txt: Permission denied\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // the unreadable file fail the audit, but it is not missing
+    let output = hashsum(&dir, &["audit", "-k", "MANIFEST", "tree"]);
+    assert_eq!(Some(1), output.status.code());
+    assert_eq!(
+        "Files matched: 2\n\
+         Files moved: 0\n\
+         New files found: 0\n\
+         Files changed: 0\n\
+         Known files not found: 0\n\
+         Files not read: 1\n\
+         Audit failed\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    fs::set_permissions(tree.join("b.txt"), fs::Permissions::from_mode(0o644)).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hashsum_broken_pipe() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+
+    // "hashsum a.txt a.txt ... | head -1": more lines than the pipe can hold, the reader stop after one
+    let dir = temp_dir("pipe");
+    fs::write(dir.join("a.txt"), b"Hello").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hashsum"))
+        .current_dir(&dir)
+        .args(std::iter::repeat_n("a.txt", 5000))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut first = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut first)
+        .unwrap();
+    assert_eq!("f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0  a.txt\n", first);
+
+    // the closed pipe end the command quietly, no panic message
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!("", String::from_utf8_lossy(&output.stderr));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hashsum_options() {
+    let dir = temp_dir("options");
+    fs::write(dir.join("--help"), b"Hello").unwrap();
+
+    // after "--" a file named --help is hashed, before it --help print the usage
+    let output = hashsum(&dir, &["--", "--help"]);
+    assert!(output.status.success());
+    assert_eq!(
+        "f7ff9e8b7bb2e09b70935a5d785e0cc5d9d0abf0  --help\n",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    let output = hashsum(&dir, &["--help", "--", "--help"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Usage"));
+
+    // the options of --check are an error without it
+    for flag in [
+        "--quiet",
+        "--status",
+        "--strict",
+        "--warn",
+        "-w",
+        "--ignore-missing",
+    ] {
+        let output = hashsum(&dir, &[flag, "--", "--help"]);
+        assert_eq!(Some(1), output.status.code());
+        assert!(output.stdout.is_empty());
+        let name = if flag == "-w" { "--warn" } else { flag };
+        assert_eq!(
+            format!(
+                "hashsum: the {} option is meaningful only when verifying checksums\n\
+                 Try 'hashsum --help' for more information.\n",
+                name
+            ),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,85 @@
+extern crate hashes;
+use hashes::hmac::Hmac;
+use hashes::sha1::Sha1;
+
+mod common;
+use common::wycheproof::{self, Summary};
+
+// tests/data/generated has the edge cases of HMAC, HKDF and PBKDF2 with SHA-1 in the Wycheproof format
+// (RFC vectors, long keys, truncated and modified tags, sizes out of range), they are not from the
+// Wycheproof repository, the expected values were computed with Python hmac and hashlib.
+
+#[test]
+fn hmac_sha1_edge_cases() {
+    let summary = wycheproof::run(
+        "generated/hmac_sha1_test.json",
+        "mac_test_schema.json",
+        &[],
+        wycheproof::hmac_sha1,
+    );
+    println!("{:?}", summary);
+
+    // tags under 80 bits are refused, they are the acceptable tests
+    assert_eq!(
+        Summary {
+            valid: 22,
+            invalid: 24,
+            acceptable_accepted: 0,
+            acceptable_rejected: 11,
+        },
+        summary
+    );
+}
+
+#[test]
+fn hkdf_sha1_edge_cases() {
+    let summary = wycheproof::run(
+        "generated/hkdf_sha1_test.json",
+        "hkdf_test_schema.json",
+        &["EmptyOutput"],
+        wycheproof::hkdf_sha1,
+    );
+    println!("{:?}", summary);
+
+    assert_eq!(13, summary.total());
+    assert_eq!(2, summary.invalid);
+    assert_eq!(1, summary.acceptable_accepted);
+}
+
+#[test]
+fn pbkdf2_hmacsha1_edge_cases() {
+    let summary = wycheproof::run(
+        "generated/pbkdf2_hmacsha1_test.json",
+        "pbkdf2_test_schema.json",
+        &["EmptyOutput"],
+        wycheproof::pbkdf2_hmacsha1,
+    );
+    println!("{:?}", summary);
+
+    assert_eq!(12, summary.total());
+    assert_eq!(1, summary.invalid);
+    assert_eq!(1, summary.acceptable_accepted);
+}
+
+#[test]
+#[should_panic(expected = "2 failures")]
+fn hmac_sha1_catch() {
+    // a MAC which ignore the last byte of a full tag is caught by the two modified last bytes
+    wycheproof::run(
+        "generated/hmac_sha1_test.json",
+        "mac_test_schema.json",
+        &[],
+        |case| {
+            let tag = case.hex("tag");
+            let mut mac = Hmac::<Sha1>::new(&case.hex("key"));
+            mac.update(&case.hex("msg"));
+            if tag.len() * 8 != case.number("tagSize") as usize {
+                false
+            } else if tag.len() == 20 {
+                mac.verify_truncated(&tag[..19])
+            } else {
+                mac.verify_truncated(&tag)
+            }
+        },
+    );
+}
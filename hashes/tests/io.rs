@@ -0,0 +1,46 @@
+extern crate hashes;
+use hashes::io::HashingWriter;
+use hashes::md5;
+use hashes::sha1;
+use hashes::Digest;
+use std::fs::File;
+use std::io::{self, Write};
+
+mod common;
+
+#[test]
+fn io_copy_file() {
+    let path = std::env::temp_dir().join(format!("hashes-io-{}.txt", std::process::id()));
+    let mut file = File::create(&path).unwrap();
+    file.write_all(b"Hello World!").unwrap();
+    drop(file);
+
+    let mut hashtest = sha1::Sha1::new();
+    io::copy(&mut File::open(&path).unwrap(), &mut hashtest).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let result = hashtest
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    println!("{}", result);
+
+    assert_eq!("2ef7bde608ce5404e97d5f042f95f89f1c232871", result);
+}
+
+#[test]
+fn io_hash_while_upload() {
+    // the uploaded bytes and the digest are produced in one pass
+    let mut upload = HashingWriter::<_, md5::MD5>::new(Vec::new());
+    io::copy(&mut &b"Hello"[..], &mut upload).unwrap();
+    let (sent, digest) = upload.finalize();
+
+    assert_eq!(b"Hello", sent.as_slice());
+    assert_eq!(
+        "8b1a9953c4611296a827abf8c47804d7",
+        digest
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    );
+}
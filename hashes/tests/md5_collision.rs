@@ -0,0 +1,31 @@
+extern crate hashes;
+use hashes::attacks::md5_collision::collide;
+use hashes::md5::{MD5Digest, MD5};
+
+mod common;
+
+fn md5(message: &[u8]) -> String {
+    let mut hashtest = MD5::new();
+    hashtest.update(message);
+    format!("{}", hashtest.finalize())
+}
+
+// An average seed takes about two minutes on one core with a release build,
+// seed 374 is one of the quick ones for this prefix, so the whole search run with the other tests.
+#[test]
+fn md5_collide() {
+    let prefix = b"hashes md5 collision";
+    let (first, second) = collide(prefix, 374);
+    assert_ne!(first, second);
+    assert_eq!(first.len(), second.len());
+    assert!(first.starts_with(prefix));
+    assert!(second.starts_with(prefix));
+    assert_eq!(md5(&first), md5(&second));
+
+    // the same suffix after both messages keep the collision
+    let mut first = first;
+    let mut second = second;
+    first.extend_from_slice(b" and the same suffix");
+    second.extend_from_slice(b" and the same suffix");
+    assert_eq!(md5(&first), md5(&second));
+}
@@ -51,3 +51,23 @@ fn sha1_3block_long() {
 
     assert_eq!("3400932512e8e8fc2b51c9d8784dffbe1495b449", result);
 }
+
+#[test]
+fn sha1_resume() {
+    // the state is written to a file like a resumable upload would do before a restart
+    let path = std::env::temp_dir().join(format!("hashes-state-{}", std::process::id()));
+    let message = "Aa0Aa1Aa2Aa3Aa4Aa5Aa6Aa7Aa8Aa9Ab0Ab1Ab2Ab3Ab4Ab5Ab6Ab7Ab8Ab9Ac0Ac1Ac2Ac3Ac4Ac5Ac6Ac7Ac8Ac9Ad0Ad1Ad2Ad3Ad4Ad5Ad6Ad7Ad8Ad9Ae0Ae1Ae2Ae3Ae4Ae5Ae6Ae7Ae8Ae9Af0Af1Af2Af3Af4Af5Af6Af7Af8Af9Ag0Ag1Ag2Ag3".as_bytes();
+    let mut hashtest = sha1::Sha1::new();
+    hashtest.update(&message[..100]);
+    std::fs::write(&path, hashtest.export_state().unwrap()).unwrap();
+
+    let state = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut hashtest = sha1::Sha1::import_state(&state).unwrap();
+    hashtest.update(&message[100..]);
+    let hashtest = hashtest.finalize();
+    let result = format!("{}", hashtest);
+    println!("{}", result);
+
+    assert_eq!("3400932512e8e8fc2b51c9d8784dffbe1495b449", result);
+}
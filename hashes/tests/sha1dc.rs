@@ -0,0 +1,110 @@
+extern crate hashes;
+use hashes::sha1;
+use hashes::sha1::Sha1Digest;
+use hashes::sha1dc::Sha1DC;
+
+mod common;
+
+// tests/data has the public colliding files
+// shattered-1.bin and shattered-2.bin are the first 320 bytes of the SHAttered PDFs (shattered.io),
+// the two near-collision blocks are in there and the rest of the PDFs is the same.
+// sha-mbles-1.bin and sha-mbles-2.bin are the chosen-prefix collision of SHAmbles (sha-mbles.github.io).
+
+fn read(name: &str) -> Vec<u8> {
+    std::fs::read(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(name),
+    )
+    .unwrap()
+}
+
+fn sha1dc(input: &[u8], safe_hash: bool) -> Sha1DC {
+    let mut hashtest = Sha1DC::with_safe_hash(safe_hash);
+    hashtest.update(input);
+    hashtest.finalize()
+}
+
+#[test]
+fn sha1dc_shattered() {
+    let first = read("shattered-1.bin");
+    let second = read("shattered-2.bin");
+    assert_ne!(first, second);
+
+    // plain SHA-1 collide
+    let mut hashtest = sha1::Sha1::new();
+    hashtest.update(&first);
+    assert_eq!(
+        "f92d74e3874587aaf443d1db961d4e26dde13e9c",
+        format!("{}", hashtest.finalize())
+    );
+    let mut hashtest = sha1::Sha1::new();
+    hashtest.update(&second);
+    assert_eq!(
+        "f92d74e3874587aaf443d1db961d4e26dde13e9c",
+        format!("{}", hashtest.finalize())
+    );
+
+    // Sha1DC detect both halves and the safe hashes are different
+    let hashtest = sha1dc(&first, true);
+    let result = format!("{}", hashtest);
+    println!("{}", result);
+    assert!(hashtest.collision_detected());
+    assert_eq!("7117b3cb9225aaf0d8ef1a40e493957b0bf8693d", result);
+
+    let hashtest = sha1dc(&second, true);
+    let result = format!("{}", hashtest);
+    println!("{}", result);
+    assert!(hashtest.collision_detected());
+    assert_eq!("29f38ae9fd98e2931120fa0bf213e024250d3f6a", result);
+}
+
+#[test]
+fn sha1dc_shattered_unsafe() {
+    // without the safe hash the collision is still reported, but the digest is the plain SHA-1
+    for name in ["shattered-1.bin", "shattered-2.bin"] {
+        let hashtest = sha1dc(&read(name), false);
+        assert!(hashtest.collision_detected());
+        assert_eq!(
+            "f92d74e3874587aaf443d1db961d4e26dde13e9c",
+            format!("{}", hashtest)
+        );
+    }
+}
+
+#[test]
+fn sha1dc_sha_mbles() {
+    let hashtest = sha1dc(&read("sha-mbles-1.bin"), true);
+    assert!(hashtest.collision_detected());
+    assert_eq!(
+        "4f3d9be4a472c4dae83c6314aa6c36a064c1fd14",
+        format!("{}", hashtest)
+    );
+
+    let hashtest = sha1dc(&read("sha-mbles-2.bin"), true);
+    assert!(hashtest.collision_detected());
+    assert_eq!(
+        "9ed5d77a4f48be1dbf3e9e15650733eb850897f2",
+        format!("{}", hashtest)
+    );
+
+    let hashtest = sha1dc(&read("sha-mbles-1.bin"), false);
+    assert_eq!(
+        "8ac60ba76f1999a1ab70223f225aefdc78d4ddc0",
+        format!("{}", hashtest)
+    );
+}
+
+#[test]
+fn sha1dc_no_collision() {
+    // the chosen prefix alone is not a collision
+    let hashtest = sha1dc(&read("sha-mbles-1.bin")[..320], true);
+    assert!(!hashtest.collision_detected());
+
+    let hashtest = sha1dc(b"Hello World!", true);
+    assert!(!hashtest.collision_detected());
+    assert_eq!(
+        "2ef7bde608ce5404e97d5f042f95f89f1c232871",
+        format!("{}", hashtest)
+    );
+}
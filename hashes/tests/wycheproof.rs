@@ -0,0 +1,73 @@
+extern crate hashes;
+
+mod common;
+use common::json::{self, Value};
+use common::wycheproof;
+
+// The files of the Wycheproof repository (github.com/C2SP/wycheproof, testvectors_v1) are run from
+// tests/data/wycheproof: hmac_sha1_test.json, hkdf_sha1_test.json and pbkdf2_hmacsha1_test.json.
+// They are copied there as they are. They are not checked in yet, so the tests are ignored
+// (cargo test -- --ignored run them) and a file which is not there is a failure, not a skip.
+// The edge cases of the crate in the same format are in tests/data/generated and run by tests/hmac.rs.
+
+// upstream function run a file of tests/data/wycheproof
+fn upstream(name: &str, schema: &str, check: fn(&wycheproof::Case) -> bool) {
+    let name = format!("wycheproof/{}", name);
+    assert!(
+        wycheproof::present(&name),
+        "tests/data/{} is not there, copy it from the Wycheproof repository",
+        name
+    );
+    // no policy for the acceptable tests, they are only counted
+    let summary = wycheproof::run(&name, schema, &[], check);
+    println!("{:?}", summary);
+    assert!(summary.valid > 0);
+}
+
+#[test]
+fn wycheproof_json() {
+    let value =
+        json::parse(r#" {"a": [1, -2.5e1, true, false, null], "b": "x\"\\\/\né😀", "c": {}} "#)
+            .unwrap();
+    let a = value.get("a").and_then(Value::as_array).unwrap();
+    assert_eq!(Some(1), a[0].as_u64());
+    assert_eq!(Value::Number(-25.0), a[1]);
+    assert_eq!(Value::Null, a[4]);
+    assert_eq!(Some("x\"\\/\né😀"), value.get("b").and_then(Value::as_str));
+    assert_eq!(Some(&[][..]), value.get("c").and_then(Value::as_object));
+
+    assert!(json::parse("[1, 2").is_err());
+    assert!(json::parse("{\"a\" 1}").is_err());
+    assert!(json::parse("\"a\" 1").is_err());
+    assert!(json::parse("\"\\ud83d\\u0041\"").is_err());
+}
+
+#[test]
+#[ignore = "tests/data/wycheproof/hmac_sha1_test.json is not checked in"]
+fn wycheproof_hmac_sha1() {
+    upstream(
+        "hmac_sha1_test.json",
+        "mac_test_schema.json",
+        wycheproof::hmac_sha1,
+    );
+}
+
+#[test]
+#[ignore = "tests/data/wycheproof/hkdf_sha1_test.json is not checked in"]
+fn wycheproof_hkdf_sha1() {
+    upstream(
+        "hkdf_sha1_test.json",
+        "hkdf_test_schema.json",
+        wycheproof::hkdf_sha1,
+    );
+}
+
+#[test]
+#[ignore = "tests/data/wycheproof/pbkdf2_hmacsha1_test.json is not checked in"]
+fn wycheproof_pbkdf2_hmacsha1() {
+    upstream(
+        "pbkdf2_hmacsha1_test.json",
+        "pbkdf_test_schema.json",
+        wycheproof::pbkdf2_hmacsha1,
+    );
+}